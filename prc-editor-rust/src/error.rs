@@ -0,0 +1,108 @@
+use std::fmt;
+
+/// Errors from reading and writing param files
+#[derive(Debug)]
+pub enum ParamError {
+    FileTooShort { len: usize }, // Smaller than the 0x10 byte header
+    BadMagic { found: String }, // Not a paracobn file
+    InvalidTableSize { table: &'static str, size: i32, available: usize }, // Negative or past the end of the file
+    ByteSwapped { reason: &'static str }, // Header only makes sense read big-endian
+    NoParamData { param_start: usize, len: usize }, // Tables leave no room for the root param
+    UnexpectedType { got: u8, offset: u64 },
+    HashIndexOutOfBounds { index: usize, len: usize },
+    InvalidCount { what: &'static str, count: i32, offset: u64 }, // Negative, or more entries than the rest of the file could hold
+    OffsetOutOfRange { what: &'static str, offset: i64, len: usize }, // Points before the start or past the end of the file
+    NestingTooDeep { offset: u64, max: usize },
+    TooManyParams, // More params than the file has bytes for: offsets loop back or share data
+    HashNotInTable { hash: u64, path: Option<String> }, // A hash used by the tree (at `path`) is missing from the rebuilt hash table
+    StringNotInRefTable { value: String }, // A string used by the tree is missing from the rebuilt ref table
+    OffsetOverflow { what: &'static str, offset: usize, path: Option<String> }, // An offset or size too large for its 32-bit field, written by the node at `path`
+    NoData,
+    VerificationFailed(Box<ParamError>), // Saved file didn't re-open; the original was kept
+    Io(std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, ParamError>;
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamError::FileTooShort { len } => {
+                write!(f, "File is too short to be a param file ({} bytes, header needs 16)", len)
+            }
+            ParamError::BadMagic { found } => {
+                write!(f, "Invalid file format - magic mismatch. Expected 'paracobn', got '{}'", found)
+            }
+            ParamError::InvalidTableSize { table, size, available } => {
+                write!(f, "Invalid {} size {} (only {} bytes follow the header)", table, size, available)
+            }
+            ParamError::ByteSwapped { reason } => {
+                write!(f, "The header {} - this may be a big-endian (byte-swapped) file or not a param file at all. Switch param files are little-endian", reason)
+            }
+            ParamError::NoParamData { param_start, len } => {
+                write!(f, "No param data: it would start at 0x{:X} but the file is only 0x{:X} bytes", param_start, len)
+            }
+            ParamError::UnexpectedType { got, offset } => {
+                write!(f, "Unexpected param type {} at offset 0x{:X}", got, offset)
+            }
+            ParamError::HashIndexOutOfBounds { index, len } => {
+                write!(f, "Hash index {} out of bounds (table size: {})", index, len)
+            }
+            ParamError::InvalidCount { what, count, offset } => {
+                write!(f, "Invalid {} entry count {} at offset 0x{:X}", what, count, offset)
+            }
+            ParamError::OffsetOutOfRange { what, offset, len } => {
+                write!(f, "{} offset 0x{:X} is outside the file (0x{:X} bytes)", what, offset, len)
+            }
+            ParamError::NestingTooDeep { offset, max } => {
+                write!(f, "Params nested more than {} levels deep at offset 0x{:X}", max, offset)
+            }
+            ParamError::TooManyParams => write!(f, "File references more params than it can contain (offsets loop or overlap)"),
+            ParamError::HashNotInTable { hash, path: Some(path) } => {
+                write!(f, "Hash 0x{:X} used at {} is missing from the rebuilt hash table", hash, path)
+            }
+            ParamError::HashNotInTable { hash, path: None } => write!(f, "Hash 0x{:X} not found in hash table", hash),
+            ParamError::StringNotInRefTable { value } => write!(f, "String {:?} not found in reference table", value),
+            ParamError::OffsetOverflow { what, offset, path: Some(path) } => {
+                write!(f, "File too large: {} 0x{:X} at {} doesn't fit in its 32-bit field", what, offset, path)
+            }
+            ParamError::OffsetOverflow { what, offset, path: None } => {
+                write!(f, "File too large: {} 0x{:X} doesn't fit in its 32-bit field", what, offset)
+            }
+            ParamError::NoData => write!(f, "No param data loaded"),
+            ParamError::VerificationFailed(e) => write!(f, "Verification failed, original file kept: {}", e),
+            ParamError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParamError::VerificationFailed(e) => Some(e.as_ref()),
+            ParamError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl ParamError {
+    /// Prefix the node path of an offset overflow with the child index it was reached
+    /// through, so the error names the node once it has propagated up to the root
+    pub fn within(self, index: usize) -> Self {
+        match self {
+            ParamError::OffsetOverflow { what, offset, path } => ParamError::OffsetOverflow {
+                what,
+                offset,
+                path: Some(format!("[{}]{}", index, path.unwrap_or_default())),
+            },
+            e => e,
+        }
+    }
+}
+
+impl From<std::io::Error> for ParamError {
+    fn from(e: std::io::Error) -> Self {
+        ParamError::Io(e)
+    }
+}
@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use anyhow::Result;
+use indexmap::IndexMap;
 
 // CRC32 table from paracobNET
 const CRC32_TABLE: [u32; 256] = [
@@ -45,76 +46,220 @@ fn crc32(word: &str) -> u32 {
     !hash
 }
 
+/// Number of malformed lines / conflicting hashes kept as examples in a load report
+const REPORT_SAMPLE_SIZE: usize = 5;
+
+/// Words common in Smash param names, tried alongside the words of nearby labels
+const COMMON_LABEL_WORDS: &[&str] = &[
+    "min", "max", "x", "y", "z", "num", "count", "frame", "speed", "rate", "angle", "id", "kind", "type",
+    "flag", "start", "end", "offset", "size", "scale", "time", "range", "power", "damage", "pos", "dir",
+    "lr", "rot", "add", "mul", "ratio", "limit", "init", "base", "life", "interval", "length", "width",
+    "height", "accel", "brake", "weight", "air", "ground", "attack", "jump", "enable", "is", "no", "l", "r",
+];
+
+/// Highest number tried when continuing a numbered label such as `param_01`
+const MAX_SUGGESTED_NUMBER: usize = 999;
+
+/// Outcome of loading a labels CSV
+#[derive(Debug, Default)]
+pub struct LabelLoadReport {
+    pub added: usize,
+    pub skipped: usize,
+    pub bad_lines: Vec<String>, // First few malformed lines as "line N: text"
+    pub duplicate_hashes: usize, // Hashes listed more than once with different labels (last one wins)
+    pub duplicate_samples: Vec<String>,
+}
+
+impl LabelLoadReport {
+    /// One-line summary suitable for the status bar
+    pub fn summary(&self) -> String {
+        let mut summary = format!("loaded {} labels", self.added);
+        if self.skipped > 0 {
+            summary.push_str(&format!(", skipped {} malformed rows ({})", self.skipped, self.bad_lines.join("; ")));
+        }
+        if self.duplicate_hashes > 0 {
+            summary.push_str(&format!(", {} hashes with conflicting labels ({})", self.duplicate_hashes, self.duplicate_samples.join("; ")));
+        }
+        summary
+    }
+
+    fn record_bad_line(&mut self, line: u64, text: &str) {
+        self.skipped += 1;
+        if self.bad_lines.len() < REPORT_SAMPLE_SIZE {
+            self.bad_lines.push(format!("line {}: {}", line, text));
+        }
+    }
+}
+
+/// Row order used when writing a labels CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelSortOrder {
+    Hash,
+    Label,
+    Insertion, // Order labels were loaded or added in
+}
+
+/// A hash's label and where it sits, so undo and redo can put it back exactly
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelState {
+    pub label: Option<String>,
+    position: usize, // Index in the label order, which Insertion order saves in
+    session_position: Option<usize>, // Index among the session's additions, when added by hand
+}
+
 pub struct HashLabels {
-    labels: HashMap<u64, String>,
+    labels: IndexMap<u64, String>, // Keeps insertion order for LabelSortOrder::Insertion
     reverse_labels: HashMap<String, u64>,
+    session_added: IndexMap<u64, String>, // Labels added or changed by hand since these labels were loaded
+    mismatched: HashSet<u64>, // Hashes whose label text hashes to something else (overrides or mistakes)
+    pub csv_sort_order: LabelSortOrder,
+    save_failure: Option<String>, // Why the last save_to_csv failed, until it is taken for reporting
 }
 
 impl HashLabels {
     pub fn new() -> Self {
         Self {
-            labels: HashMap::new(),
+            labels: IndexMap::new(),
             reverse_labels: HashMap::new(),
+            session_added: IndexMap::new(),
+            mismatched: HashSet::new(),
+            csv_sort_order: LabelSortOrder::Hash,
+            save_failure: None,
+        }
+    }
+
+    pub fn load_from_csv(&mut self, csv_content: &str) -> Result<LabelLoadReport> {
+        let mut report = LabelLoadReport::default();
+        let entries = Self::parse_csv_entries(csv_content, &mut report)?;
+        
+        // Flag hashes that the CSV itself maps to more than one label
+        let mut seen: HashMap<u64, &str> = HashMap::new();
+        for (hash, label) in &entries {
+            if let Some(previous) = seen.insert(*hash, label) {
+                if previous != label {
+                    report.duplicate_hashes += 1;
+                    if report.duplicate_samples.len() < REPORT_SAMPLE_SIZE {
+                        report.duplicate_samples.push(format!("0x{:X}: {} / {}", hash, previous, label));
+                    }
+                }
+            }
         }
+        
+        report.added = entries.len();
+        for (hash, label) in entries {
+            self.insert_label(hash, label);
+        }
+        
+        Ok(report)
     }
 
-    pub fn load_from_csv(&mut self, csv_content: &str) -> Result<usize> {
+    /// Merge labels from a CSV, keeping only entries whose hash is in `wanted`
+    pub fn import_from_csv_filtered(&mut self, csv_content: &str, wanted: &HashSet<u64>) -> Result<usize> {
+        let entries = Self::parse_csv_entries(csv_content, &mut LabelLoadReport::default())?;
+        let mut count = 0;
+        
+        for (hash, label) in entries {
+            if wanted.contains(&hash) {
+                self.insert_label(hash, label);
+                count += 1;
+            }
+        }
+        
+        Ok(count)
+    }
+
+    /// Parse `hash,label` records from CSV content, recording malformed rows in `report`
+    fn parse_csv_entries(csv_content: &str, report: &mut LabelLoadReport) -> Result<Vec<(u64, String)>> {
         let mut reader = csv::ReaderBuilder::new()
             .has_headers(false)
             .flexible(true) // Allow records with varying number of fields
             .from_reader(csv_content.as_bytes());
 
-        let mut count = 0;
+        let mut entries = Vec::new();
         
         for result in reader.records() {
-            let record = result?;
+            let record = match result {
+                Ok(record) => record,
+                Err(e) => {
+                    let line = e.position().map(|p| p.line()).unwrap_or(0);
+                    report.record_bad_line(line, &e.to_string());
+                    continue;
+                }
+            };
+            let line = record.position().map(|p| p.line()).unwrap_or(0);
+            
             // Only process records with exactly 2 fields
-            if record.len() == 2 {
-                if let (Some(hash_str), Some(label)) = (record.get(0), record.get(1)) {
-                    // Normalize the hash string by removing leading zeros after 0x
-                    let normalized_hash_str = if hash_str.starts_with("0x") || hash_str.starts_with("0X") {
-                        let hex_part = &hash_str[2..];
-                        // Remove leading zeros but keep at least one digit
-                        let trimmed = hex_part.trim_start_matches('0');
-                        if trimmed.is_empty() {
-                            "0x0".to_string()
-                        } else {
-                            format!("0x{}", trimmed)
-                        }
-                    } else {
-                        hash_str.to_string()
-                    };
-                    
-                    if let Ok(hash) = u64::from_str_radix(normalized_hash_str.trim_start_matches("0x"), 16) {
-                        self.labels.insert(hash, label.to_string());
-                        self.reverse_labels.insert(label.to_string(), hash);
-                        count += 1;
-                    }
-                    // Silently skip invalid hash formats
+            if record.len() != 2 {
+                if record.len() > 1 || !record.get(0).unwrap_or("").trim().is_empty() {
+                    report.record_bad_line(line, &record.iter().collect::<Vec<_>>().join(","));
+                }
+                continue;
+            }
+            
+            let (hash_str, label) = (record[0].trim(), record[1].trim());
+            // Normalize the hash string by removing leading zeros after 0x
+            let normalized_hash_str = if hash_str.starts_with("0x") || hash_str.starts_with("0X") {
+                let hex_part = &hash_str[2..];
+                // Remove leading zeros but keep at least one digit
+                let trimmed = hex_part.trim_start_matches('0');
+                if trimmed.is_empty() {
+                    "0x0".to_string()
+                } else {
+                    format!("0x{}", trimmed)
                 }
+            } else {
+                hash_str.to_string()
+            };
+            
+            match u64::from_str_radix(normalized_hash_str.trim_start_matches("0x"), 16) {
+                Ok(hash) if !label.is_empty() => entries.push((hash, label.to_string())),
+                _ => report.record_bad_line(line, &format!("{},{}", hash_str, label)),
             }
-            // Silently skip malformed records
         }
         
-        Ok(count)
+        Ok(entries)
+    }
+
+    /// Parse `old_label,new_label` records from a rename mapping CSV, skipping an optional header row
+    pub fn parse_rename_mapping(csv_content: &str) -> Result<Vec<(String, String)>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(csv_content.as_bytes());
+
+        let mut mapping = Vec::new();
+        
+        for result in reader.records() {
+            let record = result?;
+            if let (Some(old_label), Some(new_label)) = (record.get(0), record.get(1)) {
+                let (old_label, new_label) = (old_label.trim(), new_label.trim());
+                if old_label.is_empty() || new_label.is_empty() || old_label == "old_label" {
+                    continue;
+                }
+                mapping.push((old_label.to_string(), new_label.to_string()));
+            }
+        }
+        
+        Ok(mapping)
     }
 
     pub fn get_label(&self, hash: u64) -> Option<&String> {
         self.labels.get(&hash)
     }
 
+    /// Whether the hash has an exact label (ignoring the masked fallbacks in `hash_to_string`)
+    pub fn has_exact_label(&self, hash: u64) -> bool {
+        self.labels.contains_key(&hash)
+    }
+
     #[allow(dead_code)]
     pub fn get_hash(&self, label: &str) -> Option<u64> {
         self.reverse_labels.get(label).copied()
     }
 
-    pub fn hash_to_string(&self, hash: u64) -> String {
-        // First try direct lookup like the original prcEditor
-        if let Some(label) = self.get_label(hash) {
-            return label.clone();
-        }
-
-        // If direct lookup fails, try different masking approaches for compatibility
+    /// First masked or sign-flipped variant of `hash` that has a label, with that label. Only
+    /// meaningful when `hash` itself has no exact label.
+    pub fn masked_label(&self, hash: u64) -> Option<(u64, &String)> {
         let variants = [
             hash & 0x00FFFFFFFFFFFFFF,             // 56-bit mask (remove top 8 bits)
             hash & 0x0000FFFFFFFFFFFF,             // 48-bit mask (remove top 16 bits)
@@ -126,10 +271,18 @@ impl HashLabels {
             hash | 0x8000000000000000,             // Set sign bit
         ];
 
-        for variant in variants {
-            if let Some(label) = self.get_label(variant) {
-                return label.clone();
-            }
+        variants.into_iter().find_map(|variant| self.get_label(variant).map(|label| (variant, label)))
+    }
+
+    pub fn hash_to_string(&self, hash: u64) -> String {
+        // First try direct lookup like the original prcEditor
+        if let Some(label) = self.get_label(hash) {
+            return label.clone();
+        }
+
+        // If direct lookup fails, try different masking approaches for compatibility
+        if let Some((_, label)) = self.masked_label(hash) {
+            return label.clone();
         }
 
         // If no label found, return hex representation
@@ -140,24 +293,26 @@ impl HashLabels {
         self.labels.len()
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.labels.is_empty()
-    }
-
     #[allow(dead_code)]
-    pub fn get_all_labels(&self) -> &HashMap<u64, String> {
+    pub fn get_all_labels(&self) -> &IndexMap<u64, String> {
         &self.labels
     }
     
-    pub fn get_labels_filtered(&self, filter: &str) -> Vec<(u64, &String)> {
+    /// Labels whose text or hex hash contains `filter`. Hex digits always match
+    /// case-insensitively; `case_sensitive` only affects the label text.
+    pub fn get_labels_filtered(&self, filter: &str, case_sensitive: bool) -> Vec<(u64, &String)> {
         if filter.is_empty() {
             self.labels.iter().map(|(k, v)| (*k, v)).collect()
         } else {
             let filter_lower = filter.to_lowercase();
             self.labels.iter()
                 .filter(|(hash, label)| {
-                    label.to_lowercase().contains(&filter_lower) ||
-                    format!("{:X}", hash).to_lowercase().contains(&filter_lower)
+                    let label_matches = if case_sensitive {
+                        label.contains(filter)
+                    } else {
+                        label.to_lowercase().contains(&filter_lower)
+                    };
+                    label_matches || format!("{:X}", hash).to_lowercase().contains(&filter_lower)
                 })
                 .map(|(k, v)| (*k, v))
                 .collect()
@@ -172,24 +327,98 @@ impl HashLabels {
         (length << 32) | crc
     }
 
+    /// Guess labels for `hash` from nearby labels (`context`): numbered names continued
+    /// (`param_00`, `param_01` -> `param_02`), words of one label swapped for words of another
+    /// or a common param word, and a word appended. Hash40 stores the string length, so only
+    /// candidates of that length are hashed.
+    pub fn suggest_labels(&self, hash: u64, context: &[&str]) -> Vec<String> {
+        let target_len = (hash >> 32) as usize;
+        let mut suggestions = Vec::new();
+        let mut try_candidate = |candidate: String| {
+            if candidate.len() == target_len && self.string_to_hash40(&candidate) == hash && !suggestions.contains(&candidate) {
+                suggestions.push(candidate);
+            }
+        };
+        
+        let mut words: Vec<&str> = COMMON_LABEL_WORDS.to_vec();
+        for label in context {
+            for word in label.split('_').filter(|word| !word.is_empty()) {
+                if !words.contains(&word) {
+                    words.push(word);
+                }
+            }
+        }
+        
+        for label in context {
+            // Each run of digits, renumbered at its own width and unpadded
+            let mut run_start = None;
+            for (i, c) in label.char_indices().chain(std::iter::once((label.len(), ' '))) {
+                match (c.is_ascii_digit(), run_start) {
+                    (true, None) => run_start = Some(i),
+                    (false, Some(start)) => {
+                        let width = i - start;
+                        for n in 0..=MAX_SUGGESTED_NUMBER {
+                            try_candidate(format!("{}{:0width$}{}", &label[..start], n, &label[i..], width = width));
+                            try_candidate(format!("{}{}{}", &label[..start], n, &label[i..]));
+                        }
+                        run_start = None;
+                    }
+                    _ => {}
+                }
+            }
+            
+            let parts: Vec<&str> = label.split('_').collect();
+            for position in 0..parts.len() {
+                for word in &words {
+                    let mut swapped = parts.clone();
+                    swapped[position] = word;
+                    try_candidate(swapped.join("_"));
+                }
+            }
+            for word in &words {
+                try_candidate(format!("{}_{}", label, word));
+                try_candidate(format!("{}_{}", word, label));
+            }
+        }
+        
+        suggestions
+    }
+
     /// Add a new label and automatically generate its hash
     pub fn add_label(&mut self, label: &str) -> u64 {
         let hash = self.string_to_hash40(label);
-        self.labels.insert(hash, label.to_string());
-        self.reverse_labels.insert(label.to_string(), hash);
+        self.add_label_for_hash(hash, label);
         hash
     }
 
-    /// Save all labels to a CSV file
-    pub fn save_to_csv(&self, file_path: &str) -> Result<()> {
+    /// Save all labels to a CSV file. A failure is also kept for `take_save_failure`, so
+    /// saves whose result is ignored (like `add_label_and_save`) still get reported.
+    pub fn save_to_csv(&mut self, file_path: &str) -> Result<()> {
+        let result = self.write_csv(file_path);
+        if let Err(e) = &result {
+            self.save_failure = Some(e.to_string());
+        }
+        result
+    }
+
+    /// Why the last failed save failed, once
+    pub fn take_save_failure(&mut self) -> Option<String> {
+        self.save_failure.take()
+    }
+
+    fn write_csv(&self, file_path: &str) -> Result<()> {
         use std::fs::File;
         use std::io::Write;
         
         let mut file = File::create(file_path)?;
         
-        // Sort by hash for consistent output
+        // Every order is deterministic so the file doesn't churn between runs
         let mut sorted_labels: Vec<_> = self.labels.iter().collect();
-        sorted_labels.sort_by_key(|(hash, _)| *hash);
+        match self.csv_sort_order {
+            LabelSortOrder::Hash => sorted_labels.sort_by_key(|(hash, _)| *hash),
+            LabelSortOrder::Label => sorted_labels.sort_by(|a, b| a.1.cmp(b.1).then(a.0.cmp(b.0))),
+            LabelSortOrder::Insertion => {}
+        }
         
         for (hash, label) in sorted_labels {
             writeln!(file, "0x{:X},{}", hash, label)?;
@@ -210,7 +439,6 @@ impl HashLabels {
     }
 
     /// Try to parse a string as either a hex hash or a label name
-    #[allow(dead_code)]
     pub fn parse_hash_or_label(&self, input: &str) -> Result<u64, String> {
         // Try to parse as hex first
         if input.starts_with("0x") {
@@ -231,19 +459,72 @@ impl HashLabels {
 
     /// Add a label for an existing hash value
     pub fn add_label_for_hash(&mut self, hash: u64, label: &str) {
-        self.labels.insert(hash, label.to_string());
-        self.reverse_labels.insert(label.to_string(), hash);
+        if self.labels.get(&hash).map(String::as_str) != Some(label) {
+            self.session_added.insert(hash, label.to_string());
+        }
+        self.insert_label(hash, label.to_string());
     }
 
-    /// Add a label for an existing hash and save to CSV
-    pub fn add_label_for_hash_and_save(&mut self, hash: u64, label: &str, csv_path: Option<&str>) -> Result<()> {
-        self.add_label_for_hash(hash, label);
-        
-        if let Some(path) = csv_path {
-            self.save_to_csv(path)?;
+    /// Current label state of `hash`, for restore_label to return to
+    pub fn label_state(&self, hash: u64) -> LabelState {
+        LabelState {
+            label: self.labels.get(&hash).cloned(),
+            position: self.labels.get_index_of(&hash).unwrap_or(self.labels.len()),
+            session_position: self.session_added.get_index_of(&hash),
+        }
+    }
+    
+    /// Remove the label for `hash`, leaving it unlabeled
+    pub fn remove_label(&mut self, hash: u64) {
+        if let Some(old_label) = self.labels.shift_remove(&hash) {
+            if self.reverse_labels.get(&old_label) == Some(&hash) {
+                self.reverse_labels.remove(&old_label);
+            }
         }
+        self.session_added.shift_remove(&hash);
+        self.mismatched.remove(&hash);
+    }
+    
+    /// Put `hash` back to a state from label_state: the same label at the same place in the
+    /// label order, and a session addition only if it was one. Used by undo/redo.
+    pub fn restore_label(&mut self, hash: u64, state: &LabelState) {
+        self.remove_label(hash);
+        let Some(label) = &state.label else {
+            return;
+        };
         
-        Ok(())
+        self.insert_label(hash, label.clone());
+        self.labels.move_index(self.labels.len() - 1, state.position.min(self.labels.len() - 1));
+        if let Some(position) = state.session_position {
+            self.session_added.insert(hash, label.clone());
+            self.session_added.move_index(self.session_added.len() - 1, position.min(self.session_added.len() - 1));
+        }
+    }
+    
+    fn insert_label(&mut self, hash: u64, label: String) {
+        if self.string_to_hash40(&label) == hash {
+            self.mismatched.remove(&hash);
+        } else {
+            self.mismatched.insert(hash);
+        }
+        self.reverse_labels.insert(label.clone(), hash);
+        self.labels.insert(hash, label);
+    }
+    
+    /// Whether the label for `hash` hashes to a different value. Usually a typo, though some
+    /// CSVs deliberately name hashes whose source string isn't known.
+    pub fn is_mismatched(&self, hash: u64) -> bool {
+        self.mismatched.contains(&hash)
+    }
+    
+    /// Number of labels whose text doesn't hash to their key
+    pub fn mismatched_count(&self) -> usize {
+        self.mismatched.len()
+    }
+
+    /// Labels added or changed by hand this session, in the order they were added
+    pub fn session_additions(&self) -> &IndexMap<u64, String> {
+        &self.session_added
     }
 }
 
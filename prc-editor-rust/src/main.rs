@@ -1,10 +1,16 @@
+mod error;
 mod param_file;
 mod param_types;
 mod hash_labels;
+mod param_descriptions;
+mod param_ranges;
+mod node_notes;
 
 // Desktop version with GUI
 #[cfg(not(target_os = "horizon"))]
 mod ui;
+#[cfg(not(target_os = "horizon"))]
+mod shortcuts;
 
 #[cfg(not(target_os = "horizon"))]
 use eframe::egui;
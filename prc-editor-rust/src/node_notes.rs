@@ -0,0 +1,92 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+
+/// File next to the preferences that notes are kept in between sessions
+pub const NOTES_FILE_NAME: &str = "prc_editor_notes.csv";
+
+/// Review notes on nodes of the open file ("check this", "TODO: verify value"), keyed by
+/// label-path so they find their node again after the file is reopened. Only metadata;
+/// nothing here is written to the param file.
+#[derive(Default)]
+pub struct NodeNotes {
+    notes: IndexMap<String, String>, // Label-path -> note, in the order they were added
+    revision: u64, // Bumped on every change so node paths resolved from the notes can be cached
+}
+
+impl NodeNotes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Notes saved for `file` in a `file,label_path,note` CSV
+    pub fn load_for_file(csv_content: &str, file: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(csv_content.as_bytes());
+
+        let mut notes = Self::new();
+        for result in reader.records() {
+            let record = result?;
+            if let (Some(row_file), Some(label_path), Some(text)) = (record.get(0), record.get(1), record.get(2)) {
+                if row_file == file {
+                    notes.set(label_path, text);
+                }
+            }
+        }
+        Ok(notes)
+    }
+
+    /// `csv_content` (in the `load_for_file` layout) with the rows for `file` replaced by
+    /// these notes; other files' notes are kept as they were
+    pub fn replace_in_csv(&self, csv_content: &str, file: &str) -> Result<String> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(csv_content.as_bytes());
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_writer(Vec::new());
+
+        for result in reader.records() {
+            let record = result?;
+            if record.get(0) != Some(file) {
+                writer.write_record(&record)?;
+            }
+        }
+        for (label_path, text) in &self.notes {
+            writer.write_record([file, label_path.as_str(), text.as_str()])?;
+        }
+
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    pub fn get(&self, label_path: &str) -> Option<&str> {
+        self.notes.get(label_path).map(String::as_str)
+    }
+
+    /// Set the note at `label_path`; blank text removes it
+    pub fn set(&mut self, label_path: &str, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.notes.shift_remove(label_path);
+        } else {
+            self.notes.insert(label_path.to_string(), text.to_string());
+        }
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    /// (label-path, note) pairs in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.notes.iter().map(|(label_path, text)| (label_path.as_str(), text.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use indexmap::IndexMap;
+use crate::hash_labels::HashLabels;
+
+/// File looked for next to ParamLabels.csv
+pub const DESCRIPTIONS_FILE_NAME: &str = "ParamDescriptions.csv";
+
+/// Notes on what struct fields do, keyed by the field's hash
+#[derive(Default)]
+pub struct ParamDescriptions {
+    descriptions: IndexMap<u64, String>,
+}
+
+impl ParamDescriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge `key,description` rows into the current set, replacing descriptions of the same
+    /// field. The key is a 0x hash or a label; unquoted commas after it stay in the text.
+    /// Returns the number of descriptions read.
+    pub fn merge_from_csv(&mut self, csv_content: &str, labels: &HashLabels) -> Result<usize> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(csv_content.as_bytes());
+
+        let mut count = 0;
+        for result in reader.records() {
+            let record = result?;
+            let key = record.get(0).unwrap_or("").trim();
+            let text = record.iter().skip(1).collect::<Vec<_>>().join(",");
+            let text = text.trim();
+            if key.is_empty() || text.is_empty() {
+                continue;
+            }
+
+            let hash = labels.parse_hash_or_label(key).unwrap_or_else(|_| labels.string_to_hash40(key));
+            self.descriptions.insert(hash, text.to_string());
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    pub fn get(&self, hash: u64) -> Option<&str> {
+        self.descriptions.get(&hash).map(String::as_str)
+    }
+
+    /// Struct editor section named by a description starting with `[Category]`
+    pub fn category(&self, hash: u64) -> Option<&str> {
+        let (category, _) = self.get(hash)?.strip_prefix('[')?.split_once(']')?;
+        Some(category.trim()).filter(|category| !category.is_empty())
+    }
+    
+    /// Set the description for `hash`; blank text removes it
+    pub fn set(&mut self, hash: u64, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.descriptions.shift_remove(&hash);
+        } else {
+            self.descriptions.insert(hash, text.to_string());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.descriptions.len()
+    }
+
+    /// Write every description sorted by hash, keyed by label where the hash has one
+    pub fn save_to_csv(&self, file_path: &str, labels: &HashLabels) -> Result<()> {
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_path(file_path)?;
+
+        let mut sorted: Vec<_> = self.descriptions.iter().collect();
+        sorted.sort_by_key(|(hash, _)| **hash);
+        for (hash, text) in sorted {
+            let key = match labels.get_label(*hash) {
+                Some(label) if labels.has_exact_label(*hash) => label.clone(),
+                _ => format!("0x{:X}", hash),
+            };
+            writer.write_record([key.as_str(), text.as_str()])?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
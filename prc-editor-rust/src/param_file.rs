@@ -1,23 +1,201 @@
 use crate::param_types::*;
 use crate::hash_labels::HashLabels;
-use anyhow::{Result, anyhow};
+use crate::error::{ParamError, Result};
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::cell::OnceCell;
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
 
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+enum LabelSegment {
+    Key(u64),
+    Index(usize),
+}
 
 // Helper enum for reference entries (mimicking paracobNET's mixed list)
 enum RefEntry {
-    String(String),
+    String(Vec<u8>), // The string's bytes, without the terminator
     StructRef(Vec<(i32, i32)>), // (hash_index, param_offset) pairs
 }
 
+// What `open` tracks while reading params, kept apart from the open file until the read succeeds
+struct ReadState {
+    params_left: usize, // Params the remaining data could still hold
+    param_end: u64, // Furthest byte of param data read so far
+    unknown_types: Vec<(u8, u64)>, // (type byte, file offset) of each param read with the unknown-type fallback
+}
+
+/// Structs that share one set of field keys
+pub struct StructLayout {
+    pub fields: Vec<u64>, // Field keys in the order of the first instance found
+    pub count: usize,
+    pub sample_paths: Vec<String>,
+}
+
+impl StructLayout {
+    /// Upper bound on the reference table bytes saved by merging this layout's duplicate
+    /// entries (8 bytes per field for every instance after the first). Instances only
+    /// merge when their fields are also in the same order with the same types.
+    pub fn max_dedup_savings(&self) -> usize {
+        self.count.saturating_sub(1) * self.fields.len() * 8
+    }
+}
+
+/// Reapplyable difference between two files. Entries are keyed by label-path (see
+/// `ParamFile::walk_leaves`) so a patch applies to any file with the same layout.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ParamPatch {
+    pub set: IndexMap<String, ParamValue>, // Changed values, plus fields and list items to add
+    pub removed: Vec<String>, // Removed fields and list items, in the order to remove them
+}
+
+impl ParamPatch {
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(text: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(text)
+    }
+}
+
+/// Where a patch label-path lands in a file
+pub enum PatchTarget {
+    Existing(String), // Node path of the value at the label-path
+    Append { parent: String, hash: u64 }, // Missing last segment: a new field (by key hash) or the next list item
+}
+
+/// Every struct key and hash40 value in the tree with the node path using it, built once per
+/// revision so lookups by hash don't walk the whole tree each time
+#[derive(Default)]
+pub struct HashIndex {
+    uses: Vec<(u64, String)>, // (hash, node path) in tree order; a field with a hash40 value appears twice
+    by_hash: IndexMap<u64, Vec<usize>>, // Positions in `uses` per hash, hashes in order of first use
+}
+
+impl HashIndex {
+    fn build(root: &ParamValue) -> Self {
+        let mut index = Self::default();
+        index.add(root, &mut "root".to_string(), None);
+        index
+    }
+    
+    fn add(&mut self, value: &ParamValue, path: &mut String, key: Option<u64>) {
+        if let Some(key) = key {
+            self.push(key, path);
+        }
+        let len = path.len();
+        match value {
+            ParamValue::Struct(s) => {
+                for (i, (field_hash, field_value)) in s.fields.iter().enumerate() {
+                    path.push_str(&format!("[{}]", i));
+                    self.add(field_value, path, Some(*field_hash));
+                    path.truncate(len);
+                }
+            }
+            ParamValue::List(l) => {
+                for (i, item) in l.values.iter().enumerate() {
+                    path.push_str(&format!("[{}]", i));
+                    self.add(item, path, None);
+                    path.truncate(len);
+                }
+            }
+            ParamValue::Hash(hash) => self.push(*hash, path),
+            _ => {}
+        }
+    }
+    
+    fn push(&mut self, hash: u64, path: &str) {
+        self.by_hash.entry(hash).or_default().push(self.uses.len());
+        self.uses.push((hash, path.to_string()));
+    }
+    
+    /// Paths of the nodes using `hash` as their key or value, in tree order
+    pub fn paths_using(&self, hash: u64) -> impl Iterator<Item = &str> {
+        self.by_hash.get(&hash).into_iter().flatten().map(|&i| self.uses[i].1.as_str())
+    }
+    
+    /// Each distinct hash, in order of first use
+    pub fn hashes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.by_hash.keys().copied()
+    }
+    
+    /// Every (hash, node path) use in tree order
+    pub fn uses(&self) -> impl Iterator<Item = (u64, &str)> {
+        self.uses.iter().map(|(hash, path)| (*hash, path.as_str()))
+    }
+}
+
+/// What saving the current tree will do relative to the file as it was opened. Saves always
+/// rebuild the hash table in paracobNET's traversal order and write struct fields sorted by hash.
+#[derive(Clone, Copy, PartialEq)]
+pub struct SaveOrdering {
+    pub hash_table_kept: bool, // The rebuilt hash table has the same entries in the same order as the original
+    pub byte_identical: bool, // The serialized output equals the opened bytes exactly
+}
+
+/// Bytes some tools leave after the param data, which a rebuilt file wouldn't have
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrailingBytes {
+    Alignment { align: usize, len: usize }, // Zero padding that ends the file on a multiple of `align`
+    Raw(Vec<u8>), // Anything else, written back as is
+}
+
+impl TrailingBytes {
+    /// What follows the param data ending at `parsed_end`; None when the file ends there
+    fn detect(data: &[u8], parsed_end: usize) -> Option<TrailingBytes> {
+        let trailing = data.get(parsed_end..).filter(|trailing| !trailing.is_empty())?;
+        if trailing.iter().all(|&byte| byte == 0) {
+            // The largest power of two (4 to 4096) the padding rounds the file up to
+            let align = (2..=12).rev().map(|shift| 1usize << shift).find(|&align| parsed_end.next_multiple_of(align) == data.len());
+            if let Some(align) = align {
+                return Some(TrailingBytes::Alignment { align, len: trailing.len() });
+            }
+        }
+        Some(TrailingBytes::Raw(trailing.to_vec()))
+    }
+    
+    /// Append the padding or bytes to a serialized file
+    fn apply(&self, output: &mut Vec<u8>) {
+        match self {
+            TrailingBytes::Alignment { align, .. } => output.resize(output.len().next_multiple_of(*align), 0),
+            TrailingBytes::Raw(bytes) => output.extend_from_slice(bytes),
+        }
+    }
+}
+
+impl std::fmt::Display for TrailingBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrailingBytes::Alignment { align, len } => write!(f, "{} zero bytes padding to a multiple of {}", len, align),
+            TrailingBytes::Raw(bytes) => write!(f, "{} unreferenced bytes after the param data", bytes.len()),
+        }
+    }
+}
+
+/// Deepest struct/list nesting `open` accepts; real files stay far below this
+const MAX_PARAM_DEPTH: usize = 128;
+
 pub struct ParamFile {
     pub root: Option<ParamNode>,
     pub hash_labels: HashLabels,
     filename: String,
     original_hash_table: Vec<u64>,
+    original_digest: Option<(usize, u64)>, // Length and hash of the bytes last opened, for round-trip checks
+    original_root: Option<ParamValue>, // Tree as last opened, for exporting what changed since
+    revision: u64, // Bumped whenever the tree changes so UI caches can tell they are stale
+    label_revision: u64, // Bumped whenever labels change, for caches of node names rather than of the tree
+    hash_index: OnceCell<HashIndex>, // Built on first use after each change
+    unknown_types: Vec<(u8, u64)>, // (type byte, file offset) of each param read with the unknown-type fallback
+    trailing: Option<TrailingBytes>, // What followed the param data in the file last opened
+    pub keep_trailing_bytes: bool, // Reproduce `trailing` when saving
 }
 
 impl ParamFile {
@@ -27,30 +205,102 @@ impl ParamFile {
             hash_labels: HashLabels::new(),
             filename: String::new(),
             original_hash_table: Vec::new(),
+            original_digest: None,
+            original_root: None,
+            revision: 0,
+            label_revision: 0,
+            hash_index: OnceCell::new(),
+            unknown_types: Vec::new(),
+            trailing: None,
+            keep_trailing_bytes: true,
         }
     }
 
+    /// Counter that changes whenever the tree is modified
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Counter that changes whenever labels are added, edited or reloaded
+    pub fn label_revision(&self) -> u64 {
+        self.label_revision
+    }
+
+    /// Record that the tree changed outside of the ParamFile update methods
+    pub fn mark_modified(&mut self) {
+        self.revision = self.revision.wrapping_add(1);
+        self.hash_index = OnceCell::new();
+    }
+    
+    /// Where each hash is used, cached until the next `mark_modified`
+    pub fn hash_index(&self) -> &HashIndex {
+        self.hash_index.get_or_init(|| match self.get_root() {
+            Some(root) => HashIndex::build(&root.value),
+            None => HashIndex::default(),
+        })
+    }
+
+    /// First place the cached hash index disagrees with the tree, if it has been built. It is
+    /// only dropped by `mark_modified`, so an edit made on `root` without it leaves the index
+    /// describing the old tree.
+    pub fn hash_index_divergence(&self) -> Option<String> {
+        let cached = self.hash_index.get()?;
+        let fresh = match self.get_root() {
+            Some(root) => HashIndex::build(&root.value),
+            None => HashIndex::default(),
+        };
+        
+        let mismatch = cached.uses.iter().zip(&fresh.uses).find(|(cached_use, fresh_use)| cached_use != fresh_use);
+        if let Some(((cached_hash, cached_path), (hash, path))) = mismatch {
+            return Some(format!("{}: hash index has 0x{:X} at {}, tree has 0x{:X}", path, cached_hash, cached_path, hash));
+        }
+        (cached.uses.len() != fresh.uses.len())
+            .then(|| format!("hash index has {} hash uses, tree has {}", cached.uses.len(), fresh.uses.len()))
+    }
+
     pub fn open(&mut self, data: &[u8], filename: &str) -> Result<()> {
-        self.filename = filename.to_string();
         let mut cursor = Cursor::new(data);
 
+        // Header: 8 byte magic and two i32 table sizes
+        const HEADER_SIZE: usize = 0x10;
+        if data.len() < HEADER_SIZE {
+            return Err(ParamError::FileTooShort { len: data.len() });
+        }
+
         // Validate magic - first 8 bytes should be "paracobn"
         let mut magic = [0u8; 8];
         cursor.read_exact(&mut magic)?;
-        let magic_str = std::str::from_utf8(&magic).map_err(|_| anyhow!("Invalid magic header"))?;
-        
-        if magic_str != "paracobn" {
-            return Err(anyhow!("Invalid file format - magic mismatch. Expected 'paracobn', got '{}'", magic_str));
+        if &magic != b"paracobn" {
+            if magic.iter().rev().eq(b"paracobn") {
+                return Err(ParamError::ByteSwapped { reason: "magic is 'paracobn' reversed" });
+            }
+            return Err(ParamError::BadMagic { found: String::from_utf8_lossy(&magic).to_string() });
         }
 
-        // Read sizes
+        // Read sizes, checking both tables fit in the file before any offset math
         let hash_table_size = cursor.read_i32::<LittleEndian>()?;
         let ref_table_size = cursor.read_i32::<LittleEndian>()?;
+        let available = data.len() - HEADER_SIZE;
+        
+        // Sizes that don't fit but fit read the other way round mean the wrong byte order,
+        // which is clearer than whichever size check would fail below
+        if !Self::header_plausible(data, hash_table_size, ref_table_size)
+            && Self::header_plausible(data, hash_table_size.swap_bytes(), ref_table_size.swap_bytes())
+        {
+            return Err(ParamError::ByteSwapped { reason: "table sizes only fit the file when read big-endian" });
+        }
+        let hash_len = usize::try_from(hash_table_size).ok().filter(|&size| size <= available)
+            .ok_or(ParamError::InvalidTableSize { table: "hash table", size: hash_table_size, available })?;
+        let ref_len = usize::try_from(ref_table_size).ok().filter(|&size| size <= available - hash_len)
+            .ok_or(ParamError::InvalidTableSize { table: "ref table", size: ref_table_size, available: available - hash_len })?;
 
         // Calculate offsets
-        let hash_start = 0x10;
-        let ref_start = 0x10 + hash_table_size;
-        let param_start = 0x10 + hash_table_size + ref_table_size;
+        let hash_start = HEADER_SIZE;
+        let ref_start = hash_table_size + HEADER_SIZE as i32;
+        let param_start = HEADER_SIZE + hash_len + ref_len;
+        if param_start >= data.len() {
+            return Err(ParamError::NoParamData { param_start, len: data.len() });
+        }
 
         // Read hash table
         cursor.seek(SeekFrom::Start(hash_start as u64))?;
@@ -65,28 +315,71 @@ impl ParamFile {
         // Start reading from param section
         cursor.seek(SeekFrom::Start(param_start as u64))?;
         
-        // Check if root is a struct
+        // .prc roots are always structs, but accept any valid param type so variant
+        // containers (.stdat/.stprm) with a list or value root still open
         let type_byte = cursor.read_u8()?;
-        if type_byte != 12 { // ParamType::@struct = 12
-            return Err(anyhow!("File does not have a struct root. Got type: {}", type_byte));
+        if !(1..=12).contains(&type_byte) {
+            return Err(ParamError::UnexpectedType { got: type_byte, offset: param_start as u64 });
         }
         
         // Reset position to read the struct properly
         cursor.seek(SeekFrom::Start(param_start as u64))?;
         
+        // Every param takes at least one byte of param data, so a file can't hold more params
+        // than it has bytes; offsets that loop back or share data run out of this budget
+        let mut state = ReadState {
+            params_left: data.len() - param_start,
+            param_end: param_start as u64,
+            unknown_types: Vec::new(),
+        };
+        let root_value = Self::read_param(&mut cursor, &hash_table, ref_start, 0, &mut state)?;
+        
+        // Only a file that read completely replaces the open one
+        self.filename = filename.to_string();
+        self.unknown_types = state.unknown_types;
+        self.trailing = TrailingBytes::detect(data, state.param_end as usize);
         // Store the original hash table to preserve order during save
-        self.original_hash_table = hash_table.clone();
-
-        let root_value = self.read_param(&mut cursor, &hash_table, hash_start, ref_start)?;
+        self.original_hash_table = hash_table;
+        self.original_root = Some(root_value.clone());
         self.root = Some(ParamNode::from_value(0x0, root_value, &self.hash_labels));
+        self.original_digest = Some(Self::digest(data));
+        self.mark_modified();
 
         Ok(())
     }
+    
+    /// Whether the header's table sizes could belong to `data`: both non-negative, a hash
+    /// table of whole 8-byte hashes, both tables inside the file with room after them for a
+    /// root param of a known type
+    fn header_plausible(data: &[u8], hash_table_size: i32, ref_table_size: i32) -> bool {
+        let (Ok(hash_len), Ok(ref_len)) = (usize::try_from(hash_table_size), usize::try_from(ref_table_size)) else {
+            return false;
+        };
+        let type_byte = 0x10usize.checked_add(hash_len)
+            .and_then(|start| start.checked_add(ref_len))
+            .and_then(|param_start| data.get(param_start));
+        hash_len % 8 == 0 && type_byte.is_some_and(|type_byte| (1..=12).contains(type_byte))
+    }
+    
+    fn digest(data: &[u8]) -> (usize, u64) {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        (data.len(), hasher.finish())
+    }
 
-    fn read_param(&mut self, cursor: &mut Cursor<&[u8]>, hash_table: &[u64], hash_start: i32, ref_start: i32) -> Result<ParamValue> {
+    /// Read one param at the cursor. Offsets follow paracobNET's Disassembler:
+    /// string and struct ref offsets are relative to `ref_start`, while list item and
+    /// struct field offsets are relative to the container's own type byte. Counts and
+    /// offsets come from the file, so each one is checked against the data before it is
+    /// used to allocate, loop or seek.
+    fn read_param(cursor: &mut Cursor<&[u8]>, hash_table: &[u64], ref_start: i32, depth: usize, state: &mut ReadState) -> Result<ParamValue> {
+        if depth > MAX_PARAM_DEPTH {
+            return Err(ParamError::NestingTooDeep { offset: cursor.position(), max: MAX_PARAM_DEPTH });
+        }
+        state.params_left = state.params_left.checked_sub(1).ok_or(ParamError::TooManyParams)?;
         let type_byte = cursor.read_u8()?;
         
-        match type_byte {
+        let value = match type_byte {
             1 => Ok(ParamValue::Bool(cursor.read_u8()? != 0)),
             2 => Ok(ParamValue::I8(cursor.read_i8()?)),
             3 => Ok(ParamValue::U8(cursor.read_u8()?)),
@@ -99,7 +392,7 @@ impl ParamFile {
                 // hash40 - read index and lookup in hash table
                 let hash_index = cursor.read_u32::<LittleEndian>()? as usize;
                 if hash_index >= hash_table.len() {
-                    return Err(anyhow!("Hash index {} out of bounds (table size: {})", hash_index, hash_table.len()));
+                    return Err(ParamError::HashIndexOutOfBounds { index: hash_index, len: hash_table.len() });
                 }
                 Ok(ParamValue::Hash(hash_table[hash_index]))
             }
@@ -108,7 +401,7 @@ impl ParamFile {
                 let string_offset = cursor.read_i32::<LittleEndian>()?;
                 let current_pos = cursor.position();
                 
-                cursor.seek(SeekFrom::Start((ref_start + string_offset) as u64))?;
+                Self::seek_within(cursor, ref_start as i64, string_offset as i64, "string")?;
                 let mut string_bytes = Vec::new();
                 loop {
                     let byte = cursor.read_u8()?;
@@ -119,23 +412,24 @@ impl ParamFile {
                 }
                 
                 cursor.seek(SeekFrom::Start(current_pos))?;
-                let string_value = String::from_utf8_lossy(&string_bytes).to_string();
-                Ok(ParamValue::String(string_value))
+                // Kept byte-for-byte, even when the bytes aren't valid UTF-8
+                Ok(ParamValue::string_from_bytes(&string_bytes))
             }
             11 => {
                 // list
                 let start_pos = cursor.position() - 1;
                 let count = cursor.read_i32::<LittleEndian>()?;
-                let mut offsets = Vec::with_capacity(count as usize);
+                let count = Self::checked_count(cursor, count, 4, "list")?;
+                let mut offsets = Vec::with_capacity(count);
                 
                 for _ in 0..count {
                     offsets.push(cursor.read_u32::<LittleEndian>()?);
                 }
                 
-                let mut values = Vec::new();
+                let mut values = Vec::with_capacity(count);
                 for offset in offsets {
-                    cursor.seek(SeekFrom::Start(start_pos + offset as u64))?;
-                    values.push(self.read_param(cursor, hash_table, hash_start, ref_start)?);
+                    Self::seek_within(cursor, start_pos as i64, offset as i64, "list item")?;
+                    values.push(Self::read_param(cursor, hash_table, ref_start, depth + 1, state)?);
                 }
                 
                 Ok(ParamValue::List(ParamList { values }))
@@ -145,10 +439,13 @@ impl ParamFile {
                 let start_pos = cursor.position() - 1;
                 let size = cursor.read_i32::<LittleEndian>()?;
                 let struct_ref_offset = cursor.read_i32::<LittleEndian>()?;
+                // An empty struct's data ends with this header
+                state.param_end = state.param_end.max(cursor.position());
                 
                 // Read reference table entries
-                cursor.seek(SeekFrom::Start((ref_start + struct_ref_offset) as u64))?;
-                let mut hash_offsets = Vec::new();
+                Self::seek_within(cursor, ref_start as i64, struct_ref_offset as i64, "struct ref table")?;
+                let size = Self::checked_count(cursor, size, 8, "struct")?;
+                let mut hash_offsets = Vec::with_capacity(size);
                 
                 for _ in 0..size {
                     let hash_index = cursor.read_i32::<LittleEndian>()?;
@@ -162,9 +459,9 @@ impl ParamFile {
                 let mut fields = IndexMap::new();
                 for (hash_index, param_offset) in hash_offsets {
                     if hash_index >= 0 && (hash_index as usize) < hash_table.len() {
-                        cursor.seek(SeekFrom::Start(start_pos + param_offset as u64))?;
+                        Self::seek_within(cursor, start_pos as i64, param_offset as i64, "struct field")?;
                         let hash = hash_table[hash_index as usize];
-                        let value = self.read_param(cursor, hash_table, hash_start, ref_start)?;
+                        let value = Self::read_param(cursor, hash_table, ref_start, depth + 1, state)?;
                         fields.insert(hash, value);
                     }
                 }
@@ -175,6 +472,8 @@ impl ParamFile {
                 }))
             }
             _ => {
+                state.unknown_types.push((type_byte, cursor.position() - 1));
+                
                 // Handle unknown types like the JavaScript parser
                 // Try to determine a reasonable default value size based on type number
                 let default_size = if type_byte < 50 { 4 } else if type_byte < 100 { 8 } else { 12 };
@@ -189,7 +488,65 @@ impl ParamFile {
                 // Return a placeholder value that won't break the tree
                 Ok(ParamValue::U32(type_byte as u32)) // Store the unknown type as a U32 for now
             }
+        };
+        
+        // Containers leave the cursor at their last child (or in the ref table), which the
+        // child already counted
+        state.param_end = state.param_end.max(cursor.position());
+        value
+    }
+
+    /// Seek to `base + offset`, failing instead of wrapping around when that lies outside the data
+    fn seek_within(cursor: &mut Cursor<&[u8]>, base: i64, offset: i64, what: &'static str) -> Result<()> {
+        let len = cursor.get_ref().len() as i64;
+        let target = base + offset;
+        if !(0..len).contains(&target) {
+            return Err(ParamError::OffsetOutOfRange { what, offset: target, len: len as usize });
         }
+        cursor.set_position(target as u64);
+        Ok(())
+    }
+    
+    /// A list or struct entry count read from the file, if that many `entry_size`-byte entries
+    /// fit in the bytes after the cursor
+    fn checked_count(cursor: &Cursor<&[u8]>, count: i32, entry_size: usize, what: &'static str) -> Result<usize> {
+        let remaining = cursor.get_ref().len().saturating_sub(cursor.position() as usize);
+        usize::try_from(count).ok()
+            .filter(|&count| count <= remaining / entry_size)
+            .ok_or(ParamError::InvalidCount { what, count, offset: cursor.position() })
+    }
+
+    /// Compare what a save would write with the opened file. None when nothing was opened
+    /// (or it failed to serialize), since there is no original to compare with.
+    pub fn save_ordering(&self) -> Option<SaveOrdering> {
+        let original_digest = self.original_digest?;
+        let bytes = self.to_bytes().ok()?;
+        
+        let mut hash_table = Vec::new();
+        let mut hash_to_index = HashMap::new();
+        self.write_hash(0, &mut hash_table, &mut hash_to_index);
+        self.iterate_hashes(&self.get_root()?.value, &mut hash_table, &mut hash_to_index);
+        
+        Some(SaveOrdering {
+            hash_table_kept: hash_table == self.original_hash_table,
+            byte_identical: Self::digest(&bytes) == original_digest,
+        })
+    }
+    
+    /// Padding or other bytes that followed the param data in the file last opened
+    pub fn trailing_bytes(&self) -> Option<&TrailingBytes> {
+        self.trailing.as_ref()
+    }
+    
+    /// Whether the last `open` read any param with the unknown-type fallback. Those values
+    /// are placeholders, so saving won't reproduce the original data.
+    pub fn had_unknown_types(&self) -> bool {
+        !self.unknown_types.is_empty()
+    }
+
+    /// (type byte, file offset) of each unknown-type param from the last `open`
+    pub fn unknown_types(&self) -> &[(u8, u64)] {
+        &self.unknown_types
     }
 
     pub fn get_root(&self) -> Option<&ParamNode> {
@@ -222,304 +579,148 @@ impl ParamFile {
         Some(indices)
     }
     
-    /// Get mutable reference to node at path
-    #[allow(dead_code)]
-    pub fn get_node_mut(&mut self, path: &str) -> Option<&mut ParamNode> {
+    /// View of the node at a path like "root[0][1]"
+    pub fn node_at(&self, path: &str) -> Option<NodeRef<'_>> {
         let indices = self.parse_node_path(path)?;
-        let root = self.get_root_mut()?;
-        root.get_child_mut(&indices)
+        let mut node = self.get_root()?.view();
+        for index in indices {
+            node = node.child(index, &self.hash_labels)?;
+        }
+        Some(node)
+    }
+    
+    /// Mutable value reached by following `indices` down from `value`
+    fn value_at_mut<'v>(value: &'v mut ParamValue, indices: &[usize]) -> Option<&'v mut ParamValue> {
+        let Some((&index, rest)) = indices.split_first() else {
+            return Some(value);
+        };
+        let child = match value {
+            ParamValue::Struct(s) => s.fields.get_index_mut(index).map(|(_, v)| v)?,
+            ParamValue::List(l) => l.values.get_mut(index)?,
+            _ => return None,
+        };
+        Self::value_at_mut(child, rest)
     }
     
-    /// Update a node's key (hash and name) and update the underlying data structure
+    /// Update a node's key. Field names come from the labels, so `new_name` is only
+    /// stored for the root.
     pub fn update_node_key(&mut self, path: &str, new_name: String, new_hash: u64) -> bool {
         let indices = match self.parse_node_path(path) {
             Some(indices) => indices,
             None => return false,
         };
         
-        if indices.is_empty() {
-            // Updating root key
-            if let Some(root) = &mut self.root {
-                root.name = new_name;
-                root.hash = new_hash;
-                return true;
-            }
+        let Some(root) = &mut self.root else {
             return false;
-        }
+        };
         
-        // For child nodes, we need to update both the node AND the parent struct's field mapping
-        Self::update_nested_key(&mut self.root, &indices, new_name, new_hash, 0)
-    }
-    
-    fn update_nested_key(
-        node: &mut Option<ParamNode>, 
-        indices: &[usize], 
-        new_name: String,
-        new_hash: u64,
-        depth: usize
-    ) -> bool {
-        if let Some(current_node) = node {
-            let current_index = indices[depth];
-            
-            if depth == indices.len() - 1 {
-                // This is the parent of the target node
-                match &mut current_node.value {
-                    ParamValue::Struct(ref mut s) => {
-                        if current_index < current_node.children.len() {
-                            let old_hash = current_node.children[current_index].hash;
-                            
-                            // Remove old field and add with new hash
-                            if let Some(field_value) = s.fields.shift_remove(&old_hash) {
-                                s.fields.insert(new_hash, field_value);
-                            }
-                            
-                            // Update the child node
-                            current_node.children[current_index].name = new_name;
-                            current_node.children[current_index].hash = new_hash;
-                            return true;
-                        }
-                    }
-                    _ => {} // Lists don't have named keys
-                }
-            } else {
-                // Continue recursing
-                if current_index < current_node.children.len() {
-                    let mut child_option = Some(std::mem::replace(&mut current_node.children[current_index], ParamNode::new("temp".to_string(), 0, ParamValue::Bool(false))));
-                    let result = Self::update_nested_key(
-                        &mut child_option, 
-                        indices, 
-                        new_name, 
-                        new_hash,
-                        depth + 1
-                    );
-                    if let Some(updated_child) = child_option {
-                        current_node.children[current_index] = updated_child;
-                    }
-                    return result;
-                }
-            }
-        }
-        false
-    }
-
-    /// Get the current value of a node at the given path
-    pub fn get_node_value(&self, path: &str) -> Option<ParamValue> {
-        let indices = match self.parse_node_path(path) {
-            Some(indices) => indices,
-            None => return None,
+        let Some((&field_index, parent_indices)) = indices.split_last() else {
+            // Updating root key
+            root.name = new_name;
+            root.hash = new_hash;
+            self.mark_modified();
+            return true;
         };
         
-        if indices.is_empty() {
-            // Getting root value
-            return self.root.as_ref().map(|r| r.value.clone());
-        }
+        // Lists don't have named keys
+        let Some(ParamValue::Struct(s)) = Self::value_at_mut(&mut root.value, parent_indices) else {
+            return false;
+        };
+        let Some((&old_hash, _)) = s.fields.get_index(field_index) else {
+            return false;
+        };
         
-        if let Some(root) = &self.root {
-            Self::get_param_value_at_path(&root.value, &indices, 0)
-        } else {
-            None
+        // Refuse to rename onto an existing sibling key - it would merge two fields
+        if new_hash != old_hash && s.fields.contains_key(&new_hash) {
+            return false;
         }
+        
+        // Swap the key in place so the field keeps its position (and sibling paths stay valid)
+        s.fields = s.fields.drain(..)
+            .enumerate()
+            .map(|(i, (hash, value))| if i == field_index { (new_hash, value) } else { (hash, value) })
+            .collect();
+        self.mark_modified();
+        true
     }
-    
-    /// Get a ParamValue at a specific path in the data structure
-    fn get_param_value_at_path(value: &ParamValue, indices: &[usize], depth: usize) -> Option<ParamValue> {
-        if depth >= indices.len() {
-            return Some(value.clone());
+
+    /// Index of a sibling struct field that already uses `new_hash` as its key, if renaming
+    /// the node at `path` to it would collide. Always None for list items and the root.
+    pub fn key_collision(&self, path: &str, new_hash: u64) -> Option<usize> {
+        let indices = self.parse_node_path(path)?;
+        let (&field_index, parent_indices) = indices.split_last()?;
+        let mut parent = self.get_root()?.view();
+        for &index in parent_indices {
+            parent = parent.child(index, &self.hash_labels)?;
         }
         
-        let current_index = indices[depth];
-        
-        match value {
-            ParamValue::Struct(s) => {
-                if let Some((_, field_value)) = s.fields.iter().nth(current_index) {
-                    if depth == indices.len() - 1 {
-                        Some(field_value.clone())
-                    } else {
-                        Self::get_param_value_at_path(field_value, indices, depth + 1)
-                    }
-                } else {
-                    None
-                }
-            }
-            ParamValue::List(l) => {
-                if current_index < l.values.len() {
-                    if depth == indices.len() - 1 {
-                        Some(l.values[current_index].clone())
-                    } else {
-                        Self::get_param_value_at_path(&l.values[current_index], indices, depth + 1)
-                    }
-                } else {
-                    None
-                }
-            }
+        match parent.value {
+            ParamValue::Struct(s) => s.fields.get_index_of(&new_hash).filter(|&i| i != field_index),
             _ => None,
         }
     }
 
-    /// Update a node's value and update the underlying data structure
+    /// Get the current value of a node at the given path
+    pub fn get_node_value(&self, path: &str) -> Option<ParamValue> {
+        self.node_at(path).map(|node| node.value.clone())
+    }
+
+    /// Update a node's value in the data tree
     pub fn update_node_value(&mut self, path: &str, new_value: ParamValue) -> bool {
-        let indices = match self.parse_node_path(path) {
-            Some(indices) => indices,
-            None => return false,
-        };
-        
-        if indices.is_empty() {
-            // Updating root
-            if let Some(root) = &mut self.root {
-                root.value = new_value;
-                return true;
-            }
+        let Some(indices) = self.parse_node_path(path) else {
             return false;
-        }
-        
-        // CRITICAL FIX: Update the underlying ParamValue structure directly
-        // This ensures that the save process will see the updated values
-        if let Some(root) = &mut self.root {
-            if Self::update_param_value_directly(&mut root.value, &indices, new_value.clone(), 0) {
-                // Also update the display tree to keep UI in sync
-                Self::update_nested_value(&mut self.root, &indices, new_value, 0);
-                return true;
-            }
-        }
-        
-        false
-    }
-    
-    /// Update the underlying ParamValue structure directly (not just the display tree)
-    fn update_param_value_directly(
-        value: &mut ParamValue,
-        indices: &[usize],
-        new_value: ParamValue,
-        depth: usize
-    ) -> bool {
-        if depth >= indices.len() {
+        };
+        let Some(value) = self.root.as_mut().and_then(|root| Self::value_at_mut(&mut root.value, &indices)) else {
             return false;
-        }
-        
-        let current_index = indices[depth];
+        };
         
-        if depth == indices.len() - 1 {
-            // This is the final level - update the actual value
-            match value {
-                ParamValue::Struct(ref mut s) => {
-                    // Find the field by index in the struct
-                    if let Some((field_hash, _)) = s.fields.iter().nth(current_index) {
-                        let field_hash = *field_hash; // Copy the hash
-                        s.fields.insert(field_hash, new_value);
-                        return true;
-                    }
-                }
-                ParamValue::List(ref mut l) => {
-                    if current_index < l.values.len() {
-                        l.values[current_index] = new_value;
-                        return true;
-                    }
-                }
-                _ => return false,
-            }
-        } else {
-            // Continue recursing
-            match value {
-                ParamValue::Struct(ref mut s) => {
-                    if let Some((_, field_value)) = s.fields.iter_mut().nth(current_index) {
-                        return Self::update_param_value_directly(field_value, indices, new_value, depth + 1);
-                    }
-                }
-                ParamValue::List(ref mut l) => {
-                    if current_index < l.values.len() {
-                        return Self::update_param_value_directly(&mut l.values[current_index], indices, new_value, depth + 1);
-                    }
-                }
-                _ => return false,
-            }
-        }
-        
-        false
-    }
-
-    /// Update a node's value and update the underlying data structure
-    fn update_nested_value(
-        node: &mut Option<ParamNode>, 
-        indices: &[usize], 
-        new_value: ParamValue, 
-        depth: usize
-    ) -> bool {
-        if let Some(current_node) = node {
-            let current_index = indices[depth];
-            
-            if depth == indices.len() - 1 {
-                // This is the parent of the target node
-                match &mut current_node.value {
-                    ParamValue::Struct(ref mut s) => {
-                        if current_index < current_node.children.len() {
-                            let child_hash = current_node.children[current_index].hash;
-                            
-                            // Update the field in the struct
-                            s.fields.insert(child_hash, new_value.clone());
-                            
-                            // Update the child node
-                            current_node.children[current_index].value = new_value;
-                            
-                            return true;
-                        }
-                    }
-                    ParamValue::List(ref mut l) => {
-                        if current_index < current_node.children.len() && current_index < l.values.len() {
-                            // Update the item in the list
-                            l.values[current_index] = new_value.clone();
-                            
-                            // Update the child node
-                            current_node.children[current_index].value = new_value;
-                            
-                            return true;
-                        }
-                    }
-                    _ => return false,
-                }
-            } else {
-                // Continue recursing
-                if current_index < current_node.children.len() {
-                    let mut child_option = Some(std::mem::replace(&mut current_node.children[current_index], ParamNode::new("temp".to_string(), 0, ParamValue::Bool(false))));
-                    let result = Self::update_nested_value(
-                        &mut child_option, 
-                        indices, 
-                        new_value, 
-                        depth + 1
-                    );
-                    if let Some(updated_child) = child_option {
-                        current_node.children[current_index] = updated_child;
-                    }
-                    return result;
-                }
-            }
-        }
-        false
+        *value = new_value;
+        self.mark_modified();
+        true
     }
 
     pub fn get_filename(&self) -> &str {
         &self.filename
     }
     
-    /// Rebuild the tree from the current root data structure
-    /// This ensures the display tree is synchronized with the underlying data
-    #[allow(dead_code)]
-    pub fn rebuild_tree(&mut self) {
-        if let Some(root_value) = &self.root.as_ref().map(|n| n.value.clone()) {
-            self.root = Some(ParamNode::from_value(0x0, root_value.clone(), &self.hash_labels));
+    /// Call after labels change: node names are resolved on demand, so this only
+    /// refreshes the root's name and bumps the label revision. The tree revision is left
+    /// alone, so expansion, selection and caches of the tree itself (hash index, sizes,
+    /// the save comparison) survive a label edit.
+    pub fn refresh_names_only(&mut self) {
+        if let Some(root) = &mut self.root {
+            root.name = self.hash_labels.hash_to_string(root.hash);
         }
+        self.label_revision = self.label_revision.wrapping_add(1);
+    }
+    
+    /// Save the current parameter file to binary format
+    pub fn save(&self, output_path: &str) -> Result<()> {
+        std::fs::write(output_path, self.to_bytes()?)?;
+        Ok(())
     }
     
-    /// Rebuild the tree with updated hash labels
-    /// Call this after loading new labels to update all field names
-    pub fn rebuild_tree_with_labels(&mut self) {
-        if let Some(root_value) = &self.root.as_ref().map(|n| n.value.clone()) {
-            self.root = Some(ParamNode::from_value(0x0, root_value.clone(), &self.hash_labels));
+    /// Save through a temporary file that is read back and re-parsed before it replaces
+    /// `output_path`, so a broken save never overwrites the existing file
+    pub fn save_verified(&self, output_path: &str) -> Result<()> {
+        let temp_path = format!("{}.tmp", output_path);
+        std::fs::write(&temp_path, self.to_bytes()?)?;
+        
+        let verification = std::fs::read(&temp_path)
+            .map_err(ParamError::from)
+            .and_then(|written| ParamFile::new().open(&written, &self.filename));
+        if let Err(e) = verification {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(ParamError::VerificationFailed(Box::new(e)));
         }
+        
+        std::fs::rename(&temp_path, output_path)?;
+        Ok(())
     }
     
-    /// Save the current parameter file to binary format
-    pub fn save(&self, output_path: &str) -> Result<()> {
-        let root = self.get_root().ok_or_else(|| anyhow!("No data to save"))?;
+    /// Serialize the current parameter file to paracobn bytes
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let root = self.get_root().ok_or(ParamError::NoData)?;
         
         // Step 1: Build hash table exactly like paracobNET
         // CRITICAL: paracobNET starts with WriteHash(0) then calls IterateHashes
@@ -547,10 +748,22 @@ impl ParamFile {
             &mut struct_ref_entries,
             &mut unresolved_structs,
             &mut unresolved_strings
-        )?;
+        ).map_err(|e| match e {
+            // Name the node using the hash so the save error points at the offending value
+            ParamError::HashNotInTable { hash, path: None } => ParamError::HashNotInTable {
+                hash,
+                path: self.hash_index().paths_using(hash).next().map(str::to_string),
+            },
+            ParamError::OffsetOverflow { what, offset, path } => ParamError::OffsetOverflow {
+                what,
+                offset,
+                path: Some(format!("root{}", path.unwrap_or_default())),
+            },
+            e => e,
+        })?;
         
-        // Step 3: Skip merging for now to ensure compatibility
-        // self.merge_ref_tables(&mut ref_entries, &mut struct_ref_entries, &mut unresolved_structs);
+        // Step 3: Merge identical struct reference entries (like MergeRefTables)
+        self.merge_ref_tables(&mut ref_entries, &mut struct_ref_entries);
         
         // Step 4: Write reference table (like WriteRefTables)
         let mut ref_table = Vec::new();
@@ -561,7 +774,7 @@ impl ParamFile {
             match entry {
                 RefEntry::String(s) => {
                     string_offsets.insert(s.clone(), ref_table.len());
-                    ref_table.extend_from_slice(s.as_bytes());
+                    ref_table.extend_from_slice(s);
                     ref_table.push(0); // null terminator
                 }
                 RefEntry::StructRef(entries) => {
@@ -582,17 +795,18 @@ impl ParamFile {
             if let Some(ref_entry_index) = struct_ref_entries.get(&struct_id) {
                 if let Some(ref_table_offset) = ref_table_offsets.get(ref_entry_index) {
                     param_cursor.seek(SeekFrom::Start(position as u64))?;
-                    param_cursor.write_i32::<LittleEndian>(*ref_table_offset as i32)?;
+                    param_cursor.write_i32::<LittleEndian>(offset_field(*ref_table_offset, "struct ref table offset")?)?;
                 }
             }
         }
         
-        // Resolve string references
+        // Resolve string references. Every string was appended to the ref table when it was
+        // written, so a miss would leave a 0 offset pointing at the wrong data - fail instead.
         for (position, string) in unresolved_strings {
-            if let Some(offset) = string_offsets.get(&string) {
-                param_cursor.seek(SeekFrom::Start(position as u64))?;
-                param_cursor.write_i32::<LittleEndian>(*offset as i32)?;
-            }
+            let offset = *string_offsets.get(&string)
+                .ok_or_else(|| ParamError::StringNotInRefTable { value: String::from_utf8_lossy(&string).to_string() })?;
+            param_cursor.seek(SeekFrom::Start(position as u64))?;
+            param_cursor.write_i32::<LittleEndian>(offset_field(offset, "string offset")?)?;
         }
         
         // Step 6: Build hash table
@@ -606,8 +820,8 @@ impl ParamFile {
         
         // Write header
         output.extend_from_slice(b"paracobn");
-        output.write_i32::<LittleEndian>(hash_data.len() as i32)?; // hash table size
-        output.write_i32::<LittleEndian>(ref_table.len() as i32)?;  // ref table size
+        output.write_i32::<LittleEndian>(offset_field(hash_data.len(), "hash table size")?)?;
+        output.write_i32::<LittleEndian>(offset_field(ref_table.len(), "ref table size")?)?;
         
         // Write hash table
         output.extend(hash_data);
@@ -618,9 +832,163 @@ impl ParamFile {
         // Write parameter data
         output.extend(param_data);
         
-        // Write to file
-        std::fs::write(output_path, output)?;
-        Ok(())
+        if let Some(trailing) = self.trailing.as_ref().filter(|_| self.keep_trailing_bytes) {
+            trailing.apply(&mut output);
+        }
+        
+        Ok(output)
+    }
+    
+    /// Export the struct/list hierarchy as a GraphViz DOT graph (labels and types, no values).
+    /// Lists are drawn once through their first item since items normally share a layout.
+    pub fn export_dot(&self) -> Result<String> {
+        let root = self.get_root().ok_or(ParamError::NoData)?;
+        
+        let mut dot = String::from("digraph param_structure {\n    rankdir=LR;\n    node [shape=box, fontname=\"monospace\"];\n");
+        let mut next_id = 0;
+        self.write_dot_node(&root.value, "root", &mut dot, &mut next_id);
+        dot.push_str("}\n");
+        
+        Ok(dot)
+    }
+    
+    /// Emit a DOT node for `value` and its children, returning the node id
+    fn write_dot_node(&self, value: &ParamValue, name: &str, dot: &mut String, next_id: &mut usize) -> String {
+        let id = format!("n{}", *next_id);
+        *next_id += 1;
+        
+        let type_name = value.type_name();
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let shape = match value {
+            ParamValue::Struct(_) | ParamValue::List(_) => "box",
+            _ => "plaintext",
+        };
+        dot.push_str(&format!("    {} [label=\"{}\\n{}\", shape={}];\n", id, escape(name), type_name, shape));
+        
+        match value {
+            ParamValue::Struct(s) => {
+                for (hash, field) in &s.fields {
+                    let child_id = self.write_dot_node(field, &self.hash_labels.hash_to_string(*hash), dot, next_id);
+                    dot.push_str(&format!("    {} -> {};\n", id, child_id));
+                }
+            }
+            ParamValue::List(l) => {
+                if let Some(first) = l.values.first() {
+                    let child_id = self.write_dot_node(first, "[0]", dot, next_id);
+                    dot.push_str(&format!("    {} -> {} [label=\"{} items\"];\n", id, child_id, l.values.len()));
+                }
+            }
+            _ => {}
+        }
+        
+        id
+    }
+    
+    /// Export the tree as ParamXML-compatible XML (the `.prcx` format).
+    /// Hashes are written as labels where known, otherwise as `0x` followed by 10 hex digits.
+    pub fn export_xml(&self) -> Result<String> {
+        let root = self.get_root().ok_or(ParamError::NoData)?;
+        
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        self.write_xml_node(&root.value, "", 0, &mut xml);
+        
+        Ok(xml)
+    }
+    
+    /// Emit the element for `value` with the given attribute string (e.g. ` hash="..."`)
+    fn write_xml_node(&self, value: &ParamValue, attributes: &str, depth: usize, xml: &mut String) {
+        let indent = "  ".repeat(depth);
+        let tag = match value {
+            ParamValue::Bool(_) => "bool",
+            ParamValue::I8(_) => "sbyte",
+            ParamValue::U8(_) => "byte",
+            ParamValue::I16(_) => "short",
+            ParamValue::U16(_) => "ushort",
+            ParamValue::I32(_) => "int",
+            ParamValue::U32(_) => "uint",
+            ParamValue::F32(_) => "float",
+            ParamValue::Hash(_) => "hash40",
+            ParamValue::String(_) | ParamValue::RawString(_) => "string",
+            ParamValue::List(_) => "list",
+            ParamValue::Struct(_) => "struct",
+        };
+        
+        match value {
+            ParamValue::Struct(s) => {
+                if s.fields.is_empty() {
+                    xml.push_str(&format!("{}<{}{} />\n", indent, tag, attributes));
+                    return;
+                }
+                xml.push_str(&format!("{}<{}{}>\n", indent, tag, attributes));
+                for (hash, field) in &s.fields {
+                    let attr = format!(" hash=\"{}\"", xml_escape(&self.xml_hash_string(*hash)));
+                    self.write_xml_node(field, &attr, depth + 1, xml);
+                }
+                xml.push_str(&format!("{}</{}>\n", indent, tag));
+            }
+            ParamValue::List(l) => {
+                let attributes = format!("{} size=\"{}\"", attributes, l.values.len());
+                if l.values.is_empty() {
+                    xml.push_str(&format!("{}<{}{} />\n", indent, tag, attributes));
+                    return;
+                }
+                xml.push_str(&format!("{}<{}{}>\n", indent, tag, attributes));
+                for (index, item) in l.values.iter().enumerate() {
+                    self.write_xml_node(item, &format!(" index=\"{}\"", index), depth + 1, xml);
+                }
+                xml.push_str(&format!("{}</{}>\n", indent, tag));
+            }
+            _ => {
+                let text = match value {
+                    ParamValue::Bool(v) => if *v { "True".to_string() } else { "False".to_string() },
+                    ParamValue::Hash(h) => self.xml_hash_string(*h),
+                    ParamValue::I8(v) => v.to_string(),
+                    ParamValue::U8(v) => v.to_string(),
+                    ParamValue::I16(v) => v.to_string(),
+                    ParamValue::U16(v) => v.to_string(),
+                    ParamValue::I32(v) => v.to_string(),
+                    ParamValue::U32(v) => v.to_string(),
+                    ParamValue::F32(v) => v.to_string(),
+                    ParamValue::String(v) => v.clone(),
+                    ParamValue::RawString(v) => escape_raw_string(v),
+                    ParamValue::List(_) | ParamValue::Struct(_) => unreachable!(),
+                };
+                // Bytes that aren't UTF-8 can't go into the XML as they are, so they're escaped
+                // and the element says so
+                let escaped = if matches!(value, ParamValue::RawString(_)) { " escaped=\"true\"" } else { "" };
+                xml.push_str(&format!("{}<{}{}{}>{}</{}>\n", indent, tag, attributes, escaped, xml_escape(&text), tag));
+            }
+        }
+    }
+    
+    /// Hash formatted the way ParamXML writes it
+    fn xml_hash_string(&self, hash: u64) -> String {
+        match self.hash_labels.get_label(hash) {
+            Some(label) => label.clone(),
+            None => format!("0x{:010x}", hash),
+        }
+    }
+    
+    /// Size in bytes of a value's param data when serialized on its own.
+    /// Strings and struct ref tables live in the shared ref table, so they aren't counted.
+    pub fn serialized_size(&self, value: &ParamValue) -> Result<usize> {
+        let mut hash_table = Vec::new();
+        let mut hash_to_index = HashMap::new();
+        self.write_hash(0, &mut hash_table, &mut hash_to_index);
+        self.iterate_hashes(value, &mut hash_table, &mut hash_to_index);
+        
+        let mut output = Vec::new();
+        self.write_param_value(
+            value,
+            &mut output,
+            &hash_to_index,
+            &mut Vec::new(),
+            &mut HashMap::new(),
+            &mut Vec::new(),
+            &mut Vec::new()
+        )?;
+        
+        Ok(output.len())
     }
     
     /// Collect hashes like paracobNET's IterateHashes method
@@ -630,6 +998,8 @@ impl ParamFile {
             ParamValue::Struct(s) => {
                 // CRITICAL: paracobNET's IterateHashes processes struct fields in NATURAL ORDER (not sorted!)
                 // Only the Write method sorts them - this is the key difference!
+                // Natural order after `read_param` is hash-table index order, which is the same
+                // order paracobNET's Disassembler produces, so the rebuilt hash table matches.
                 for (field_hash, field_value) in &s.fields {
                     self.write_hash(*field_hash, hash_table, hash_to_index);
                     self.iterate_hashes(field_value, hash_table, hash_to_index);
@@ -647,6 +1017,284 @@ impl ParamFile {
         }
     }
     
+    /// Collect every hash used in the current tree (struct keys and hash40 values)
+    pub fn collect_used_hashes(&self) -> Vec<u64> {
+        self.hash_index().hashes().collect()
+    }
+
+    /// Call `f` for every leaf (any value that isn't a struct or list) in tree order, with its
+    /// node path ("root[3][0]") and label-path: struct keys joined by '/', list items as
+    /// "[index]", e.g. "fighter_param[3]/walk_speed".
+    pub fn walk_leaves(&self, mut f: impl FnMut(&str, &str, &ParamValue)) {
+        if let Some(root) = self.get_root() {
+            let mut path = "root".to_string();
+            let mut label_path = String::new();
+            self.walk_leaves_recursive(&root.value, &mut path, &mut label_path, &mut f);
+        }
+    }
+    
+    fn walk_leaves_recursive(&self, value: &ParamValue, path: &mut String, label_path: &mut String, f: &mut impl FnMut(&str, &str, &ParamValue)) {
+        let (parent_len, parent_label_len) = (path.len(), label_path.len());
+        match value {
+            ParamValue::Struct(s) => {
+                for (i, (hash, child)) in s.fields.iter().enumerate() {
+                    path.push_str(&format!("[{}]", i));
+                    self.push_label_key(label_path, *hash);
+                    self.walk_leaves_recursive(child, path, label_path, f);
+                    path.truncate(parent_len);
+                    label_path.truncate(parent_label_len);
+                }
+            }
+            ParamValue::List(l) => {
+                for (i, child) in l.values.iter().enumerate() {
+                    path.push_str(&format!("[{}]", i));
+                    label_path.push_str(&format!("[{}]", i));
+                    self.walk_leaves_recursive(child, path, label_path, f);
+                    path.truncate(parent_len);
+                    label_path.truncate(parent_label_len);
+                }
+            }
+            _ => f(path, label_path, value),
+        }
+    }
+
+    /// What changed since the file was opened, as a partial tree: struct fields whose value
+    /// differs or that were added, inside the structs leading to them. A list with any change
+    /// is kept whole, since list items are matched by position. The count is of struct fields
+    /// removed since opening, which a partial tree can't express. None when nothing was opened.
+    pub fn changes_since_open(&self) -> Option<(Option<ParamValue>, usize)> {
+        let original = self.original_root.as_ref()?;
+        let root = self.get_root()?;
+        let mut removed = 0;
+        let changed = Self::changed_part(&root.value, original, &mut removed);
+        Some((changed, removed))
+    }
+    
+    fn changed_part(value: &ParamValue, original: &ParamValue, removed: &mut usize) -> Option<ParamValue> {
+        match (value, original) {
+            (ParamValue::Struct(s), ParamValue::Struct(o)) => {
+                *removed += o.fields.keys().filter(|hash| !s.fields.contains_key(*hash)).count();
+                let fields: IndexMap<u64, ParamValue> = s.fields.iter()
+                    .filter_map(|(hash, child)| match o.fields.get(hash) {
+                        Some(original_child) => Self::changed_part(child, original_child, removed).map(|part| (*hash, part)),
+                        None => Some((*hash, child.clone())),
+                    })
+                    .collect();
+                (!fields.is_empty()).then(|| ParamValue::Struct(ParamStruct { type_hash: s.type_hash, fields }))
+            }
+            (ParamValue::List(l), ParamValue::List(o)) => {
+                let changed = l.values.len() != o.values.len()
+                    || l.values.iter().zip(&o.values).any(|(item, original_item)| Self::changed_part(item, original_item, &mut 0).is_some());
+                changed.then(|| value.clone())
+            }
+            // Compare bits so NaN fields that didn't change aren't reported
+            (ParamValue::F32(v), ParamValue::F32(o)) if v.to_bits() == o.to_bits() => None,
+            _ if value == original => None,
+            _ => Some(value.clone()),
+        }
+    }
+    
+    /// Whether `data` opens, saves and re-opens to the same tree. Floats are compared by bits.
+    /// Takes only the bytes so it can run away from the open file, e.g. on another thread.
+    pub fn round_trips(data: &[u8]) -> Result<bool> {
+        let mut original = ParamFile::new();
+        original.open(data, "")?;
+        let mut reopened = ParamFile::new();
+        reopened.open(&original.to_bytes()?, "")?;
+        
+        let (Some(root), Some(reopened_root)) = (original.get_root(), reopened.get_root()) else {
+            return Ok(false);
+        };
+        let mut removed = 0;
+        let changed = Self::changed_part(&reopened_root.value, &root.value, &mut removed);
+        Ok(changed.is_none() && removed == 0)
+    }
+    
+    /// Paracobn bytes of a standalone file whose root is `root`
+    pub fn bytes_of(root: ParamValue) -> Result<Vec<u8>> {
+        let mut file = ParamFile::new();
+        file.root = Some(ParamNode::new(String::new(), 0, root));
+        file.to_bytes()
+    }
+    
+    /// Patch that turns `base` into this file: values that differ, plus fields and list
+    /// items only one side has. List items are matched by index and removed from the end.
+    pub fn diff_patch(&self, base: &ParamFile) -> ParamPatch {
+        let mut patch = ParamPatch::default();
+        if let (Some(root), Some(base_root)) = (self.get_root(), base.get_root()) {
+            let mut path = String::new();
+            self.diff_values(&root.value, &base_root.value, &mut path, &mut patch);
+        }
+        patch
+    }
+    
+    fn diff_values(&self, value: &ParamValue, base: &ParamValue, path: &mut String, patch: &mut ParamPatch) {
+        let parent_len = path.len();
+        match (value, base) {
+            (ParamValue::Struct(s), ParamValue::Struct(b)) => {
+                for (hash, child) in &s.fields {
+                    self.push_label_key(path, *hash);
+                    match b.fields.get(hash) {
+                        Some(base_child) => self.diff_values(child, base_child, path, patch),
+                        None => {
+                            patch.set.insert(path.clone(), child.clone());
+                        }
+                    }
+                    path.truncate(parent_len);
+                }
+                for hash in b.fields.keys().filter(|hash| !s.fields.contains_key(*hash)) {
+                    self.push_label_key(path, *hash);
+                    patch.removed.push(path.clone());
+                    path.truncate(parent_len);
+                }
+            }
+            (ParamValue::List(l), ParamValue::List(b)) => {
+                for (i, child) in l.values.iter().enumerate() {
+                    path.push_str(&format!("[{}]", i));
+                    match b.values.get(i) {
+                        Some(base_child) => self.diff_values(child, base_child, path, patch),
+                        None => {
+                            patch.set.insert(path.clone(), child.clone());
+                        }
+                    }
+                    path.truncate(parent_len);
+                }
+                // Last item first so earlier indices stay valid while removing
+                for i in (l.values.len()..b.values.len()).rev() {
+                    patch.removed.push(format!("{}[{}]", path, i));
+                }
+            }
+            // Compare bits so NaN fields that didn't change aren't reported
+            (ParamValue::F32(v), ParamValue::F32(b)) if v.to_bits() == b.to_bits() => {}
+            _ if value == base => {}
+            _ => {
+                patch.set.insert(path.clone(), value.clone());
+            }
+        }
+    }
+    
+    fn push_label_key(&self, path: &mut String, hash: u64) {
+        if !path.is_empty() {
+            path.push('/');
+        }
+        path.push_str(&self.hash_labels.hash_to_string(hash));
+    }
+    
+    /// Label-path of the node at a path like "root[0][1]", e.g. "fighter_param[3]/walk_speed";
+    /// empty for the root
+    pub fn label_path_of(&self, path: &str) -> Option<String> {
+        let mut value = &self.get_root()?.value;
+        let mut label_path = String::new();
+        for index in self.parse_node_path(path)? {
+            value = match value {
+                ParamValue::Struct(s) => {
+                    let (hash, child) = s.fields.get_index(index)?;
+                    self.push_label_key(&mut label_path, *hash);
+                    child
+                }
+                ParamValue::List(l) => {
+                    label_path.push_str(&format!("[{}]", index));
+                    l.values.get(index)?
+                }
+                _ => return None,
+            };
+        }
+        Some(label_path)
+    }
+    
+    /// Find where a label-path from a patch lands in this file. A missing last segment is
+    /// an `Append` when its parent exists and it names a new field or the next list item.
+    pub fn locate_label_path(&self, label_path: &str) -> Option<PatchTarget> {
+        let mut value = &self.get_root()?.value;
+        let mut node_path = "root".to_string();
+        let segments = self.parse_label_path(label_path)?;
+        
+        for (i, segment) in segments.iter().enumerate() {
+            let is_last = i + 1 == segments.len();
+            let (index, child) = match (value, segment) {
+                (ParamValue::Struct(s), LabelSegment::Key(hash)) => match s.fields.get_full(hash) {
+                    Some((index, _, child)) => (index, child),
+                    None if is_last => return Some(PatchTarget::Append { parent: node_path, hash: *hash }),
+                    None => return None,
+                },
+                (ParamValue::List(l), LabelSegment::Index(index)) => match l.values.get(*index) {
+                    Some(child) => (*index, child),
+                    None if is_last && *index == l.values.len() => return Some(PatchTarget::Append { parent: node_path, hash: 0 }),
+                    None => return None,
+                },
+                _ => return None,
+            };
+            node_path = format!("{}[{}]", node_path, index);
+            value = child;
+        }
+        
+        Some(PatchTarget::Existing(node_path))
+    }
+    
+    /// Split a label-path like "fighter_param[3]/walk_speed" into keys and list indices.
+    /// Keys may be labels or 0x hashes.
+    fn parse_label_path(&self, label_path: &str) -> Option<Vec<LabelSegment>> {
+        let mut segments = Vec::new();
+        if label_path.is_empty() {
+            return Some(segments);
+        }
+        
+        for part in label_path.split('/') {
+            let (key, indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+            if !key.is_empty() {
+                let hash = match key.strip_prefix("0x") {
+                    Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+                    None => self.hash_labels.get_hash(key).unwrap_or_else(|| self.hash_labels.string_to_hash40(key)),
+                };
+                segments.push(LabelSegment::Key(hash));
+            }
+            for index in indices.split('[').skip(1) {
+                segments.push(LabelSegment::Index(index.strip_suffix(']')?.parse().ok()?));
+            }
+        }
+        
+        Some(segments)
+    }
+
+    /// Group every struct in the tree by its field-key signature, most common layout first.
+    /// Keeps up to `max_samples` example paths per layout.
+    pub fn struct_layouts(&self, max_samples: usize) -> Vec<StructLayout> {
+        let mut layouts = IndexMap::new();
+        if let Some(root) = self.get_root() {
+            self.collect_struct_layouts(&root.value, "root".to_string(), max_samples, &mut layouts);
+        }
+        
+        let mut layouts: Vec<StructLayout> = layouts.into_values().collect();
+        layouts.sort_by_key(|layout| std::cmp::Reverse(layout.count));
+        layouts
+    }
+    
+    fn collect_struct_layouts(&self, value: &ParamValue, path: String, max_samples: usize, layouts: &mut IndexMap<u64, StructLayout>) {
+        match value {
+            ParamValue::Struct(s) => {
+                let layout = layouts.entry(self.calculate_struct_hash(s)).or_insert_with(|| StructLayout {
+                    fields: s.fields.keys().copied().collect(),
+                    count: 0,
+                    sample_paths: Vec::new(),
+                });
+                layout.count += 1;
+                if layout.sample_paths.len() < max_samples {
+                    layout.sample_paths.push(path.clone());
+                }
+                
+                for (i, child) in s.fields.values().enumerate() {
+                    self.collect_struct_layouts(child, format!("{}[{}]", path, i), max_samples, layouts);
+                }
+            }
+            ParamValue::List(l) => {
+                for (i, child) in l.values.iter().enumerate() {
+                    self.collect_struct_layouts(child, format!("{}[{}]", path, i), max_samples, layouts);
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// Write hash like paracobNET's WriteHash method
     fn write_hash(&self, hash: u64, hash_table: &mut Vec<u64>, hash_to_index: &mut HashMap<u64, usize>) {
         if !hash_to_index.contains_key(&hash) {
@@ -664,7 +1312,7 @@ impl ParamFile {
         ref_entries: &mut Vec<RefEntry>,
         struct_ref_entries: &mut HashMap<u64, usize>, // Maps struct hash to ref entry index
         unresolved_structs: &mut Vec<(usize, u64)>, // (position, struct_hash)
-        unresolved_strings: &mut Vec<(usize, String)>
+        unresolved_strings: &mut Vec<(usize, Vec<u8>)>
     ) -> Result<()> {
         match value {
             ParamValue::Bool(v) => {
@@ -701,28 +1349,30 @@ impl ParamFile {
             }
             ParamValue::Hash(v) => {
                 output.write_u8(9)?; // type
-                let index = hash_to_index.get(v).ok_or_else(|| anyhow!("Hash not found in hash table"))?;
-                output.write_u32::<LittleEndian>(*index as u32)?;
+                let index = hash_to_index.get(v).ok_or(ParamError::HashNotInTable { hash: *v, path: None })?;
+                output.write_u32::<LittleEndian>(offset_field(*index, "hash index")?)?;
             }
-            ParamValue::String(v) => {
+            ParamValue::String(_) | ParamValue::RawString(_) => {
                 output.write_u8(10)?; // type
+                let bytes = value.string_bytes().unwrap_or_default();
                 
-                // Add string to ref_entries if not already present (like AppendRefTableString)
+                // Add string to ref_entries if not already present (like AppendRefTableString).
+                // Equal strings, including "" (a lone NUL), share one entry and resolve to the same offset.
                 let string_exists = ref_entries.iter().any(|entry| {
-                    matches!(entry, RefEntry::String(s) if s == v)
+                    matches!(entry, RefEntry::String(s) if s == bytes)
                 });
                 if !string_exists {
-                    ref_entries.push(RefEntry::String(v.clone()));
+                    ref_entries.push(RefEntry::String(bytes.to_vec()));
                 }
                 
                 // Record unresolved string reference (position BEFORE writing placeholder)
-                unresolved_strings.push((output.len(), v.clone()));
+                unresolved_strings.push((output.len(), bytes.to_vec()));
                 output.write_i32::<LittleEndian>(0)?; // placeholder
             }
             ParamValue::List(l) => {
                 output.write_u8(11)?; // type
                 let start_pos = output.len() - 1;
-                output.write_i32::<LittleEndian>(l.values.len() as i32)?; // count
+                output.write_i32::<LittleEndian>(offset_field(l.values.len(), "list item count")?)?;
                 
                 // Write placeholder offsets
                 let offset_start = output.len();
@@ -732,10 +1382,11 @@ impl ParamFile {
                 
                 // Write actual values and update offsets
                 let mut offsets = Vec::new();
-                for item in &l.values {
+                for (index, item) in l.values.iter().enumerate() {
                     let item_offset = output.len() - start_pos;
-                    offsets.push(item_offset as u32);
-                    self.write_param_value(item, output, hash_to_index, ref_entries, struct_ref_entries, unresolved_structs, unresolved_strings)?;
+                    offsets.push(offset_field::<u32>(item_offset, "list item offset").map_err(|e| e.within(index))?);
+                    self.write_param_value(item, output, hash_to_index, ref_entries, struct_ref_entries, unresolved_structs, unresolved_strings)
+                        .map_err(|e| e.within(index))?;
                 }
                 
                 // Update the offset table
@@ -747,7 +1398,7 @@ impl ParamFile {
             ParamValue::Struct(s) => {
                 output.write_u8(12)?; // type
                 let start_pos = output.len() - 1;
-                output.write_i32::<LittleEndian>(s.fields.len() as i32)?; // size
+                output.write_i32::<LittleEndian>(offset_field(s.fields.len(), "struct field count")?)?;
                 
                 // Create a RefTableEntry for this struct (like paracobNET)
                 // We'll handle deduplication later in merge_ref_tables
@@ -763,18 +1414,25 @@ impl ParamFile {
                 unresolved_structs.push((output.len(), struct_id));
                 output.write_i32::<LittleEndian>(0)?; // placeholder for ref table offset
                 
-                // Sort fields by hash for consistent ordering (like paracobNET)
+                // Sort fields by hash value (paracobNET's `Nodes.OrderBy(x => x.Key)`), independent
+                // of the natural order used for hash collection
                 let mut sorted_fields: Vec<_> = s.fields.iter().collect();
                 sorted_fields.sort_by_key(|(hash, _)| *hash);
                 
                 // Write each field and record its offset in the RefTableEntry
                 let mut hash_offsets = Vec::new();
                 for (field_hash, field_value) in sorted_fields {
-                    let hash_index = *hash_to_index.get(field_hash).ok_or_else(|| anyhow!("Field hash not found"))?;
+                    let hash_index = *hash_to_index.get(field_hash).ok_or(ParamError::HashNotInTable { hash: *field_hash, path: None })?;
+                    // Paths index fields in their natural order, not the sorted write order
+                    let index = s.fields.get_index_of(field_hash).unwrap_or_default();
                     let param_offset = output.len() - start_pos;
-                    hash_offsets.push((hash_index as i32, param_offset as i32));
+                    hash_offsets.push((
+                        offset_field(hash_index, "hash index").map_err(|e| e.within(index))?,
+                        offset_field(param_offset, "struct field offset").map_err(|e| e.within(index))?,
+                    ));
                     
-                    self.write_param_value(field_value, output, hash_to_index, ref_entries, struct_ref_entries, unresolved_structs, unresolved_strings)?;
+                    self.write_param_value(field_value, output, hash_to_index, ref_entries, struct_ref_entries, unresolved_structs, unresolved_strings)
+                        .map_err(|e| e.within(index))?;
                 }
                 
                 // Update the RefTableEntry with the hash offsets
@@ -787,7 +1445,6 @@ impl ParamFile {
     }
     
     /// Calculate a hash for struct based on its field pattern (for deduplication)
-    #[allow(dead_code)]
     fn calculate_struct_hash(&self, s: &ParamStruct) -> u64 {
         let mut sorted_fields: Vec<_> = s.fields.keys().collect();
         sorted_fields.sort();
@@ -800,27 +1457,21 @@ impl ParamFile {
         std::hash::Hasher::finish(&hasher)
     }
     
-    /// Merge duplicate struct reference entries like paracobNET's MergeRefTables
-    #[allow(dead_code)]
+    /// Merge duplicate struct reference entries like paracobNET's MergeRefTables.
+    /// Entries are sorted by hash value when written, so comparing the pair lists
+    /// directly matches paracobNET's order-insensitive comparison.
     fn merge_ref_tables(
         &self, 
         ref_entries: &mut Vec<RefEntry>, 
         struct_ref_entries: &mut HashMap<u64, usize>,
-        _unresolved_structs: &mut Vec<(usize, u64)>
     ) {
         let mut i = 0;
         while i < ref_entries.len() {
             if let RefEntry::StructRef(current_entries) = &ref_entries[i] {
                 // Look for an earlier identical struct reference
-                let mut found_duplicate = None;
-                for j in 0..i {
-                    if let RefEntry::StructRef(earlier_entries) = &ref_entries[j] {
-                        if current_entries == earlier_entries {
-                            found_duplicate = Some(j);
-                            break;
-                        }
-                    }
-                }
+                let found_duplicate = ref_entries.iter().take(i).position(|earlier| {
+                    matches!(earlier, RefEntry::StructRef(earlier_entries) if earlier_entries == current_entries)
+                });
                 
                 if let Some(duplicate_index) = found_duplicate {
                     // Update struct_ref_entries to point to the earlier entry
@@ -842,8 +1493,498 @@ impl ParamFile {
     }
 }
 
+/// Narrow an offset, size or index to its field in the file, failing instead of silently
+/// truncating when the file has grown too large for it
+fn offset_field<T: TryFrom<usize>>(offset: usize, what: &'static str) -> Result<T> {
+    T::try_from(offset).map_err(|_| ParamError::OffsetOverflow { what, offset, path: None })
+}
+
+/// Escape text for use in XML element content or attribute values
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 impl Default for ParamFile {
     fn default() -> Self {
         Self::new()
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(fields: Vec<(u64, ParamValue)>) -> ParamValue {
+        ParamValue::Struct(ParamStruct { type_hash: 0, fields: fields.into_iter().collect() })
+    }
+
+    fn list(values: Vec<ParamValue>) -> ParamValue {
+        ParamValue::List(ParamList { values })
+    }
+
+    /// Struct with fields out of hash order, holding a list of structs that share a layout
+    fn sample() -> ParamValue {
+        let item = |n: i32, name: &str| fields(vec![
+            (0x0a_1111_1111, ParamValue::I32(n)),
+            (0x0b_2222_2222, ParamValue::String(name.to_string())),
+            (0x03_3333_3333, ParamValue::F32(1.5)),
+        ]);
+        fields(vec![
+            (0x05_0000_0005, ParamValue::Bool(true)),
+            (0x04_0000_0004, ParamValue::Hash(0x07_7777_7777)),
+            (0x06_0000_0006, list(vec![item(1, "a"), item(2, "b"), item(3, "a")])),
+            (0x02_0000_0002, fields(vec![(0x01_0000_0001, ParamValue::U8(7))])),
+        ])
+    }
+
+    fn file_from(value: ParamValue) -> ParamFile {
+        let mut file = ParamFile::new();
+        file.root = Some(ParamNode::new(String::new(), 0, value));
+        file
+    }
+
+    /// Same keys and values, whatever order struct fields are in
+    fn same_tree(value: &ParamValue, expected: &ParamValue) -> bool {
+        let mut removed = 0;
+        ParamFile::changed_part(value, expected, &mut removed).is_none() && removed == 0
+    }
+
+    fn opened(data: &[u8]) -> ParamFile {
+        let mut file = ParamFile::new();
+        file.open(data, "test.prc").unwrap();
+        file
+    }
+
+    #[test]
+    fn multi_field_struct_resaves_byte_identical() {
+        // Written out by hand the way paracobNET lays it out: the hash table follows the
+        // struct's own field order, fields and their ref entries follow hash order
+        let mut expected = b"paracobn".to_vec();
+        expected.extend_from_slice(&32i32.to_le_bytes());
+        expected.extend_from_slice(&24i32.to_le_bytes());
+        for hash in [0u64, 0x30, 0x10, 0x20] {
+            expected.extend_from_slice(&hash.to_le_bytes());
+        }
+        for (index, offset) in [(2i32, 9i32), (3, 11), (1, 13)] {
+            expected.extend_from_slice(&index.to_le_bytes());
+            expected.extend_from_slice(&offset.to_le_bytes());
+        }
+        expected.extend_from_slice(&[0x0c, 3, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend_from_slice(&[0x01, 1, 0x03, 7, 0x06, 1, 0, 0, 0]);
+        let value = fields(vec![
+            (0x30, ParamValue::I32(1)),
+            (0x10, ParamValue::Bool(true)),
+            (0x20, ParamValue::U8(7)),
+        ]);
+        assert_eq!(file_from(value).to_bytes().unwrap(), expected);
+        assert_eq!(opened(&expected).to_bytes().unwrap(), expected);
+
+        let bytes = file_from(sample()).to_bytes().unwrap();
+        let mut file = opened(&bytes);
+        assert_eq!(file.to_bytes().unwrap(), bytes);
+        let ordering = file.save_ordering().unwrap();
+        assert!(ordering.byte_identical && ordering.hash_table_kept);
+
+        // An edit changes the bytes but not which hashes the table holds
+        assert!(file.update_node_value("root[0]", ParamValue::Bool(false)));
+        let ordering = file.save_ordering().unwrap();
+        assert!(!ordering.byte_identical && ordering.hash_table_kept);
+    }
+
+    /// Header with the given table sizes followed by `rest`
+    fn header(hash_table_size: i32, ref_table_size: i32, rest: &[u8]) -> Vec<u8> {
+        let mut data = b"paracobn".to_vec();
+        data.extend(hash_table_size.to_le_bytes());
+        data.extend(ref_table_size.to_le_bytes());
+        data.extend(rest);
+        data
+    }
+
+    #[test]
+    fn list_root_opens_and_resaves() {
+        // .stdat/.stprm containers don't have to hold a struct at the root
+        let value = list(vec![ParamValue::I32(4), fields(vec![(0x10, ParamValue::Bool(true))])]);
+        let bytes = file_from(value.clone()).to_bytes().unwrap();
+        let file = opened(&bytes);
+        assert!(same_tree(&file.root.as_ref().unwrap().value, &value));
+        assert_eq!(file.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn zero_padding_is_kept_as_alignment() {
+        let bytes = file_from(sample()).to_bytes().unwrap();
+        let mut padded = bytes.clone();
+        padded.resize(bytes.len().next_multiple_of(4096), 0);
+
+        let mut file = opened(&padded);
+        assert_eq!(file.trailing_bytes(), Some(&TrailingBytes::Alignment { align: 4096, len: padded.len() - bytes.len() }));
+        assert_eq!(file.to_bytes().unwrap(), padded);
+        assert!(file.save_ordering().unwrap().byte_identical);
+
+        // Edits change the param data, and the padding still ends the file on the boundary
+        assert!(file.update_node_value("root[2][0][1]", ParamValue::String("a longer name".to_string())));
+        let saved = file.to_bytes().unwrap();
+        assert_eq!(saved.len() % 4096, 0);
+        assert!(same_tree(&opened(&saved).root.unwrap().value, &file.root.as_ref().unwrap().value));
+
+        file.keep_trailing_bytes = false;
+        assert!(!file.to_bytes().unwrap().len().is_multiple_of(4096));
+    }
+
+    #[test]
+    fn other_trailing_data_is_kept_as_raw_bytes() {
+        let bytes = file_from(sample()).to_bytes().unwrap();
+        let with_data = [bytes.as_slice(), b"\0\0tool signature"].concat();
+
+        let mut file = opened(&with_data);
+        assert_eq!(file.trailing_bytes(), Some(&TrailingBytes::Raw(b"\0\0tool signature".to_vec())));
+        assert_eq!(file.to_bytes().unwrap(), with_data);
+        file.keep_trailing_bytes = false;
+        assert_eq!(file.to_bytes().unwrap(), bytes);
+
+        // Zeros that don't reach an alignment boundary aren't padding
+        let zeros = if bytes.len() % 4 == 3 { 2 } else { 1 };
+        let with_zeros = [bytes.as_slice(), &vec![0; zeros]].concat();
+        assert_eq!(opened(&with_zeros).trailing_bytes(), Some(&TrailingBytes::Raw(vec![0; zeros])));
+        assert_eq!(opened(&bytes).trailing_bytes(), None);
+    }
+
+    #[test]
+    fn empty_input_is_rejected() {
+        let result = ParamFile::new().open(&[], "test.prc");
+        assert!(matches!(result, Err(ParamError::FileTooShort { len: 0 })));
+    }
+
+    #[test]
+    fn magic_only_input_is_rejected() {
+        let result = ParamFile::new().open(b"paracobn", "test.prc");
+        assert!(matches!(result, Err(ParamError::FileTooShort { len: 8 })));
+        // A whole header with empty tables still has no root param
+        let result = ParamFile::new().open(&header(0, 0, &[]), "test.prc");
+        assert!(matches!(result, Err(ParamError::NoParamData { param_start: 0x10, len: 0x10 })));
+    }
+
+    #[test]
+    fn table_sizes_past_the_end_are_rejected() {
+        let result = ParamFile::new().open(&header(100, 0, &[1, 1]), "test.prc");
+        assert!(matches!(result, Err(ParamError::InvalidTableSize { table: "hash table", size: 100, available: 2 })));
+        let result = ParamFile::new().open(&header(0, 100, &[1, 1]), "test.prc");
+        assert!(matches!(result, Err(ParamError::InvalidTableSize { table: "ref table", size: 100, available: 2 })));
+        let result = ParamFile::new().open(&header(0, -5, &[1, 1]), "test.prc");
+        assert!(matches!(result, Err(ParamError::InvalidTableSize { table: "ref table", size: -5, .. })));
+        // Tables that fill the file leave no room for the root param
+        let result = ParamFile::new().open(&header(0, 2, &[1, 1]), "test.prc");
+        assert!(matches!(result, Err(ParamError::NoParamData { .. })));
+        // The smallest valid file: a bool root
+        assert!(ParamFile::new().open(&header(0, 0, &[1, 1]), "test.prc").is_ok());
+    }
+
+    /// xorshift64, so failures reproduce from the seed
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn open_never_panics_on_corrupt_input() {
+        let valid = file_from(sample()).to_bytes().unwrap();
+        let mut state = 0x9E37_79B9_7F4A_7C15;
+        for _ in 0..20_000 {
+            // A valid file with a few bytes or whole i32 fields overwritten, sometimes cut short
+            let mut data = valid.clone();
+            for _ in 0..1 + next_random(&mut state) % 6 {
+                let pos = next_random(&mut state) as usize % (data.len() - 4);
+                let r = next_random(&mut state);
+                match r % 3 {
+                    0 => data[pos] = (r >> 8) as u8,
+                    1 => data[pos..pos + 4].copy_from_slice(&((r >> 16) as i32).to_le_bytes()),
+                    _ => data[pos..pos + 4].copy_from_slice(&(-((r % 1000) as i32)).to_le_bytes()),
+                }
+            }
+            if next_random(&mut state).is_multiple_of(10) {
+                data.truncate(next_random(&mut state) as usize % data.len());
+            }
+            // Ok or a clean Err; a panic fails the test. An Err leaves the open file as it was.
+            let mut file = opened(&valid);
+            if file.open(&data, "fuzz.prc").is_err() {
+                assert_eq!(file.get_filename(), "test.prc");
+                assert!(same_tree(&file.root.as_ref().unwrap().value, &sample()));
+                assert!(file.trailing_bytes().is_none() && file.unknown_types().is_empty());
+                assert_eq!(file.to_bytes().unwrap(), valid);
+            }
+            let _ = ParamFile::round_trips(&data);
+        }
+
+        for _ in 0..2_000 {
+            // Random bytes after a valid magic
+            let len = next_random(&mut state) as usize % 256;
+            let mut data = b"paracobn".to_vec();
+            data.extend((0..len).map(|_| next_random(&mut state) as u8));
+            let _ = ParamFile::new().open(&data, "fuzz.prc");
+        }
+    }
+
+    #[test]
+    fn offsets_that_loop_back_are_rejected() {
+        // A root list whose two items both point back at the list itself
+        let mut data = header(8, 0, &0u64.to_le_bytes());
+        data.push(11);
+        data.extend(2i32.to_le_bytes());
+        data.extend([0u32, 0u32].iter().flat_map(|offset| offset.to_le_bytes()));
+        assert!(ParamFile::new().open(&data, "loop.prc").is_err());
+    }
+
+    #[test]
+    fn offsets_near_u32_max_are_rejected() {
+        for offset in [u32::MAX, u32::MAX - 4, 0x8000_0000] {
+            // Root list whose one item lies about 4 GiB past the list
+            let mut data = header(8, 0, &0u64.to_le_bytes());
+            data.push(11);
+            data.extend(1i32.to_le_bytes());
+            data.extend(offset.to_le_bytes());
+            data.extend([1, 1]);
+            assert!(ParamFile::new().open(&data, "far.prc").is_err(), "list item offset 0x{:X}", offset);
+        }
+
+        // Struct and string offsets are signed, so their far ends are i32::MAX and i32::MIN
+        for offset in [i32::MAX, i32::MAX - 4, i32::MIN] {
+            // Root struct whose one field, then whose ref table, is that far out
+            let mut ref_table = 0i32.to_le_bytes().to_vec();
+            ref_table.extend(offset.to_le_bytes());
+            let mut data = header(8, 8, &[0u64.to_le_bytes().as_slice(), &ref_table].concat());
+            data.push(12);
+            data.extend(1i32.to_le_bytes());
+            data.extend(0i32.to_le_bytes());
+            data.extend([1, 1]);
+            assert!(ParamFile::new().open(&data, "far.prc").is_err(), "struct field offset 0x{:X}", offset);
+            let ref_offset = data.len() - 6;
+            data[ref_offset..ref_offset + 4].copy_from_slice(&offset.to_le_bytes());
+            assert!(ParamFile::new().open(&data, "far.prc").is_err(), "struct ref offset 0x{:X}", offset);
+
+            // Root string that far into the ref table
+            let mut data = header(0, 1, &[0]);
+            data.push(10);
+            data.extend(offset.to_le_bytes());
+            assert!(ParamFile::new().open(&data, "far.prc").is_err(), "string offset 0x{:X}", offset);
+        }
+    }
+
+    #[test]
+    fn byte_swapped_header_is_reported_as_wrong_endianness() {
+        let valid = file_from(sample()).to_bytes().unwrap();
+
+        let mut swapped = valid.clone();
+        swapped[8..12].reverse();
+        swapped[12..16].reverse();
+        let result = ParamFile::new().open(&swapped, "test.prc");
+        assert!(matches!(result, Err(ParamError::ByteSwapped { .. })));
+
+        let mut swapped_magic = valid.clone();
+        swapped_magic[..8].reverse();
+        let result = ParamFile::new().open(&swapped_magic, "test.prc");
+        assert!(matches!(result, Err(ParamError::ByteSwapped { .. })));
+
+        // Sizes that fit neither way round are just invalid
+        let mut garbage = valid.clone();
+        garbage[8..12].copy_from_slice(&[0x7F; 4]);
+        let result = ParamFile::new().open(&garbage, "test.prc");
+        assert!(matches!(result, Err(ParamError::InvalidTableSize { table: "hash table", .. })));
+    }
+
+    #[test]
+    fn renaming_onto_a_sibling_key_is_refused() {
+        let mut file = file_from(sample());
+        assert_eq!(file.key_collision("root[1]", 0x05_0000_0005), Some(0));
+        assert_eq!(file.key_collision("root[1]", 0x04_0000_0004), None);
+        // List items have no keys to collide with
+        assert_eq!(file.key_collision("root[2][0]", 0x0a_1111_1111), None);
+
+        // Refused edits don't count as changes
+        let revision = file.revision();
+        assert!(!file.update_node_key("root[1]", String::new(), 0x05_0000_0005));
+        assert!(!file.update_node_value("root[9]", ParamValue::Bool(false)));
+        assert_eq!(file.revision(), revision);
+        let ParamValue::Struct(root) = &file.get_root().unwrap().value else {
+            panic!("sample root is a struct");
+        };
+        assert_eq!(root.fields.len(), 4);
+        assert_eq!(root.fields.get(&0x05_0000_0005), Some(&ParamValue::Bool(true)));
+        assert_eq!(root.fields.get(&0x04_0000_0004), Some(&ParamValue::Hash(0x07_7777_7777)));
+
+        // A free key renames in place
+        assert!(file.update_node_key("root[1]", String::new(), 0x09_0000_0009));
+        assert_eq!(file.get_node_value("root[1]"), Some(ParamValue::Hash(0x07_7777_7777)));
+        assert_ne!(file.revision(), revision);
+    }
+
+    #[test]
+    fn walk_leaves_visits_every_leaf_in_tree_order() {
+        let mut file = file_from(sample());
+        file.hash_labels.add_label_for_hash(0x06_0000_0006, "items");
+        file.hash_labels.add_label_for_hash(0x0a_1111_1111, "count");
+
+        let mut leaves = Vec::new();
+        file.walk_leaves(|path, label_path, _| leaves.push((path.to_string(), label_path.to_string())));
+        // 2 root values, 3 in each of 3 list items and 1 in the nested struct
+        assert_eq!(leaves.len(), 12);
+        assert_eq!(leaves[0], ("root[0]".to_string(), "0x500000005".to_string()));
+        assert_eq!(leaves[2], ("root[2][0][0]".to_string(), "items[0]/count".to_string()));
+        assert_eq!(leaves[11], ("root[3][0]".to_string(), "0x200000002/0x100000001".to_string()));
+        for (path, label_path) in &leaves {
+            assert_eq!(file.label_path_of(path).as_ref(), Some(label_path));
+        }
+    }
+
+    /// Decodes param data following the offsets as the format defines them, independently
+    /// of `read_param`: strings and struct ref tables relative to the ref table start, list
+    /// items and struct fields relative to their container's type byte
+    struct SpecReader<'a> {
+        data: &'a [u8],
+        ref_start: usize,
+        string_offsets: Vec<(String, usize)>, // Ref table offset each string param points at
+    }
+
+    impl<'a> SpecReader<'a> {
+        fn decode(data: &'a [u8]) -> (ParamValue, Vec<(String, usize)>) {
+            let mut reader = SpecReader { data, ref_start: 0x10 + i32_at(data, 8), string_offsets: Vec::new() };
+            let root = reader.param(reader.ref_start + i32_at(data, 12));
+            (root, reader.string_offsets)
+        }
+
+        fn param(&mut self, pos: usize) -> ParamValue {
+            let data = self.data;
+            match data[pos] {
+                6 => ParamValue::I32(i32_at(data, pos + 1) as i32),
+                10 => {
+                    let offset = i32_at(data, pos + 1);
+                    let start = self.ref_start + offset;
+                    let end = start + data[start..].iter().position(|&b| b == 0).unwrap();
+                    self.string_offsets.push((String::from_utf8_lossy(&data[start..end]).to_string(), offset));
+                    ParamValue::string_from_bytes(&data[start..end])
+                }
+                11 => {
+                    let values = (0..i32_at(data, pos + 1))
+                        .map(|i| self.param(pos + i32_at(data, pos + 5 + i * 4)))
+                        .collect();
+                    ParamValue::List(ParamList { values })
+                }
+                12 => {
+                    let table = self.ref_start + i32_at(data, pos + 5);
+                    let fields = (0..i32_at(data, pos + 1))
+                        .map(|i| {
+                            let hash_index = i32_at(data, table + i * 8);
+                            let hash = u64::from_le_bytes(data[0x10 + hash_index * 8..0x18 + hash_index * 8].try_into().unwrap());
+                            (hash, self.param(pos + i32_at(data, table + i * 8 + 4)))
+                        })
+                        .collect();
+                    ParamValue::Struct(ParamStruct { type_hash: 0, fields })
+                }
+                other => panic!("fixture has no type {}", other),
+            }
+        }
+    }
+
+    fn i32_at(data: &[u8], pos: usize) -> usize {
+        i32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize
+    }
+
+    #[test]
+    fn identical_strings_share_one_ref_table_entry() {
+        let string = |text: &str| ParamValue::String(text.to_string());
+        let root = fields(vec![
+            (0x01_0000_0001, string("")),
+            (0x02_0000_0002, string("dup")),
+            (0x03_0000_0003, list(vec![
+                string(""),
+                string("dup"),
+                fields(vec![(0x04_0000_0004, string("")), (0x05_0000_0005, string("other"))]),
+            ])),
+            (0x06_0000_0006, string("dup")),
+        ]);
+
+        let bytes = file_from(root.clone()).to_bytes().unwrap();
+        let (decoded, string_offsets) = SpecReader::decode(&bytes);
+        assert!(same_tree(&decoded, &root));
+        assert_eq!(string_offsets.len(), 7);
+        for text in ["", "dup", "other"] {
+            let mut offsets: Vec<usize> = string_offsets.iter().filter(|(t, _)| t == text).map(|(_, offset)| *offset).collect();
+            offsets.dedup();
+            assert_eq!(offsets.len(), 1, "'{}' points at {:?}", text, offsets);
+        }
+        // Each string once with its terminator, then the two struct ref tables
+        assert_eq!(i32_at(&bytes, 12), 1 + 4 + 6 + 8 * 6);
+
+        let file = opened(&bytes);
+        assert!(same_tree(&file.get_root().unwrap().value, &root));
+        assert_eq!(file.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn strings_that_are_not_utf8_save_byte_exact() {
+        let cases: [&[u8]; 5] = [
+            &[0xFF, b'a', 0xC3],          // Invalid byte, then a lead byte with nothing after it
+            &[0xE2, 0x82],                // Truncated three-byte character
+            "x\u{F701}y".as_bytes(),     // Private-use characters are ordinary text
+            &[0xEF, 0x9C, 0x81, 0xFF],    // The same character next to an invalid byte
+            "plain ascii".as_bytes(),
+        ];
+        for raw in cases {
+            let value = ParamValue::string_from_bytes(raw);
+            assert_eq!(matches!(value, ParamValue::RawString(_)), std::str::from_utf8(raw).is_err());
+            assert_eq!(value.string_bytes(), Some(raw));
+
+            let bytes = file_from(fields(vec![(0x05_0000_0005, value.clone())])).to_bytes().unwrap();
+            let (_, string_offsets) = SpecReader::decode(&bytes);
+            let stored = 0x10 + i32_at(&bytes, 8) + string_offsets[0].1;
+            assert_eq!(&bytes[stored..stored + raw.len() + 1], [raw, &[0]].concat().as_slice());
+
+            let file = opened(&bytes);
+            assert_eq!(file.get_node_value("root[0]"), Some(value));
+            assert_eq!(file.to_bytes().unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn raw_strings_are_escaped_for_display_and_xml() {
+        let raw = ParamValue::RawString(vec![b'a', 0xFF, b'\\', 0xE2, 0x82]);
+        let text = "a\\xFF\\\\\\xE2\\x82";
+        let node = NodeRef { name: String::new(), hash: 0, value: &raw };
+        assert_eq!(node.get_value_string(None), text);
+        assert_eq!(unescape_raw_string(text).as_deref(), Ok(raw.string_bytes().unwrap()));
+        assert!(unescape_raw_string("a\\q").is_err());
+        assert!(unescape_raw_string("a\\x+1").is_err());
+        assert!(unescape_raw_string("\\x00").is_err());
+
+        let xml = file_from(fields(vec![(0x05, raw), (0x06, ParamValue::String("a\\xFF".to_string()))])).export_xml().unwrap();
+        assert!(xml.contains(&format!("escaped=\"true\">{}</string>", text)), "{}", xml);
+        // Real text is written as it is, backslashes included
+        assert!(xml.contains(">a\\xFF</string>") && xml.matches("escaped=").count() == 1, "{}", xml);
+    }
+
+    #[test]
+    fn nested_ref_offsets_follow_the_format() {
+        // Structs in lists in structs, three levels deep, with one string shared at every level
+        let leaf = |n: i32| fields(vec![(0x0a_1111_1111, ParamValue::String("shared".to_string())), (0x0c_0000_000c, ParamValue::I32(n))]);
+        let middle = |n: i32| fields(vec![
+            (0x0d_0000_000d, list(vec![leaf(n), leaf(n + 1)])),
+            (0x0e_0000_000e, ParamValue::String("shared".to_string())),
+        ]);
+        let root = fields(vec![
+            (0x0f_0000_000f, list(vec![middle(1), middle(10), fields(vec![])])),
+            (0x10_0000_0010, ParamValue::String("shared".to_string())),
+        ]);
+
+        let bytes = file_from(root.clone()).to_bytes().unwrap();
+        assert_eq!(bytes.windows(7).filter(|w| w == b"shared\0").count(), 1);
+
+        // Struct ref tables are sorted by hash, so the decoded field order differs
+        assert!(same_tree(&SpecReader::decode(&bytes).0, &root));
+        let file = opened(&bytes);
+        assert!(same_tree(&file.get_root().unwrap().value, &root));
+        assert_eq!(file.to_bytes().unwrap(), bytes);
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{bail, Result};
+use indexmap::IndexMap;
+use crate::hash_labels::HashLabels;
+
+/// File looked for next to ParamLabels.csv
+pub const RANGES_FILE_NAME: &str = "ParamRanges.csv";
+
+/// Known-safe bounds for a numeric field, inclusive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueRange {
+    pub min: f64,
+    pub max: f64,
+    pub step: Option<f64>, // Slider increment; values off the step grid are still accepted
+}
+
+impl ValueRange {
+    pub fn contains(&self, value: f64) -> bool {
+        value >= self.min && value <= self.max
+    }
+}
+
+impl std::fmt::Display for ValueRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} to {}", self.min, self.max)?;
+        if let Some(step) = self.step {
+            write!(f, " (step {})", step)?;
+        }
+        Ok(())
+    }
+}
+
+/// Valid value ranges for numeric fields, keyed by the field's hash. Fields without an entry
+/// are edited without limits.
+#[derive(Default)]
+pub struct ParamRanges {
+    ranges: IndexMap<u64, ValueRange>,
+}
+
+impl ParamRanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge `key,min,max[,step]` rows into the current set, replacing ranges of the same
+    /// field. The key is a 0x hash or a label; a first row whose bounds aren't numbers is
+    /// taken as a header. Returns the number of ranges read.
+    pub fn merge_from_csv(&mut self, csv_content: &str, labels: &HashLabels) -> Result<usize> {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(csv_content.as_bytes());
+
+        let mut parsed = Vec::new();
+        for (row, result) in reader.records().enumerate() {
+            let record = result?;
+            let key = record.get(0).unwrap_or("");
+            if key.is_empty() {
+                continue;
+            }
+
+            let number = |column: usize| record.get(column).filter(|text| !text.is_empty()).map(str::parse::<f64>);
+            let (min, max) = match (number(1), number(2)) {
+                (Some(Ok(min)), Some(Ok(max))) => (min, max),
+                _ if row == 0 => continue,
+                _ => bail!("Row {}: '{}' needs numeric min and max columns", row + 1, key),
+            };
+            let step = match number(3) {
+                None => None,
+                Some(Ok(step)) if step > 0.0 => Some(step),
+                Some(_) => bail!("Row {}: step for '{}' must be a positive number", row + 1, key),
+            };
+            if !min.is_finite() || !max.is_finite() || min > max {
+                bail!("Row {}: range {} to {} for '{}' is not valid", row + 1, min, max, key);
+            }
+
+            let hash = labels.parse_hash_or_label(key).unwrap_or_else(|_| labels.string_to_hash40(key));
+            parsed.push((hash, ValueRange { min, max, step }));
+        }
+
+        // Only merged once the whole file parsed, so a bad row doesn't leave half a file loaded
+        let count = parsed.len();
+        self.ranges.extend(parsed);
+        Ok(count)
+    }
+
+    pub fn get(&self, hash: u64) -> Option<ValueRange> {
+        self.ranges.get(&hash).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+}
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use crate::hash_labels::HashLabels;
 use indexmap::IndexMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ParamValue {
     Bool(bool),
     I8(i8),
@@ -14,27 +14,497 @@ pub enum ParamValue {
     F32(f32),
     Hash(u64),
     String(String),
+    RawString(Vec<u8>), // String whose bytes aren't valid UTF-8, kept exactly as read
     List(ParamList),
     Struct(ParamStruct),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ParamStruct {
     pub type_hash: u64,
     pub fields: IndexMap<u64, ParamValue>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ParamList {
     pub values: Vec<ParamValue>,
 }
 
+/// How integer values are shown (the stored type never changes)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntDisplay {
+    Native,
+    FlippedSign, // Signed types shown as unsigned and vice versa
+    Hex,
+}
+
+/// Arithmetic applied to many numeric values at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathOp {
+    Add,
+    Subtract,
+    Multiply,
+    Set,
+}
+
+/// How the value filter compares leaves with the value typed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueComparison {
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+}
+
+impl ValueComparison {
+    pub const ALL: [ValueComparison; 4] = [
+        ValueComparison::Equal, ValueComparison::NotEqual, ValueComparison::Greater, ValueComparison::Less,
+    ];
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            ValueComparison::Equal => "=",
+            ValueComparison::NotEqual => "≠",
+            ValueComparison::Greater => ">",
+            ValueComparison::Less => "<",
+        }
+    }
+}
+
+impl ParamValue {
+    /// Apply `op` with `operand` to a numeric value. Integers are rounded and clamped to
+    /// their type's range (the flag says whether clamping happened). None for non-numeric
+    /// values or a float result that isn't finite.
+    pub fn apply_math(&self, op: MathOp, operand: f64) -> Option<(ParamValue, bool)> {
+        let current = self.as_f64()?;
+        
+        let result = match op {
+            MathOp::Add => current + operand,
+            MathOp::Subtract => current - operand,
+            MathOp::Multiply => current * operand,
+            MathOp::Set => operand,
+        };
+        
+        if let ParamValue::F32(_) = self {
+            let result = result as f32;
+            return result.is_finite().then_some((ParamValue::F32(result), false));
+        }
+        
+        let (_, width, signed) = self.integer_bits()?;
+        let (min, max) = if signed {
+            (-(1i64 << (width - 1)), (1i64 << (width - 1)) - 1)
+        } else {
+            (0, (1i64 << width) - 1)
+        };
+        let rounded = result.round();
+        let clamped = rounded.clamp(min as f64, max as f64);
+        let value = self.with_integer_bits(clamped as i64 as u32)?;
+        Some((value, clamped != rounded))
+    }
+
+    /// Numeric value of an integer or float
+    pub fn as_f64(&self) -> Option<f64> {
+        if let ParamValue::F32(v) = *self {
+            return Some(v as f64);
+        }
+        let (bits, width, signed) = self.integer_bits()?;
+        Some(if signed {
+            (((bits << (32 - width)) as i32) >> (32 - width)) as f64
+        } else {
+            bits as f64
+        })
+    }
+
+    /// Raw bits, bit width and signedness of an integer value
+    fn integer_bits(&self) -> Option<(u32, u32, bool)> {
+        match *self {
+            ParamValue::I8(v) => Some((v as u8 as u32, 8, true)),
+            ParamValue::U8(v) => Some((v as u32, 8, false)),
+            ParamValue::I16(v) => Some((v as u16 as u32, 16, true)),
+            ParamValue::U16(v) => Some((v as u32, 16, false)),
+            ParamValue::I32(v) => Some((v as u32, 32, true)),
+            ParamValue::U32(v) => Some((v, 32, false)),
+            _ => None,
+        }
+    }
+
+    /// Same integer variant holding the given raw bits
+    fn with_integer_bits(&self, bits: u32) -> Option<ParamValue> {
+        match self {
+            ParamValue::I8(_) => Some(ParamValue::I8(bits as u8 as i8)),
+            ParamValue::U8(_) => Some(ParamValue::U8(bits as u8)),
+            ParamValue::I16(_) => Some(ParamValue::I16(bits as u16 as i16)),
+            ParamValue::U16(_) => Some(ParamValue::U16(bits as u16)),
+            ParamValue::I32(_) => Some(ParamValue::I32(bits as i32)),
+            ParamValue::U32(_) => Some(ParamValue::U32(bits)),
+            _ => None,
+        }
+    }
+
+    /// Format an integer value in the given display mode; None for non-integers
+    pub fn format_integer(&self, mode: IntDisplay) -> Option<String> {
+        let (bits, width, signed) = self.integer_bits()?;
+        Some(match mode {
+            IntDisplay::Hex => format!("0x{:0digits$X}", bits, digits = (width / 4) as usize),
+            IntDisplay::FlippedSign if signed => bits.to_string(),
+            IntDisplay::FlippedSign => (((bits << (32 - width)) as i32) >> (32 - width)).to_string(),
+            IntDisplay::Native => match self {
+                ParamValue::I8(v) => v.to_string(),
+                ParamValue::I16(v) => v.to_string(),
+                ParamValue::I32(v) => v.to_string(),
+                _ => bits.to_string(),
+            },
+        })
+    }
+
+    /// Parse an edited integer into the same variant. Accepts any display form:
+    /// signed or unsigned decimal of the same width, or 0x hex.
+    pub fn parse_integer(&self, input: &str) -> Option<ParamValue> {
+        let (_, width, _) = self.integer_bits()?;
+        let input = input.trim();
+        let max_unsigned = (1u64 << width) - 1;
+        
+        let bits = if let Some(hex) = input.strip_prefix("0x").or_else(|| input.strip_prefix("0X")) {
+            u64::from_str_radix(hex, 16).ok().filter(|v| *v <= max_unsigned)?
+        } else {
+            let value = input.parse::<i64>().ok()?;
+            let min_signed = -(1i64 << (width - 1));
+            if value < min_signed || value > max_unsigned as i64 {
+                return None;
+            }
+            value as u64 & max_unsigned
+        };
+        
+        self.with_integer_bits(bits as u32)
+    }
+
+    /// NaN or infinite float
+    pub fn is_special_float(&self) -> bool {
+        matches!(self, ParamValue::F32(v) if !v.is_finite())
+    }
+
+    /// Parse an edited float. Accepts decimals, `NaN`, `inf`, `-inf`, exact `0x` bit
+    /// patterns, and the flagged display form `NaN (0x7FC00000)`. A plain `NaN` keeps
+    /// the current bits when the value is already NaN so its payload isn't lost.
+    pub fn parse_float(&self, input: &str) -> Option<ParamValue> {
+        let ParamValue::F32(current) = *self else {
+            return None;
+        };
+        let input = input.trim();
+        
+        // In "NaN (0x7FC00000)" the bits are authoritative
+        let hex = match input.split_once('(') {
+            Some((_, bits)) => Some(bits.trim_end_matches(')').trim()),
+            None => Some(input).filter(|i| i.starts_with("0x") || i.starts_with("0X")),
+        };
+        if let Some(hex) = hex {
+            let digits = hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X"))?;
+            return u32::from_str_radix(digits, 16).ok().map(|bits| ParamValue::F32(f32::from_bits(bits)));
+        }
+        
+        // Rust's float parsing already accepts inf, -inf, infinity and NaN in any case
+        let value = if input.eq_ignore_ascii_case("nan") && current.is_nan() {
+            current
+        } else {
+            input.parse::<f32>().ok()?
+        };
+        Some(ParamValue::F32(value))
+    }
+
+    /// `value` converted to this leaf's type, for pasting a copied value. The same type is
+    /// taken as is; numbers convert between integer and float types when the value fits
+    /// exactly (floats only when whole for integer types), and bools paste as 0/1 and from
+    /// 0/1. Err says why the value can't be applied.
+    pub fn coerce_from(&self, value: &ParamValue) -> Result<ParamValue, String> {
+        if matches!(self, ParamValue::List(_) | ParamValue::Struct(_)) || matches!(value, ParamValue::List(_) | ParamValue::Struct(_)) {
+            return Err(format!("only leaf values can be pasted ({} into {})", value.type_name(), self.type_name()));
+        }
+        // String and RawString are both the String type
+        if self.type_name() == value.type_name() {
+            return Ok(value.clone());
+        }
+        
+        let number = match *value {
+            ParamValue::Bool(v) => Some(if v { 1.0 } else { 0.0 }),
+            _ => value.as_f64(),
+        };
+        let mismatch = || format!("a {} can't be pasted into a {}", value.type_name(), self.type_name());
+        let Some(number) = number.filter(|_| !matches!(self, ParamValue::Hash(_) | ParamValue::String(_) | ParamValue::RawString(_))) else {
+            return Err(mismatch());
+        };
+        
+        match self {
+            ParamValue::Bool(_) if number == 0.0 || number == 1.0 => Ok(ParamValue::Bool(number == 1.0)),
+            ParamValue::Bool(_) => Err(format!("{} isn't 0 or 1, so it can't be a Bool", number)),
+            ParamValue::F32(_) => Ok(ParamValue::F32(number as f32)),
+            _ if number.fract() != 0.0 || !number.is_finite() => Err(format!("{} isn't a whole number, so it can't be a {}", number, self.type_name())),
+            _ => match self.apply_math(MathOp::Set, number) {
+                Some((converted, false)) => Ok(converted),
+                _ => Err(format!("{} doesn't fit in a {}", number, self.type_name())),
+            },
+        }
+    }
+
+    /// Whether this leaf compares to `operand` as asked. The operand is read as the leaf's own
+    /// type: a number (0x hex for integers), true/false or 0/1 for bools, a label or 0x hash
+    /// for hash40s, text for strings. Greater and less only apply to integers and floats;
+    /// false when the operand can't be read as the leaf's type.
+    pub fn compare_with(&self, comparison: ValueComparison, operand: &str, hash_labels: &HashLabels) -> bool {
+        use std::cmp::Ordering;
+        
+        let operand = operand.trim();
+        let ordering = match self {
+            ParamValue::F32(v) => match self.parse_float(operand) {
+                Some(ParamValue::F32(wanted)) if v.is_nan() && wanted.is_nan() => Some(Ordering::Equal),
+                Some(ParamValue::F32(wanted)) => v.partial_cmp(&wanted),
+                _ => return false,
+            },
+            _ if self.integer_bits().is_some() => {
+                // Also read as a plain number, so "< 300" works on a byte
+                let wanted = match self.parse_integer(operand) {
+                    Some(parsed) => parsed.as_f64(),
+                    None => operand.parse::<f64>().ok().filter(|v| v.is_finite()),
+                };
+                let Some(wanted) = wanted else {
+                    return false;
+                };
+                self.as_f64().and_then(|v| v.partial_cmp(&wanted))
+            }
+            _ => {
+                let equal = match self {
+                    ParamValue::Bool(v) => match operand.to_ascii_lowercase().as_str() {
+                        "true" | "1" => *v,
+                        "false" | "0" => !*v,
+                        _ => return false,
+                    },
+                    ParamValue::Hash(v) => match hash_labels.parse_hash_or_label(operand) {
+                        Ok(hash) => *v == hash,
+                        Err(_) => return false,
+                    },
+                    ParamValue::String(v) => v == operand,
+                    ParamValue::RawString(v) => escape_raw_string(v) == operand,
+                    _ => return false,
+                };
+                return match comparison {
+                    ValueComparison::Equal => equal,
+                    ValueComparison::NotEqual => !equal,
+                    ValueComparison::Greater | ValueComparison::Less => false,
+                };
+            }
+        };
+        match comparison {
+            ValueComparison::Equal => ordering == Some(Ordering::Equal),
+            ValueComparison::NotEqual => ordering != Some(Ordering::Equal),
+            ValueComparison::Greater => ordering == Some(Ordering::Greater),
+            ValueComparison::Less => ordering == Some(Ordering::Less),
+        }
+    }
+
+    /// Every name `type_name` returns, in type byte order
+    pub const TYPE_NAMES: [&'static str; 12] = [
+        "Bool", "SByte", "Byte", "Short", "UShort", "Int", "UInt", "Float", "Hash40", "String", "List", "Struct",
+    ];
+
+    /// Zero, empty or false value of the type `type_name` names
+    pub fn default_of_type(type_name: &str) -> Option<ParamValue> {
+        Some(match type_name {
+            "Bool" => ParamValue::Bool(false),
+            "SByte" => ParamValue::I8(0),
+            "Byte" => ParamValue::U8(0),
+            "Short" => ParamValue::I16(0),
+            "UShort" => ParamValue::U16(0),
+            "Int" => ParamValue::I32(0),
+            "UInt" => ParamValue::U32(0),
+            "Float" => ParamValue::F32(0.0),
+            "Hash40" => ParamValue::Hash(0),
+            "String" => ParamValue::String(String::new()),
+            "List" => ParamValue::List(ParamList { values: Vec::new() }),
+            "Struct" => ParamValue::Struct(ParamStruct { type_hash: 0, fields: IndexMap::new() }),
+            _ => return None,
+        })
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ParamValue::Bool(_) => "Bool",
+            ParamValue::I8(_) => "SByte",
+            ParamValue::U8(_) => "Byte", 
+            ParamValue::I16(_) => "Short",
+            ParamValue::U16(_) => "UShort",
+            ParamValue::I32(_) => "Int",
+            ParamValue::U32(_) => "UInt",
+            ParamValue::F32(_) => "Float",
+            ParamValue::Hash(_) => "Hash40",
+            ParamValue::String(_) | ParamValue::RawString(_) => "String",
+            ParamValue::List(_) => "List",
+            ParamValue::Struct(_) => "Struct",
+        }
+    }
+
+    /// A leaf as its type byte followed by its little-endian payload. Hash40 and string
+    /// payloads are the value itself (8-byte hash, the string's bytes) rather than the table index
+    /// or offset a file stores, so the bytes mean the same thing outside any one file.
+    pub fn leaf_bytes(&self) -> Option<Vec<u8>> {
+        let (type_byte, payload) = match self {
+            ParamValue::Bool(v) => (1, vec![*v as u8]),
+            ParamValue::I8(v) => (2, v.to_le_bytes().to_vec()),
+            ParamValue::U8(v) => (3, vec![*v]),
+            ParamValue::I16(v) => (4, v.to_le_bytes().to_vec()),
+            ParamValue::U16(v) => (5, v.to_le_bytes().to_vec()),
+            ParamValue::I32(v) => (6, v.to_le_bytes().to_vec()),
+            ParamValue::U32(v) => (7, v.to_le_bytes().to_vec()),
+            ParamValue::F32(v) => (8, v.to_le_bytes().to_vec()),
+            ParamValue::Hash(v) => (9, v.to_le_bytes().to_vec()),
+            ParamValue::String(v) => (10, v.as_bytes().to_vec()),
+            ParamValue::RawString(v) => (10, v.clone()),
+            ParamValue::List(_) | ParamValue::Struct(_) => return None,
+        };
+        let mut bytes = vec![type_byte];
+        bytes.extend(payload);
+        Some(bytes)
+    }
+
+    /// Parse bytes in the `leaf_bytes` layout. The payload must be exactly the size of its
+    /// type; bools must be 0 or 1 and strings free of NUL bytes.
+    pub fn from_leaf_bytes(bytes: &[u8]) -> Result<ParamValue, String> {
+        let (&type_byte, payload) = bytes.split_first().ok_or("No type byte")?;
+        let expected = match type_byte {
+            1..=3 => 1,
+            4 | 5 => 2,
+            6..=8 => 4,
+            9 => 8,
+            10 => payload.len(),
+            11 | 12 => return Err("Lists and structs can't be edited as raw bytes".to_string()),
+            _ => return Err(format!("Unknown type byte {}", type_byte)),
+        };
+        if payload.len() != expected {
+            return Err(format!("Type {} needs {} payload bytes, got {}", type_byte, expected, payload.len()));
+        }
+
+        let word = || [payload[0], payload[1], payload[2], payload[3]];
+        Ok(match type_byte {
+            1 => match payload[0] {
+                0 | 1 => ParamValue::Bool(payload[0] == 1),
+                other => return Err(format!("Bool must be 0 or 1, got {}", other)),
+            },
+            2 => ParamValue::I8(payload[0] as i8),
+            3 => ParamValue::U8(payload[0]),
+            4 => ParamValue::I16(i16::from_le_bytes([payload[0], payload[1]])),
+            5 => ParamValue::U16(u16::from_le_bytes([payload[0], payload[1]])),
+            6 => ParamValue::I32(i32::from_le_bytes(word())),
+            7 => ParamValue::U32(u32::from_le_bytes(word())),
+            8 => ParamValue::F32(f32::from_le_bytes(word())),
+            9 => {
+                let mut hash = [0; 8];
+                hash.copy_from_slice(payload);
+                ParamValue::Hash(u64::from_le_bytes(hash))
+            },
+            _ => {
+                if payload.contains(&0) {
+                    return Err("Strings can't contain NUL bytes".to_string());
+                }
+                ParamValue::string_from_bytes(payload)
+            }
+        })
+    }
+
+    /// A string value from its bytes: String when they are UTF-8, RawString otherwise
+    pub fn string_from_bytes(bytes: &[u8]) -> ParamValue {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => ParamValue::String(text.to_string()),
+            Err(_) => ParamValue::RawString(bytes.to_vec()),
+        }
+    }
+
+    /// The bytes a String or RawString is written as
+    pub fn string_bytes(&self) -> Option<&[u8]> {
+        match self {
+            ParamValue::String(v) => Some(v.as_bytes()),
+            ParamValue::RawString(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl ParamStruct {
+    /// Convert to a list of the field values, dropping the keys.
+    /// Only offered when the struct is empty or all fields share one type.
+    pub fn to_list(&self) -> Option<ParamList> {
+        let mut values = self.fields.values();
+        if let Some(first) = values.next() {
+            let first_type = std::mem::discriminant(first);
+            if values.any(|v| std::mem::discriminant(v) != first_type) {
+                return None;
+            }
+        }
+
+        Some(ParamList {
+            values: self.fields.values().cloned().collect(),
+        })
+    }
+}
+
+impl ParamList {
+    /// Stable sort of struct items by one field's value: numbers (and bools) numerically
+    /// before strings and hash40 labels, which sort alphabetically. Items without the
+    /// field, or that aren't structs, go last.
+    pub fn sort_by_field(&mut self, field_hash: u64, hash_labels: &HashLabels) {
+        use std::cmp::Ordering;
+        
+        let sort_key = |item: &ParamValue| -> Option<Result<f64, String>> {
+            let ParamValue::Struct(s) = item else {
+                return None;
+            };
+            Some(match s.fields.get(&field_hash)? {
+                ParamValue::Bool(v) => Ok(*v as u8 as f64),
+                ParamValue::String(v) => Err(v.clone()),
+                ParamValue::RawString(v) => Err(escape_raw_string(v)),
+                ParamValue::Hash(v) => Err(hash_labels.hash_to_string(*v)),
+                value => Ok(value.as_f64()?),
+            })
+        };
+        
+        let mut keyed: Vec<_> = self.values.drain(..).map(|item| (sort_key(&item), item)).collect();
+        keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Some(Ok(x)), Some(Ok(y))) => x.total_cmp(y),
+            (Some(Err(x)), Some(Err(y))) => x.cmp(y),
+            (Some(Ok(_)), Some(Err(_))) | (Some(_), None) => Ordering::Less,
+            (Some(Err(_)), Some(Ok(_))) | (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        self.values = keyed.into_iter().map(|(_, item)| item).collect();
+    }
+
+    /// Convert to a struct, flattening a list of single-field structs into their fields.
+    /// Returns None when an item isn't a single-field struct or two items share a key.
+    pub fn to_struct(&self) -> Option<ParamStruct> {
+        let mut fields = IndexMap::new();
+        for item in &self.values {
+            match item {
+                ParamValue::Struct(s) if s.fields.len() == 1 => {
+                    let (hash, value) = s.fields.first()?;
+                    if fields.insert(*hash, value.clone()).is_some() {
+                        return None;
+                    }
+                }
+                _ => return None,
+            }
+        }
+
+        Some(ParamStruct {
+            type_hash: 0x0,
+            fields,
+        })
+    }
+}
+
+/// A value together with its key. Used for the file root and for clipboard / undo snapshots;
+/// nodes below the root are viewed through `NodeRef` instead of being stored.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParamNode {
     pub name: String,
     pub hash: u64,
     pub value: ParamValue,
-    pub children: Vec<ParamNode>,
 }
 
 impl ParamNode {
@@ -43,7 +513,6 @@ impl ParamNode {
             name,
             hash,
             value,
-            children: Vec::new(),
         }
     }
     
@@ -54,91 +523,78 @@ impl ParamNode {
         self.hash = new_hash;
     }
     
-    /// Update the value of this node
-    #[allow(dead_code)]
-    pub fn update_value(&mut self, new_value: ParamValue) {
-        self.value = new_value;
-        // Rebuild children if it's a struct or list
-        self.children.clear();
-        match &self.value {
-            ParamValue::Struct(_s) => {
-                // Would need hash_labels reference to rebuild children properly
-                // For now, just clear children - they'll be rebuilt when needed
-            }
-            ParamValue::List(_l) => {
-                // Similar issue - would need to rebuild children
-            }
-            _ => {}
-        }
+    pub fn from_value(hash: u64, value: ParamValue, hash_labels: &HashLabels) -> Self {
+        Self::new(hash_labels.hash_to_string(hash), hash, value)
     }
-    
-    /// Get mutable reference to child at path
-    #[allow(dead_code)]
-    pub fn get_child_mut(&mut self, path: &[usize]) -> Option<&mut ParamNode> {
-        if path.is_empty() {
-            return Some(self);
-        }
-        
-        let first = path[0];
-        if first >= self.children.len() {
-            return None;
+
+    /// Borrowed view of this node for walking its children
+    pub fn view(&self) -> NodeRef<'_> {
+        NodeRef {
+            name: self.name.clone(),
+            hash: self.hash,
+            value: &self.value,
         }
-        
-        if path.len() == 1 {
-            Some(&mut self.children[first])
-        } else {
-            self.children[first].get_child_mut(&path[1..])
+    }
+}
+
+/// Borrowed view of a node in a `ParamValue` tree. Names are resolved from the labels
+/// when a view is made, so there is no separate display tree to keep in sync.
+#[derive(Debug, Clone)]
+pub struct NodeRef<'a> {
+    pub name: String,
+    pub hash: u64, // Struct field key, or the index for list items
+    pub value: &'a ParamValue,
+}
+
+impl<'a> NodeRef<'a> {
+    pub fn child_count(&self) -> usize {
+        match self.value {
+            ParamValue::Struct(s) => s.fields.len(),
+            ParamValue::List(l) => l.values.len(),
+            _ => 0,
         }
     }
-    
-    pub fn from_value(hash: u64, value: ParamValue, hash_labels: &HashLabels) -> Self {
-        let name = hash_labels.hash_to_string(hash);
-        let mut node = Self::new(name, hash, value.clone());
-        
-        // Build children for struct and list types
-        match &value {
+
+    pub fn has_children(&self) -> bool {
+        self.child_count() > 0
+    }
+
+    pub fn child(&self, index: usize, hash_labels: &HashLabels) -> Option<NodeRef<'a>> {
+        match self.value {
             ParamValue::Struct(s) => {
-                for (field_hash, field_value) in &s.fields {
-                    let child = Self::from_value(*field_hash, field_value.clone(), hash_labels);
-                    node.children.push(child);
-                }
-            },
+                let (hash, value) = s.fields.get_index(index)?;
+                Some(NodeRef { name: hash_labels.hash_to_string(*hash), hash: *hash, value })
+            }
             ParamValue::List(l) => {
-                for (index, item_value) in l.values.iter().enumerate() {
-                    let mut child = Self::from_value(index as u64, item_value.clone(), hash_labels);
-                    child.name = format!("[{}]", index); // Override name for list items
-                    node.children.push(child);
-                }
-            },
-            _ => {}
+                let value = l.values.get(index)?;
+                Some(NodeRef { name: format!("[{}]", index), hash: index as u64, value })
+            }
+            _ => None,
         }
-        
-        node
+    }
+
+    pub fn children(&self, hash_labels: &HashLabels) -> Vec<NodeRef<'a>> {
+        (0..self.child_count())
+            .filter_map(|index| self.child(index, hash_labels))
+            .collect()
+    }
+
+    /// Owned copy of this node (clones the subtree)
+    pub fn to_owned_node(&self) -> ParamNode {
+        ParamNode::new(self.name.clone(), self.hash, self.value.clone())
     }
 
     pub fn is_expandable(&self) -> bool {
-        !self.children.is_empty() || matches!(self.value, ParamValue::Struct(_) | ParamValue::List(_))
+        matches!(self.value, ParamValue::Struct(_) | ParamValue::List(_))
     }
 
     pub fn get_type_name(&self) -> &'static str {
-        match &self.value {
-            ParamValue::Bool(_) => "Bool",
-            ParamValue::I8(_) => "SByte",
-            ParamValue::U8(_) => "Byte", 
-            ParamValue::I16(_) => "Short",
-            ParamValue::U16(_) => "UShort",
-            ParamValue::I32(_) => "Int",
-            ParamValue::U32(_) => "UInt",
-            ParamValue::F32(_) => "Float",
-            ParamValue::Hash(_) => "Hash40",
-            ParamValue::String(_) => "String",
-            ParamValue::List(_) => "List",
-            ParamValue::Struct(_) => "Struct",
-        }
+        self.value.type_name()
     }
 
-    pub fn get_value_string(&self) -> String {
-        match &self.value {
+    /// Value text; floats are rounded to `float_digits` significant digits when given
+    pub fn get_value_string(&self, float_digits: Option<usize>) -> String {
+        match self.value {
             ParamValue::Bool(v) => v.to_string(),
             ParamValue::I8(v) => v.to_string(),
             ParamValue::U8(v) => v.to_string(),
@@ -146,18 +602,84 @@ impl ParamNode {
             ParamValue::U16(v) => v.to_string(),
             ParamValue::I32(v) => v.to_string(),
             ParamValue::U32(v) => v.to_string(),
-            ParamValue::F32(v) => v.to_string(),
+            ParamValue::F32(v) => match float_digits {
+                Some(digits) => format_float_significant(*v, digits),
+                None => v.to_string(),
+            },
             ParamValue::Hash(v) => format!("0x{:X}", v),
             ParamValue::String(v) => v.clone(),
+            ParamValue::RawString(v) => escape_raw_string(v),
             ParamValue::List(l) => format!("List ({} items)", l.values.len()),
             ParamValue::Struct(s) => format!("Struct ({} fields)", s.fields.len()),
         }
     }
 
-    pub fn get_value_string_with_labels(&self, hash_labels: &crate::hash_labels::HashLabels) -> String {
-        match &self.value {
+    pub fn get_value_string_with_labels(&self, hash_labels: &HashLabels, float_digits: Option<usize>) -> String {
+        match self.value {
             ParamValue::Hash(v) => hash_labels.hash_to_string(*v),
-            _ => self.get_value_string(),
+            _ => self.get_value_string(float_digits),
+        }
+    }
+}
+
+/// A RawString as text: its valid UTF-8 as is with `\` doubled, and every other byte as `\xNN`
+pub fn escape_raw_string(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(&chunk.valid().replace('\\', "\\\\"));
+        for byte in chunk.invalid() {
+            text.push_str(&format!("\\x{:02X}", byte));
+        }
+    }
+    text
+}
+
+/// Bytes of text written the way `escape_raw_string` writes them. Err for a lone `\`, an
+/// escape other than `\\` and `\xNN`, or a NUL byte, which would end the string in a file.
+pub fn unescape_raw_string(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('\\') {
+        bytes.extend_from_slice(&rest.as_bytes()[..start]);
+        let escape = &rest[start..];
+        let hex_byte = escape.strip_prefix("\\x")
+            .and_then(|after| Some((after.get(..2)?, after.get(2..)?)))
+            .filter(|(hex, _)| hex.bytes().all(|b| b.is_ascii_hexdigit()));
+        if let Some(after) = escape.strip_prefix("\\\\") {
+            bytes.push(b'\\');
+            rest = after;
+        } else if let Some((hex, after)) = hex_byte {
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|e| e.to_string())?);
+            rest = after;
+        } else {
+            let shown: String = escape.chars().take(4).collect();
+            return Err(format!("Invalid escape '{}' - use \\\\ for a backslash or \\xNN for a byte", shown));
         }
     }
-} 
\ No newline at end of file
+    bytes.extend_from_slice(rest.as_bytes());
+    if bytes.contains(&0) {
+        return Err("Strings can't contain NUL bytes".to_string());
+    }
+    Ok(bytes)
+}
+
+/// Round a float to `digits` significant digits for display, dropping trailing zeros.
+/// The integer part is never rounded away, and NaN/Inf are shown as is.
+pub fn format_float_significant(value: f32, digits: usize) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return value.to_string();
+    }
+    
+    let magnitude = (value.abs() as f64).log10().floor() as i32;
+    let decimals = (digits.max(1) as i32 - 1 - magnitude).max(0) as usize;
+    let rounded = format!("{:.*}", decimals, value);
+    let rounded = if rounded.contains('.') {
+        rounded.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &rounded
+    };
+    
+    // Never longer than the exact form, e.g. 0.5 stays "0.5"
+    let exact = value.to_string();
+    if exact.len() <= rounded.len() { exact } else { rounded.to_string() }
+}
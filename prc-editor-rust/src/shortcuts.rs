@@ -0,0 +1,331 @@
+use eframe::egui::{Event, InputState, Key, KeyboardShortcut, ModifierNames, Modifiers};
+use indexmap::IndexMap;
+
+/// Something the keyboard can trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Command {
+    NavigateUp,
+    NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    Back,
+    Forward,
+    CollapseSubtree,
+    ExpandSubtree,
+    ToggleExpand,
+    Search,
+    NextMatch,
+    PreviousMatch,
+    NextUnlabeled,
+    Rename,
+    AddSibling,
+    Delete,
+    Duplicate,
+    Undo,
+    Redo,
+    UndoAll,
+    RedoAll,
+    Copy,
+    Cut,
+    Paste,
+    PasteValues,
+    PasteToParent,
+    CopyValue,
+    PasteValue,
+    Save,
+    LabelEditor,
+    ShortcutsHelp,
+}
+
+/// When a command's shortcut is honoured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandScope {
+    Always, // Even while typing in a text field
+    NotEditing, // Unless a value, key or description is being edited
+    NoTextFocus, // Only when no text field has keyboard focus, so typing and text copy/paste are left alone
+}
+
+/// How a command is listed, stored in the preferences file and scoped
+pub struct CommandInfo {
+    pub command: Command,
+    pub id: &'static str, // Preferences key, after "shortcut."
+    pub category: &'static str,
+    pub action: &'static str,
+    pub scope: CommandScope,
+    pub essential: bool, // Also shown in the corner overlay
+}
+
+const fn info(command: Command, id: &'static str, category: &'static str, action: &'static str, scope: CommandScope, essential: bool) -> CommandInfo {
+    CommandInfo { command, id, category, action, scope, essential }
+}
+
+/// Every command in display order
+pub const COMMANDS: &[CommandInfo] = &[
+    info(Command::NavigateUp, "navigate_up", "Navigation", "Select previous row", CommandScope::NotEditing, true),
+    info(Command::NavigateDown, "navigate_down", "Navigation", "Select next row", CommandScope::NotEditing, true),
+    info(Command::NavigateLeft, "navigate_left", "Navigation", "Collapse / go to parent", CommandScope::NotEditing, false),
+    info(Command::NavigateRight, "navigate_right", "Navigation", "Expand / go to first child", CommandScope::NotEditing, false),
+    info(Command::Back, "back", "Navigation", "Back", CommandScope::NotEditing, true),
+    info(Command::Forward, "forward", "Navigation", "Forward", CommandScope::NotEditing, false),
+    info(Command::CollapseSubtree, "collapse_subtree", "Navigation", "Collapse subtree", CommandScope::NotEditing, false),
+    info(Command::ExpandSubtree, "expand_subtree", "Navigation", "Expand subtree", CommandScope::NotEditing, false),
+    info(Command::ToggleExpand, "toggle_expand", "Navigation", "Expand/collapse", CommandScope::NotEditing, true),
+    info(Command::Search, "search", "Search", "Search", CommandScope::Always, true),
+    info(Command::NextMatch, "next_match", "Search", "Next match", CommandScope::NotEditing, false),
+    info(Command::PreviousMatch, "previous_match", "Search", "Previous match", CommandScope::NotEditing, false),
+    info(Command::NextUnlabeled, "next_unlabeled", "Search", "Next unlabeled", CommandScope::NotEditing, false),
+    info(Command::Rename, "rename", "Editing", "Rename node", CommandScope::NotEditing, true),
+    info(Command::AddSibling, "add_sibling", "Editing", "Add sibling field", CommandScope::NoTextFocus, false),
+    info(Command::Delete, "delete", "Editing", "Delete node", CommandScope::NotEditing, true),
+    info(Command::Duplicate, "duplicate", "Editing", "Duplicate node", CommandScope::NotEditing, false),
+    info(Command::Undo, "undo", "Editing", "Undo", CommandScope::NotEditing, true),
+    info(Command::Redo, "redo", "Editing", "Redo", CommandScope::NotEditing, true),
+    info(Command::UndoAll, "undo_all", "Editing", "Undo all", CommandScope::NotEditing, false),
+    info(Command::RedoAll, "redo_all", "Editing", "Redo all", CommandScope::NotEditing, false),
+    info(Command::Copy, "copy", "Clipboard", "Copy node", CommandScope::NoTextFocus, true),
+    info(Command::Cut, "cut", "Clipboard", "Cut node", CommandScope::NoTextFocus, false),
+    info(Command::Paste, "paste", "Clipboard", "Paste node", CommandScope::NoTextFocus, true),
+    info(Command::PasteValues, "paste_values", "Clipboard", "Paste values only", CommandScope::NoTextFocus, false),
+    info(Command::PasteToParent, "paste_to_parent", "Clipboard", "Paste to parent", CommandScope::NotEditing, false),
+    info(Command::CopyValue, "copy_value", "Clipboard", "Copy leaf value only", CommandScope::NoTextFocus, false),
+    info(Command::PasteValue, "paste_value", "Clipboard", "Paste leaf value only", CommandScope::NoTextFocus, false),
+    info(Command::Save, "save", "File", "Save file", CommandScope::NotEditing, true),
+    info(Command::LabelEditor, "label_editor", "Labels", "Label editor", CommandScope::NoTextFocus, false),
+    info(Command::ShortcutsHelp, "shortcuts_help", "Help", "All keyboard shortcuts", CommandScope::NoTextFocus, false),
+];
+
+const fn chord(modifiers: Modifiers, key: Key) -> KeyboardShortcut {
+    KeyboardShortcut::new(modifiers, key)
+}
+
+const CTRL_SHIFT: Modifiers = Modifiers { shift: true, ..Modifiers::CTRL };
+const CTRL_ALT: Modifiers = Modifiers { alt: true, ..Modifiers::CTRL };
+
+fn default_bindings(command: Command) -> Vec<KeyboardShortcut> {
+    let none = Modifiers::NONE;
+    match command {
+        Command::NavigateUp => vec![chord(none, Key::ArrowUp)],
+        Command::NavigateDown => vec![chord(none, Key::ArrowDown)],
+        Command::NavigateLeft => vec![chord(none, Key::ArrowLeft)],
+        Command::NavigateRight => vec![chord(none, Key::ArrowRight)],
+        Command::Back => vec![chord(Modifiers::ALT, Key::ArrowLeft)],
+        Command::Forward => vec![chord(Modifiers::ALT, Key::ArrowRight)],
+        Command::CollapseSubtree => vec![chord(Modifiers::SHIFT, Key::ArrowLeft)],
+        Command::ExpandSubtree => vec![chord(Modifiers::SHIFT, Key::ArrowRight)],
+        Command::ToggleExpand => vec![chord(none, Key::Enter)],
+        Command::Search => vec![chord(Modifiers::CTRL, Key::F)],
+        Command::NextMatch => vec![chord(none, Key::F3)],
+        Command::PreviousMatch => vec![chord(Modifiers::SHIFT, Key::F3)],
+        Command::NextUnlabeled => vec![chord(Modifiers::CTRL, Key::U)],
+        Command::Rename => vec![chord(none, Key::F2)],
+        Command::AddSibling => vec![chord(none, Key::Insert)],
+        Command::Delete => vec![chord(none, Key::Delete)],
+        Command::Duplicate => vec![chord(Modifiers::CTRL, Key::D)],
+        Command::Undo => vec![chord(Modifiers::CTRL, Key::Z)],
+        Command::Redo => vec![chord(Modifiers::CTRL, Key::Y)],
+        Command::UndoAll => vec![chord(CTRL_SHIFT, Key::Z)],
+        Command::RedoAll => vec![chord(CTRL_SHIFT, Key::Y)],
+        Command::Copy => vec![chord(Modifiers::CTRL, Key::C), chord(Modifiers::CTRL, Key::Insert)],
+        Command::Cut => vec![chord(Modifiers::CTRL, Key::X)],
+        Command::Paste => vec![chord(Modifiers::CTRL, Key::V), chord(Modifiers::SHIFT, Key::Insert)],
+        Command::PasteValues => vec![chord(CTRL_SHIFT, Key::V)],
+        Command::PasteToParent => vec![chord(Modifiers::CTRL, Key::P)],
+        Command::CopyValue => vec![chord(CTRL_ALT, Key::C)],
+        Command::PasteValue => vec![chord(CTRL_ALT, Key::V)],
+        Command::Save => vec![chord(Modifiers::CTRL, Key::S)],
+        Command::LabelEditor => vec![chord(Modifiers::CTRL, Key::L)],
+        // "?" is Shift+/ on most layouts but unshifted on some
+        Command::ShortcutsHelp => vec![chord(Modifiers::SHIFT, Key::Questionmark), chord(none, Key::Questionmark)],
+    }
+}
+
+pub fn command_info(command: Command) -> &'static CommandInfo {
+    COMMANDS.iter().find(|info| info.command == command).expect("every command is listed in COMMANDS")
+}
+
+/// Display text such as "Ctrl+Shift+V"; parsed back by `parse_shortcut`
+pub fn format_shortcut(shortcut: &KeyboardShortcut) -> String {
+    let mut text = ModifierNames::NAMES.format(&shortcut.modifiers, false);
+    if !text.is_empty() {
+        text.push('+');
+    }
+    text.push_str(match shortcut.logical_key {
+        // Arrow symbols aren't in every font and "+" would read as a separator
+        Key::ArrowUp | Key::ArrowDown | Key::ArrowLeft | Key::ArrowRight | Key::Plus => shortcut.logical_key.name(),
+        key => key.symbol_or_name(),
+    });
+    text
+}
+
+/// Parse "Ctrl+Alt+Shift+Key" as written by `format_shortcut`
+pub fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+    let key = Key::from_name(parts.pop()?)?;
+    let mut modifiers = Modifiers::NONE;
+    for part in parts {
+        match part {
+            "Ctrl" | "Cmd" => modifiers = modifiers | Modifiers::CTRL,
+            "Alt" | "Option" => modifiers = modifiers | Modifiers::ALT,
+            "Shift" => modifiers = modifiers | Modifiers::SHIFT,
+            _ => return None,
+        }
+    }
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+fn same_shortcut(a: &KeyboardShortcut, b: &KeyboardShortcut) -> bool {
+    a.logical_key == b.logical_key && a.modifiers.matches_exact(b.modifiers) && b.modifiers.matches_exact(a.modifiers)
+}
+
+/// Key chords pressed this frame. The system clipboard chords reach egui as Copy/Cut/Paste
+/// events instead of key presses (and Paste only when the system clipboard has text), so
+/// they are turned back into the chord that produces them. Windows sends Ctrl+Insert as the
+/// same Copy event as Ctrl+C, so both are reported.
+pub fn pressed_chords(input: &InputState) -> Vec<KeyboardShortcut> {
+    let modifiers = input.modifiers;
+    let mut chords = Vec::new();
+    for event in &input.events {
+        let keys: &[Key] = match event {
+            Event::Key { key, pressed: true, modifiers, .. } => {
+                chords.push(KeyboardShortcut::new(*modifiers, *key));
+                continue;
+            }
+            Event::Copy if modifiers.command => &[Key::C, Key::Insert],
+            Event::Copy => &[Key::Copy],
+            Event::Cut if modifiers.command => &[Key::X],
+            Event::Cut if modifiers.shift => &[Key::Delete],
+            Event::Cut => &[Key::Cut],
+            Event::Paste(_) if modifiers.command => &[Key::V],
+            Event::Paste(_) if modifiers.shift => &[Key::Insert],
+            Event::Paste(_) => &[Key::Paste],
+            _ => continue,
+        };
+        chords.extend(keys.iter().map(|key| KeyboardShortcut::new(modifiers, *key)));
+    }
+    chords
+}
+
+/// The binding to store for a pressed chord: Ctrl and the platform command key both become Ctrl
+pub fn binding_for(pressed: KeyboardShortcut) -> KeyboardShortcut {
+    let modifiers = pressed.modifiers;
+    let mut binding = Modifiers::NONE;
+    if modifiers.ctrl || modifiers.command {
+        binding = binding | Modifiers::CTRL;
+    }
+    if modifiers.alt {
+        binding = binding | Modifiers::ALT;
+    }
+    if modifiers.shift {
+        binding = binding | Modifiers::SHIFT;
+    }
+    KeyboardShortcut::new(binding, pressed.logical_key)
+}
+
+/// The key bindings of every command, starting from the defaults. Modifiers must match
+/// exactly, so Ctrl+Shift+Z never also counts as Ctrl+Z.
+pub struct ShortcutRegistry {
+    bindings: IndexMap<Command, Vec<KeyboardShortcut>>,
+}
+
+impl Default for ShortcutRegistry {
+    fn default() -> Self {
+        Self {
+            bindings: COMMANDS.iter().map(|info| (info.command, default_bindings(info.command))).collect(),
+        }
+    }
+}
+
+impl ShortcutRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bindings(&self, command: Command) -> &[KeyboardShortcut] {
+        self.bindings.get(&command).map_or(&[], Vec::as_slice)
+    }
+
+    /// Bindings as "Ctrl+C / Ctrl+Insert", or "unbound"
+    pub fn keys_text(&self, command: Command) -> String {
+        let bindings = self.bindings(command);
+        if bindings.is_empty() {
+            return "unbound".to_string();
+        }
+        bindings.iter().map(format_shortcut).collect::<Vec<_>>().join(" / ")
+    }
+
+    pub fn set_bindings(&mut self, command: Command, bindings: Vec<KeyboardShortcut>) {
+        self.bindings.insert(command, bindings);
+    }
+
+    /// Add `shortcut` to the command's bindings unless it is already one of them
+    pub fn add_binding(&mut self, command: Command, shortcut: KeyboardShortcut) {
+        let bindings = self.bindings.entry(command).or_default();
+        if !bindings.iter().any(|existing| same_shortcut(existing, &shortcut)) {
+            bindings.push(shortcut);
+        }
+    }
+
+    pub fn reset(&mut self, command: Command) {
+        self.bindings.insert(command, default_bindings(command));
+    }
+
+    pub fn reset_all(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_default(&self, command: Command) -> bool {
+        let defaults = default_bindings(command);
+        let bindings = self.bindings(command);
+        bindings.len() == defaults.len() && bindings.iter().zip(&defaults).all(|(a, b)| same_shortcut(a, b))
+    }
+
+    /// Other commands sharing one of `command`'s bindings
+    pub fn conflicts_with(&self, command: Command) -> Vec<Command> {
+        let own = self.bindings(command);
+        self.bindings.iter()
+            .filter(|(other, bindings)| **other != command && bindings.iter().any(|b| own.iter().any(|o| same_shortcut(o, b))))
+            .map(|(other, _)| *other)
+            .collect()
+    }
+
+    /// Number of commands that share a binding with another command
+    pub fn conflict_count(&self) -> usize {
+        COMMANDS.iter().filter(|info| !self.conflicts_with(info.command).is_empty()).count()
+    }
+
+    /// Commands bound to any of `chords`, each once, in `COMMANDS` order
+    pub fn commands_for(&self, chords: &[KeyboardShortcut]) -> Vec<Command> {
+        COMMANDS.iter()
+            .map(|info| info.command)
+            .filter(|command| {
+                self.bindings(*command).iter().any(|binding| {
+                    chords.iter().any(|pressed| pressed.logical_key == binding.logical_key && pressed.modifiers.matches_exact(binding.modifiers))
+                })
+            })
+            .collect()
+    }
+
+    /// Preferences lines (`shortcut.<id>=Ctrl+C|Ctrl+Insert`) for commands that differ from
+    /// the defaults; an empty value means unbound
+    pub fn to_preferences(&self) -> String {
+        COMMANDS.iter()
+            .filter(|info| !self.is_default(info.command))
+            .map(|info| {
+                let keys: Vec<String> = self.bindings(info.command).iter().map(format_shortcut).collect();
+                format!("shortcut.{}={}\n", info.id, keys.join("|"))
+            })
+            .collect()
+    }
+
+    /// Apply one `shortcut.<id>` preferences value; unknown ids and unparseable keys are ignored
+    pub fn load_preference(&mut self, id: &str, value: &str) {
+        let Some(info) = COMMANDS.iter().find(|info| info.id == id) else {
+            return;
+        };
+        let bindings = value.split('|')
+            .filter(|text| !text.trim().is_empty())
+            .filter_map(parse_shortcut)
+            .collect();
+        self.set_bindings(info.command, bindings);
+    }
+}
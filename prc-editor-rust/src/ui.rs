@@ -1,9 +1,15 @@
-use crate::param_file::ParamFile;
+use crate::hash_labels::{HashLabels, LabelSortOrder, LabelState};
+use crate::param_descriptions::{ParamDescriptions, DESCRIPTIONS_FILE_NAME};
+use crate::param_ranges::{ParamRanges, ValueRange, RANGES_FILE_NAME};
+use crate::node_notes::{NodeNotes, NOTES_FILE_NAME};
+use crate::param_file::{ParamFile, ParamPatch, PatchTarget, SaveOrdering, StructLayout};
+use crate::shortcuts::{self, command_info, format_shortcut, Command, CommandInfo, CommandScope, ShortcutRegistry, COMMANDS};
 use crate::param_types::*;
 use eframe::egui;
 use rfd::FileDialog;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
 
 pub struct PrcEditorApp {
     param_file: ParamFile,
@@ -13,6 +19,7 @@ pub struct PrcEditorApp {
     tree_width: f32,
     show_label_editor: bool,
     label_editor_filter: String,
+    label_mismatches_only: bool, // Label editor lists only labels whose text doesn't hash to their key
     editing_value: Option<(String, String)>, // (node_path, current_edit_value)
     new_label_input: String, // For adding new labels
     new_hash_input: String, // For adding labels to existing hashes
@@ -21,12 +28,306 @@ pub struct PrcEditorApp {
     clipboard: Option<String>, // Copied node path
     clipboard_data: Option<ParamNode>, // Actual copied node data
     cut_mode: bool, // Whether the clipboard operation was cut (vs copy)
+    value_clipboard: Option<ParamValue>, // Leaf value from Copy Value, kept apart from the node clipboard
     // show_shortcuts_help removed - shortcuts are now always visible
     param_labels_path: Option<String>, // Path to the ParamLabels.csv file
+    labels_save_failure: Option<String>, // Why ParamLabels.csv couldn't be written; shows the prompt to move it
+    keep_unwritable_labels: bool, // User chose to keep an unwritable labels file this session; failures only reach the status bar
     tree_items: Vec<String>, // Flattened list of visible tree items for navigation
     selected_index: Option<usize>, // Index in tree_items for keyboard navigation
     undo_stack: Vec<UndoAction>, // Stack of undo actions
     redo_stack: Vec<UndoAction>, // Stack of redo actions
+    highlight_unlabeled: bool, // Mark nodes whose key or hash40 value has no exact label (persisted)
+    highlight_editing: bool, // Mark the tree row of the node whose value or key is being edited (persisted)
+    unlabeled_paths: Vec<String>, // Paths of unlabeled nodes in tree order (refreshed while highlighting)
+    unlabeled_paths_key: Option<(u64, u64)>, // Tree and label revisions `unlabeled_paths` was collected at
+    auto_expand_on_select: bool, // Expand expandable nodes when they become selected (persisted)
+    without_labels: bool, // User chose to open files without a ParamLabels.csv
+    search_query: String, // Tree search text (matches names and values; case follows `case_sensitive_search`)
+    search_matches: Vec<String>, // Cached matching paths in tree order
+    search_cache_key: Option<(String, bool, u64, u64)>, // Query, case sensitivity, and tree and label revisions the cached matches belong to
+    value_filter_comparison: ValueComparison, // How the value filter compares leaves with its operand
+    value_filter_operand: String, // Value the tree is filtered by; empty with no type chosen turns the filter off
+    value_filter_type: Option<&'static str>, // Leaf type the value filter is limited to
+    value_filter_paths: Option<(usize, HashSet<String>)>, // Number of leaves passing the value filter, and their paths with every ancestor
+    value_filter_key: Option<(ValueComparison, String, Option<&'static str>, u64, u64)>, // Filter, and tree and label revisions `value_filter_paths` belongs to
+    scroll_to_selected: bool, // Scroll the tree to the selected node on the next frame
+    undo_limit: usize, // Maximum number of undo actions kept, see MAX_UNDO_LIMIT (persisted)
+    raw_byte_editing: bool, // Show the editable type byte + payload row for leaves in the details panel (persisted)
+    node_size_cache: Option<(String, u64, usize)>, // (path, tree revision, serialized size) for the details panel
+    save_ordering_cache: Option<(u64, Option<SaveOrdering>)>, // (tree revision, comparison with the opened file) for the status bar
+    round_trip_pending: Option<mpsc::Receiver<Result<bool, String>>>, // Background round-trip check of the file last opened
+    round_trip_result: Option<Result<bool, String>>, // Whether the opened file re-opened to the same tree after a save; Err says why it couldn't be checked
+    value_chart_cache: Option<(String, u64, Vec<f64>)>, // (path, tree revision, child values) for the details panel chart
+    reference_file: Option<ParamFile>, // Read-only second file for looking up values at the same label-path
+    naming_hash: Option<(u64, String)>, // Hash being given a label and the label typed so far
+    naming_suggestions: Option<(u64, Vec<String>)>, // Guessed labels for the hash being named
+    compact_mode: bool, // Hide the shortcuts overlay and verbose status bar extras (persisted)
+    compact_density: bool, // Tighter row height and spacing in the tree and editor grids (persisted)
+    group_struct_fields: bool, // Show struct fields in collapsible sections by category or label prefix (persisted)
+    show_tree_values: bool, // Show leaf values next to their type in the tree (persisted)
+    fold_single_child: bool, // Draw chains of single-child containers on one tree row (persisted)
+    flag_special_floats: bool, // Show NaN/Inf floats with their raw bits, highlighted (persisted)
+    cut_origin: Option<(String, usize)>, // Parent path and index of a cut node that hasn't been pasted yet
+    show_cut_warning: bool, // Ask whether to restore or discard an unpasted cut node
+    int_display: IntDisplay, // How integer values are displayed and edited (persisted)
+    float_digits: Option<usize>, // Significant digits floats are shown with; None shows the exact value (persisted)
+    full_precision_paths: HashSet<String>, // Float fields shown exactly regardless of float_digits
+    verify_after_save: bool, // Re-open saved files before replacing the original (persisted)
+    write_xml_sidecar: bool, // Also write a .prcx next to every binary save (persisted)
+    nav_back: Vec<String>, // Previously selected paths, most recent last (Alt+Left)
+    nav_forward: Vec<String>, // Paths left by going back, most recent last (Alt+Right)
+    canonical_labels_path: Option<String>, // Master labels CSV used by "Reset to Canonical" (persisted)
+    show_session_labels: bool, // Show the window listing labels added this session
+    show_reset_labels_confirm: bool, // Ask before replacing labels with the canonical CSV
+    focus_search: bool, // Move keyboard focus to the search box on the next frame (Ctrl+F)
+    list_display_keys: HashMap<String, u64>, // List path -> field hash used to label its items instead of [index]
+    case_sensitive_search: bool, // Tree search and label filter match case exactly (persisted)
+    hash_locations: Option<(u64, Vec<String>)>, // Label editor "Find in file" result: hash and the paths using it
+    selected_label_hashes: HashSet<u64>, // Label Editor rows ticked for bulk delete
+    label_select_anchor: Option<u64>, // Last ticked Label Editor row, start of a shift-click range
+    show_bulk_label_delete_confirm: bool, // Ask before deleting the ticked labels
+    show_struct_layouts: bool, // Show the struct layout report window
+    struct_layouts: Option<(u64, Vec<StructLayout>)>, // Layout report and the tree revision it was built from
+    layout_sort: (LayoutSort, bool), // Layout report sort column and whether it is descending
+    multi_selection: Vec<String>, // Paths added with Ctrl+click, in the order they were added
+    show_unknown_types: bool, // Show the window listing unknown-type params from the last open
+    show_preferences: bool, // Show the Preferences window
+    patch_conflicts: Vec<String>, // Entries of the last applied patch that didn't fit this file; shown until closed
+    show_apply_math: bool, // Show the "Apply Math to Selection" window
+    math_op: MathOp, // Operation chosen in the math window
+    math_operand: String, // Operand typed in the math window
+    pending_key_overwrite: Option<(String, String, u64)>, // Rename (path, new name, new hash) onto an existing sibling key, awaiting confirmation
+    wrap_in_struct: Option<(String, String)>, // Node path being wrapped in a new struct and the key typed for it inside that struct
+    pinned_details: Option<String>, // Node path the details panel stays on regardless of selection
+    opened_file: Option<(PathBuf, Option<FileStamp>)>, // Path the current file was opened from and its state on disk when last read or written
+    external_change_save: Option<PathBuf>, // Save target that changed on disk since it was opened, waiting for the user to decide
+    sequence_labels: Option<(String, String)>, // Container whose unlabeled children get numbered labels, and the base name typed
+    duplicate_item: Option<(String, String, Option<u64>)>, // List item being duplicated, the count typed, and the field numbered up in each copy
+    clear_children: Option<String>, // Large container waiting for confirmation before its children are removed
+    normalize_hashes: Option<Vec<(String, u64, u64)>>, // (path, stored hash, labelled hash) rewrites waiting for confirmation
+    recent_edits: Vec<String>, // Paths of nodes whose value or key was last changed, most recent first
+    session_log: Vec<String>, // Opens, saves, edits, undos and redos this session, oldest first, for Export Edit Log
+    session_start: std::time::Instant, // Log entries are stamped with the time since this
+    descriptions: ParamDescriptions, // Field descriptions shown as tooltips on keys
+    descriptions_path: Option<String>, // ParamDescriptions.csv that edited descriptions are written to
+    ranges: ParamRanges, // Valid bounds for numeric fields; typed edits outside them are rejected
+    range_drag: Option<(String, ParamValue)>, // Path being changed with its range slider and its value before, undone as one step
+    add_field: Option<(String, String)>, // Struct the inline Add Field form adds to and the key typed
+    add_field_type: &'static str, // Type of the last field added; the form starts on it
+    add_field_focus: bool, // Move keyboard focus to the Add Field key box on the next frame
+    show_shortcuts: bool, // Keyboard Shortcuts window open
+    shortcuts_filter: String, // Text the shortcuts window is filtered by
+    shortcuts: ShortcutRegistry, // Key bindings of every command; changed ones are persisted
+    notes: NodeNotes, // Review notes on nodes of the open file, keyed by label-path
+    note_nodes: HashMap<String, String>, // Node path -> label-path of each note that resolves in the open file
+    note_nodes_key: Option<(u64, u64, u64)>, // Tree, label and notes revisions `note_nodes` was resolved at
+    editing_note: Option<(String, String)>, // Node path whose note is being written and the text typed
+    show_notes: bool, // Show the window listing every note
+    persist_notes: bool, // Keep notes per file in the config folder and load them on open (persisted)
+    folder_files: Option<(PathBuf, Vec<(PathBuf, u64)>)>, // Folder listed by File > Open Folder and its param files with their sizes
+    folder_filter: String, // Text the folder's file list is filtered by
+    capturing_shortcut: Option<(Command, bool)>, // Command waiting for its next key chord in the shortcuts window, and whether the chord is added rather than replacing its bindings
+}
+
+/// Column the struct layout report is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LayoutSort {
+    Instances,
+    Fields,
+    Savings,
+}
+
+/// Upper bound for the undo history preference
+const MAX_UNDO_LIMIT: usize = 1000;
+
+/// Number of previous selections kept for back/forward navigation
+const MAX_NAV_HISTORY: usize = 200;
+
+/// Number of entries kept in the recently edited list
+const MAX_RECENT_EDITS: usize = 10;
+
+/// Most copies "Duplicate N Times" appends in one go
+const MAX_DUPLICATE_COUNT: usize = 500;
+
+/// Containers with more children than this ask before "Clear Children" empties them
+const CONFIRM_CLEAR_CHILDREN: usize = 20;
+
+/// Oldest session log entries are dropped beyond this many
+const MAX_SESSION_LOG: usize = 10_000;
+
+/// Longest opened-file path the status bar shows before shortening it from the front
+const MAX_STATUS_PATH_CHARS: usize = 60;
+
+/// Extensions of the param files the editor opens, lowercase
+const PARAM_EXTENSIONS: [&str; 6] = ["prc", "prcx", "stdat", "stdatx", "stprm", "stprmx"];
+
+/// Truncate a string to at most `max_chars` characters, ending with "..." when shortened.
+/// Works on char boundaries so multibyte names never panic.
+fn truncate_chars(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    
+    let kept: String = s.chars().take(max_chars.saturating_sub(3)).collect();
+    format!("{}...", kept)
+}
+
+/// Keep the last `max_chars` characters of a path, starting with "..." when shortened, so the
+/// file name stays visible
+fn truncate_chars_start(s: &str, max_chars: usize) -> String {
+    let count = s.chars().count();
+    if count <= max_chars {
+        return s.to_string();
+    }
+    
+    let kept: String = s.chars().skip(count - max_chars.saturating_sub(3)).collect();
+    format!("...{}", kept)
+}
+
+/// Just the file name of `path`, for logs that shouldn't reveal the folders it sits in
+fn log_file_name(path: &Path) -> String {
+    path.file_name().map_or_else(|| "unnamed file".to_string(), |name| name.to_string_lossy().to_string())
+}
+
+/// File name without its extension, or `fallback` when there is none
+fn file_stem_or<'a>(filename: &'a str, fallback: &'a str) -> &'a str {
+    Path::new(filename).file_stem().and_then(|s| s.to_str()).unwrap_or(fallback)
+}
+
+/// The source's param extension (lowercased), or "prc" when it has none of them
+fn param_extension(filename: &str) -> String {
+    Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .filter(|e| PARAM_EXTENSIONS.contains(&e.as_str()))
+        .unwrap_or_else(|| "prc".to_string())
+}
+
+/// Param files directly inside `dir` (not in subfolders) with their sizes, sorted by name
+fn list_param_files(dir: &Path) -> std::io::Result<Vec<(PathBuf, u64)>> {
+    let mut files: Vec<(PathBuf, u64)> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|ft| ft.is_file()))
+        .map(|entry| entry.path())
+        .filter(|path| path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| PARAM_EXTENSIONS.contains(&e.to_lowercase().as_str())))
+        .map(|path| {
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            (path, size)
+        })
+        .collect();
+    files.sort_by_key(|(path, _)| log_file_name(path).to_lowercase());
+    Ok(files)
+}
+
+/// Byte count as B, KB or MB for file lists
+fn format_file_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Default name for saving a copy: `<stem>_<suffix>.<ext>`, keeping the source's param extension
+fn default_save_name(filename: &str, suffix: &str) -> String {
+    format!("{}_{}.{}", file_stem_or(filename, "params"), suffix, param_extension(filename))
+}
+
+/// Whether `path` is `ancestor` itself or lies somewhere inside its subtree
+fn is_same_or_descendant(path: &str, ancestor: &str) -> bool {
+    path == ancestor || (path.starts_with(ancestor) && path[ancestor.len()..].starts_with('['))
+}
+
+/// Node path an `editing_value` path belongs to: edit paths are a node path plus an optional
+/// "_name"/"_key"/"_bits"/"_raw"/"_desc" suffix
+fn edit_node_path(edit_path: &str) -> &str {
+    match edit_path.rfind(']') {
+        Some(end) => &edit_path[..=end],
+        None => "root",
+    }
+}
+
+/// `path` as addressed once the child `parent[index]` has been removed: later siblings of
+/// that child, and everything under them, move up by one
+fn path_after_removal(path: &str, parent: &str, index: usize) -> String {
+    let Some((sibling, tail)) = path.strip_prefix(parent)
+        .and_then(|rest| rest.strip_prefix('['))
+        .and_then(|rest| rest.split_once(']'))
+    else {
+        return path.to_string();
+    };
+    match sibling.parse::<usize>() {
+        Ok(sibling) if sibling > index => format!("{}[{}]{}", parent, sibling - 1, tail),
+        _ => path.to_string(),
+    }
+}
+
+/// Bytes as space-separated hex pairs, e.g. `07 2A 00 00 00`
+fn format_hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+}
+
+/// Inverse of `format_hex_bytes`; whitespace between (or within) pairs is ignored
+fn parse_hex_bytes(text: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if let Some(bad) = digits.iter().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' isn't a hex digit", bad));
+    }
+    if digits.len() % 2 == 1 {
+        return Err("odd number of hex digits".to_string());
+    }
+    Ok(digits.chunks(2)
+        .map(|pair| (pair[0].to_digit(16).unwrap() * 16 + pair[1].to_digit(16).unwrap()) as u8)
+        .collect())
+}
+
+/// Modified time and size of a file on disk, to notice changes made by other programs
+#[derive(Clone, Copy, PartialEq)]
+struct FileStamp {
+    modified: Option<std::time::SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self { modified: metadata.modified().ok(), len: metadata.len() })
+    }
+}
+
+/// Whether two paths name the same file, resolving links and relative parts when both exist
+fn same_file_path(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Show `dir` in the platform's file manager
+fn open_in_file_manager(dir: &Path) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let program = "explorer";
+    #[cfg(target_os = "macos")]
+    let program = "open";
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let program = "xdg-open";
+    
+    std::process::Command::new(program).arg(dir).spawn().map(|_| ())
+}
+
+/// Drag-and-drop payload: path of the tree row being dragged
+struct DraggedNode(String);
+
+/// Where a dragged row lands relative to the row it is dropped on
+#[derive(Clone, Copy, PartialEq)]
+enum DropPlacement {
+    Before,
+    After,
+    Into,
 }
 
 #[derive(Clone)]
@@ -52,11 +353,84 @@ enum UndoAction {
         new_name: String,
         new_hash: u64,
     },
+    /// Drag-and-drop move. `to_parent` addresses the destination once the node has been
+    /// taken out of `from_parent`; `hash` keys the node whenever it lands in a struct.
+    Move {
+        from_parent: String,
+        from_index: usize,
+        to_parent: String,
+        to_index: usize,
+        hash: u64,
+    },
+    Batch {
+        actions: Vec<UndoAction>, // Applied in order; undone in reverse
+    },
+    /// Label Editor / hash naming change. Shares the stack with param edits so Ctrl+Z
+    /// always reverts the most recent change of either kind; the CSV is re-saved.
+    Label {
+        hash: u64,
+        old: LabelState,
+        new: LabelState,
+    },
+}
+
+impl UndoAction {
+    /// One-line summary for the session edit log; batches list their actions on indented lines
+    fn describe(&self) -> String {
+        let value_text = |value: &ParamValue| NodeRef { name: String::new(), hash: 0, value }.get_value_string(None);
+        match self {
+            UndoAction::DeleteNode { path, node, .. } => {
+                format!("Delete {} ({} {})", path, node.value.type_name(), node.name)
+            }
+            UndoAction::AddNode { path } => format!("Add {}", path),
+            UndoAction::UpdateValue { path, old_value, new_value } => {
+                format!("Set {}: {} -> {}", path, value_text(old_value), value_text(new_value))
+            }
+            UndoAction::UpdateKey { path, old_name, old_hash, new_name, new_hash } => {
+                format!("Rename {}: {} (0x{:X}) -> {} (0x{:X})", path, old_name, old_hash, new_name, new_hash)
+            }
+            UndoAction::Move { from_parent, from_index, to_parent, to_index, .. } => {
+                format!("Move {}[{}] to {}[{}]", from_parent, from_index, to_parent, to_index)
+            }
+            UndoAction::Batch { actions } => {
+                let mut text = format!("Batch of {} changes", actions.len());
+                for action in actions {
+                    for line in action.describe().lines() {
+                        text.push_str("\n    ");
+                        text.push_str(line);
+                    }
+                }
+                text
+            }
+            UndoAction::Label { hash, old, new } => {
+                format!(
+                    "Label 0x{:X}: {} -> {}",
+                    hash,
+                    old.label.as_deref().unwrap_or("(none)"),
+                    new.label.as_deref().unwrap_or("(none)")
+                )
+            }
+        }
+    }
 }
 
 impl PrcEditorApp {
     pub fn new() -> Self {
-        let mut app = Self {
+        let mut app = Self::with_defaults();
+        app.load_preferences();
+        
+        // Try to load ParamLabels.csv at startup
+        app.load_param_labels();
+        app.load_default_descriptions();
+        app.load_default_ranges();
+        
+        app
+    }
+    
+    /// Editor with nothing open and every setting at its default, before the preferences
+    /// and the labels, descriptions and ranges files are read
+    fn with_defaults() -> Self {
+        Self {
             param_file: ParamFile::new(),
             selected_node: None,
             expanded_nodes: HashSet::new(),
@@ -64,6 +438,7 @@ impl PrcEditorApp {
             tree_width: 700.0,
             show_label_editor: false,
             label_editor_filter: String::new(),
+            label_mismatches_only: false,
             editing_value: None,
             new_label_input: String::new(),
             new_hash_input: String::new(),
@@ -72,18 +447,107 @@ impl PrcEditorApp {
             clipboard: None,
             clipboard_data: None,
             cut_mode: false,
+            value_clipboard: None,
             // show_shortcuts_help removed
             param_labels_path: None,
+            labels_save_failure: None,
+            keep_unwritable_labels: false,
             tree_items: Vec::new(),
             selected_index: None,
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
-        };
-        
-        // Try to load ParamLabels.csv at startup
-        app.load_param_labels();
-        
-        app
+            highlight_unlabeled: false,
+            highlight_editing: true,
+            unlabeled_paths: Vec::new(),
+            unlabeled_paths_key: None,
+            auto_expand_on_select: false,
+            without_labels: false,
+            search_query: String::new(),
+            search_matches: Vec::new(),
+            search_cache_key: None,
+            value_filter_comparison: ValueComparison::Equal,
+            value_filter_operand: String::new(),
+            value_filter_type: None,
+            value_filter_paths: None,
+            value_filter_key: None,
+            scroll_to_selected: false,
+            undo_limit: 100,
+            raw_byte_editing: false,
+            node_size_cache: None,
+            save_ordering_cache: None,
+            round_trip_pending: None,
+            round_trip_result: None,
+            value_chart_cache: None,
+            reference_file: None,
+            naming_hash: None,
+            naming_suggestions: None,
+            compact_mode: false,
+            compact_density: false,
+            group_struct_fields: false,
+            show_tree_values: false,
+            fold_single_child: false,
+            flag_special_floats: true,
+            cut_origin: None,
+            show_cut_warning: false,
+            int_display: IntDisplay::Native,
+            float_digits: Some(6),
+            full_precision_paths: HashSet::new(),
+            verify_after_save: false,
+            write_xml_sidecar: false,
+            nav_back: Vec::new(),
+            nav_forward: Vec::new(),
+            canonical_labels_path: None,
+            show_session_labels: false,
+            show_reset_labels_confirm: false,
+            focus_search: false,
+            list_display_keys: HashMap::new(),
+            case_sensitive_search: false,
+            hash_locations: None,
+            selected_label_hashes: HashSet::new(),
+            label_select_anchor: None,
+            show_bulk_label_delete_confirm: false,
+            show_struct_layouts: false,
+            struct_layouts: None,
+            layout_sort: (LayoutSort::Instances, true),
+            multi_selection: Vec::new(),
+            show_unknown_types: false,
+            show_preferences: false,
+            patch_conflicts: Vec::new(),
+            show_apply_math: false,
+            math_op: MathOp::Multiply,
+            math_operand: String::new(),
+            pending_key_overwrite: None,
+            wrap_in_struct: None,
+            pinned_details: None,
+            opened_file: None,
+            external_change_save: None,
+            sequence_labels: None,
+            duplicate_item: None,
+            clear_children: None,
+            normalize_hashes: None,
+            recent_edits: Vec::new(),
+            session_log: Vec::new(),
+            session_start: std::time::Instant::now(),
+            descriptions: ParamDescriptions::new(),
+            descriptions_path: None,
+            ranges: ParamRanges::new(),
+            range_drag: None,
+            add_field: None,
+            add_field_type: "Int",
+            add_field_focus: false,
+            show_shortcuts: false,
+            shortcuts_filter: String::new(),
+            shortcuts: ShortcutRegistry::new(),
+            notes: NodeNotes::new(),
+            note_nodes: HashMap::new(),
+            note_nodes_key: None,
+            editing_note: None,
+            show_notes: false,
+            persist_notes: false,
+            folder_files: None,
+            folder_filter: String::new(),
+            capturing_shortcut: None,
+        }
     }
     
     fn load_param_labels(&mut self) {
@@ -136,12 +600,187 @@ impl PrcEditorApp {
         }
     }
     
+    /// Read ParamDescriptions.csv from next to the labels file, or the working directory
+    /// when no labels file is set. A missing file is not an error; descriptions are optional.
+    fn load_default_descriptions(&mut self) {
+        let path = self.param_labels_path.as_deref()
+            .and_then(|labels| Path::new(labels).parent())
+            .map(|dir| dir.join(DESCRIPTIONS_FILE_NAME))
+            .unwrap_or_else(|| PathBuf::from(DESCRIPTIONS_FILE_NAME));
+        self.descriptions_path = Some(path.to_string_lossy().to_string());
+        
+        if let Ok(csv_content) = std::fs::read_to_string(&path) {
+            if let Err(e) = self.descriptions.merge_from_csv(&csv_content, &self.param_file.hash_labels) {
+                self.status_message = format!("Error loading descriptions from {}: {}", path.display(), e);
+            }
+        }
+    }
+    
+    /// Merge descriptions from a chosen CSV; edits are saved to that file from then on
+    fn load_descriptions_dialog(&mut self) {
+        let Some(file_path) = FileDialog::new()
+            .add_filter("CSV files", &["csv"])
+            .add_filter("All files", &["*"])
+            .set_title("Select ParamDescriptions.csv")
+            .set_file_name(DESCRIPTIONS_FILE_NAME)
+            .pick_file() else {
+            return;
+        };
+        
+        let result = std::fs::read_to_string(&file_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|csv_content| self.descriptions.merge_from_csv(&csv_content, &self.param_file.hash_labels));
+        match result {
+            Ok(count) => {
+                self.descriptions_path = Some(file_path.to_string_lossy().to_string());
+                self.status_message = format!("Loaded {} descriptions from {} ({} total)", count, file_path.display(), self.descriptions.len());
+            }
+            Err(e) => {
+                self.status_message = format!("Error loading descriptions from {}: {}", file_path.display(), e);
+            }
+        }
+    }
+    
+    /// Read ParamRanges.csv from next to the labels file, or the working directory when no
+    /// labels file is set. Like descriptions, the file is optional.
+    fn load_default_ranges(&mut self) {
+        let path = self.param_labels_path.as_deref()
+            .and_then(|labels| Path::new(labels).parent())
+            .map(|dir| dir.join(RANGES_FILE_NAME))
+            .unwrap_or_else(|| PathBuf::from(RANGES_FILE_NAME));
+        
+        if let Ok(csv_content) = std::fs::read_to_string(&path) {
+            if let Err(e) = self.ranges.merge_from_csv(&csv_content, &self.param_file.hash_labels) {
+                self.status_message = format!("Error loading value ranges from {}: {}", path.display(), e);
+            }
+        }
+    }
+    
+    /// Merge `key,min,max[,step]` value ranges from a chosen CSV
+    fn load_ranges_dialog(&mut self) {
+        let Some(file_path) = FileDialog::new()
+            .add_filter("CSV files", &["csv"])
+            .add_filter("All files", &["*"])
+            .set_title("Select ParamRanges.csv")
+            .set_file_name(RANGES_FILE_NAME)
+            .pick_file() else {
+            return;
+        };
+        
+        let result = std::fs::read_to_string(&file_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|csv_content| self.ranges.merge_from_csv(&csv_content, &self.param_file.hash_labels));
+        self.status_message = match result {
+            Ok(count) => format!("Loaded {} value ranges from {} ({} total)", count, file_path.display(), self.ranges.len()),
+            Err(e) => format!("Error loading value ranges from {}: {}", file_path.display(), e),
+        };
+    }
+    
+    /// Range for the numeric value at `path`: its struct field key's range, or for a list
+    /// item the range of the list's key. None for unannotated fields.
+    fn value_range(&self, path: &str) -> Option<ValueRange> {
+        let node = self.find_node_by_path(path)?;
+        node.value.as_f64()?;
+        if self.is_struct_field(path) {
+            return self.ranges.get(node.hash);
+        }
+        
+        let parent_path = self.get_parent_path(path)?;
+        let parent = self.find_node_by_path(&parent_path)?;
+        if matches!(parent.value, ParamValue::List(_)) && self.is_struct_field(&parent_path) {
+            self.ranges.get(parent.hash)
+        } else {
+            None
+        }
+    }
+    
+    /// Why `value` can't be stored at `path`, when it falls outside the field's range
+    fn range_violation(&self, path: &str, value: &ParamValue) -> Option<String> {
+        let range = self.value_range(path)?;
+        let number = value.as_f64()?;
+        (!range.contains(number)).then(|| format!("{} is outside the allowed range {} for {}", number, range, path))
+    }
+    
+    /// Details row with a slider over the field's range. A drag is applied live and recorded
+    /// as a single undo step when it ends.
+    fn show_range_row(&mut self, ui: &mut egui::Ui, path: &str, value: &ParamValue, range: ValueRange) {
+        ui.strong("Range:");
+        ui.horizontal(|ui| {
+            let Some(mut number) = value.as_f64() else {
+                return;
+            };
+            let in_range = range.contains(number);
+            
+            let mut slider = egui::Slider::new(&mut number, range.min..=range.max);
+            if !matches!(value, ParamValue::F32(_)) {
+                slider = slider.integer();
+            }
+            if let Some(step) = range.step {
+                slider = slider.step_by(step);
+            }
+            let response = ui.add(slider);
+            
+            if response.changed() {
+                if let Some((new_value, _)) = value.apply_math(MathOp::Set, number) {
+                    if self.range_drag.as_ref().is_none_or(|(drag_path, _)| drag_path != path) {
+                        self.range_drag = Some((path.to_string(), value.clone()));
+                    }
+                    self.param_file.update_node_value(path, new_value);
+                }
+            }
+            if !response.dragged() {
+                self.finish_range_drag(path);
+            }
+            
+            if !in_range {
+                ui.colored_label(egui::Color32::from_rgb(255, 140, 0), format!("⚠ outside {}", range));
+            } else {
+                ui.weak(range.to_string());
+            }
+        });
+        ui.end_row();
+    }
+    
+    /// Record a finished range slider change at `path` as one undoable value update
+    fn finish_range_drag(&mut self, path: &str) {
+        let Some((_, old_value)) = self.range_drag.take_if(|(drag_path, _)| drag_path == path) else {
+            return;
+        };
+        let Some(new_value) = self.param_file.get_node_value(path).filter(|new_value| *new_value != old_value) else {
+            return;
+        };
+        
+        self.status_message = format!("{} set to {}", path, NodeRef { name: String::new(), hash: 0, value: &new_value }.get_value_string(None));
+        self.push_undo_action(UndoAction::UpdateValue { path: path.to_string(), old_value, new_value });
+        self.note_recent_edit(path);
+    }
+    
+    /// Set or clear (blank text) the description of field `hash` and write the descriptions file
+    fn set_description(&mut self, hash: u64, text: &str) {
+        self.descriptions.set(hash, text);
+        let label = self.param_file.hash_labels.hash_to_string(hash);
+        self.status_message = match &self.descriptions_path {
+            Some(path) => match self.descriptions.save_to_csv(path, &self.param_file.hash_labels) {
+                Ok(()) => format!("Description of '{}' saved to {}", label, path),
+                Err(e) => format!("Description of '{}' updated but could not be saved to {}: {}", label, path, e),
+            },
+            None => format!("Description of '{}' updated (no descriptions file to save to)", label),
+        };
+    }
+    
+    /// Hover text for a field key: its description, if any, above the usual hint
+    fn key_hover_text(&self, hash: u64, hint: &str) -> String {
+        match self.descriptions.get(hash) {
+            Some(description) => format!("{}\n\n{}", description, hint),
+            None => hint.to_string(),
+        }
+    }
+    
     fn load_labels_from_content(&mut self, csv_content: &str, file_path: &str) {
         match self.param_file.hash_labels.load_from_csv(csv_content) {
-            Ok(count) => {
-                self.status_message = format!("Loaded {} param labels from {}", count, file_path);
-                // Rebuild the tree to apply the new labels to field names
-                self.param_file.rebuild_tree_with_labels();
+            Ok(report) => {
+                self.status_message = format!("{}: {}", file_path, report.summary());
+                self.param_file.refresh_names_only();
             }
             Err(e) => {
                 self.status_message = format!("Error loading labels from {}: {}", file_path, e);
@@ -149,6 +788,106 @@ impl PrcEditorApp {
         }
     }
     
+    /// Re-read the current labels file, either replacing the in-memory labels or merging into them
+    fn reload_labels_from_disk(&mut self, replace: bool) {
+        let Some(path) = self.param_labels_path.clone() else {
+            self.status_message = "No labels file path set - use 'Load Labels...' first".to_string();
+            return;
+        };
+        
+        let csv_content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.status_message = format!("Could not read {}: {}", path, e);
+                return;
+            }
+        };
+        
+        let result = if replace {
+            // Parse into a fresh set first so a bad file doesn't wipe the current labels
+            let mut fresh_labels = HashLabels::new();
+            fresh_labels.csv_sort_order = self.param_file.hash_labels.csv_sort_order;
+            let result = fresh_labels.load_from_csv(&csv_content);
+            if result.is_ok() {
+                self.param_file.hash_labels = fresh_labels;
+            }
+            result
+        } else {
+            self.param_file.hash_labels.load_from_csv(&csv_content)
+        };
+        
+        match result {
+            Ok(report) => {
+                let mode = if replace { "Reloaded" } else { "Merged" };
+                self.status_message = format!("{} {}: {} ({} labels total)", mode, path, report.summary(), self.param_file.hash_labels.len());
+                self.param_file.refresh_names_only();
+            }
+            Err(e) => {
+                self.status_message = format!("Error reloading labels from {}: {}", path, e);
+            }
+        }
+    }
+    
+    /// Pick the master labels CSV that "Reset Labels to Canonical" restores from
+    fn choose_canonical_labels_file(&mut self) {
+        if let Some(file_path) = FileDialog::new()
+            .add_filter("CSV files", &["csv"])
+            .add_filter("All files", &["*"])
+            .set_title("Select canonical ParamLabels.csv")
+            .pick_file()
+        {
+            let path = file_path.to_string_lossy().to_string();
+            self.status_message = format!("Canonical labels file set to {}", path);
+            self.canonical_labels_path = Some(path);
+            self.save_preferences();
+        }
+    }
+    
+    /// Replace all labels with the canonical CSV, discarding session additions,
+    /// and write the result over the working labels file
+    fn reset_labels_to_canonical(&mut self) {
+        let Some(canonical_path) = self.canonical_labels_path.clone() else {
+            self.status_message = "No canonical labels file set".to_string();
+            return;
+        };
+        
+        let csv_content = match std::fs::read_to_string(&canonical_path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.status_message = format!("Could not read {}: {}", canonical_path, e);
+                return;
+            }
+        };
+        
+        // Parse into a fresh set first so a bad file doesn't wipe the current labels
+        let mut fresh_labels = HashLabels::new();
+        fresh_labels.csv_sort_order = self.param_file.hash_labels.csv_sort_order;
+        let report = match fresh_labels.load_from_csv(&csv_content) {
+            Ok(report) => report,
+            Err(e) => {
+                self.status_message = format!("Error loading canonical labels from {}: {}", canonical_path, e);
+                return;
+            }
+        };
+        
+        let discarded = self.param_file.hash_labels.session_additions().len();
+        self.param_file.hash_labels = fresh_labels;
+        self.param_file.refresh_names_only();
+        
+        let save_note = match &self.param_labels_path {
+            Some(path) if path != &canonical_path => match self.param_file.hash_labels.save_to_csv(path) {
+                Ok(()) => format!(", rewrote {}", path),
+                Err(e) => format!(", but failed to rewrite {}: {}", path, e),
+            },
+            _ => String::new(),
+        };
+        
+        self.status_message = format!(
+            "Reset labels to {}: {}, discarded {} session labels{}",
+            canonical_path, report.summary(), discarded, save_note
+        );
+    }
+    
     fn prompt_for_labels_file(&mut self) {
         // Try to find the Blender addon directory as default
         let mut dialog = FileDialog::new()
@@ -196,7 +935,7 @@ impl PrcEditorApp {
         egui::menu::bar(ui, |ui| {
             ui.menu_button("File", |ui| {
                 let has_labels = self.param_labels_path.is_some();
-                let open_button = ui.add_enabled(has_labels, egui::Button::new("Open"));
+                let open_button = ui.add_enabled(has_labels || self.without_labels, egui::Button::new("Open"));
                 if open_button.clicked() {
                     self.open_file_dialog();
                     ui.close_menu();
@@ -205,6 +944,19 @@ impl PrcEditorApp {
                     open_button.on_hover_text("Load ParamLabels.csv first");
                 }
                 
+                if ui.button("Open Folder...").on_hover_text("List a folder's param files in a side panel").clicked() {
+                    self.open_folder_dialog();
+                    ui.close_menu();
+                }
+                
+                if !has_labels && ui.button("Open Without Labels...")
+                    .on_hover_text("Hashes will be shown as 0x... until labels are loaded")
+                    .clicked()
+                {
+                    self.open_without_labels();
+                    ui.close_menu();
+                }
+                
                 ui.separator();
                 
                 let has_file = self.param_file.get_root().is_some();
@@ -217,8 +969,106 @@ impl PrcEditorApp {
                     self.save_file_dialog();
                     ui.close_menu();
                 }
+                
+                if ui.checkbox(&mut self.verify_after_save, "Verify After Save")
+                    .on_hover_text("Re-open each saved file before it replaces the original")
+                    .changed()
+                {
+                    self.save_preferences();
+                }
+                
+                if ui.checkbox(&mut self.write_xml_sidecar, "Write XML Sidecar")
+                    .on_hover_text("Also write a .prcx XML copy next to each saved file")
+                    .changed()
+                {
+                    self.save_preferences();
+                }
+                
+                ui.separator();
+                
+                let opened_dir = self.opened_file.as_ref().and_then(|(path, _)| path.parent()).map(Path::to_path_buf);
+                if ui.add_enabled(opened_dir.is_some(), egui::Button::new("Open Containing Folder"))
+                    .on_hover_text("Show the opened file's folder in the file manager")
+                    .clicked()
+                {
+                    self.open_folder("containing", opened_dir);
+                    ui.close_menu();
+                }
+                
+                ui.separator();
+                
+                if ui.add_enabled(has_file, egui::Button::new("Export Structure (DOT)...")).clicked() {
+                    self.export_dot_dialog();
+                    ui.close_menu();
+                }
+                
+                if ui.add_enabled(has_file && self.reference_file.is_some(), egui::Button::new("Export Diff as Patch..."))
+                    .on_hover_text("Save what changed from the reference file to this one as a patch")
+                    .clicked()
+                {
+                    self.export_patch_dialog();
+                    ui.close_menu();
+                }
+                
+                if ui.add_enabled(has_file, egui::Button::new("Export Changes Only..."))
+                    .on_hover_text("Save a partial file with only the params changed since opening - not a complete replacement file")
+                    .clicked()
+                {
+                    self.export_changes_dialog();
+                    ui.close_menu();
+                }
+                
+                if ui.add_enabled(has_file, egui::Button::new("Apply Patch...")).clicked() {
+                    self.apply_patch_dialog();
+                    ui.close_menu();
+                }
+                
+                if ui.add_enabled(!self.session_log.is_empty(), egui::Button::new("Export Edit Log..."))
+                    .on_hover_text("Save this session's opens, saves and edits to a text file to attach to a bug report.\nThe file is only written to your computer; nothing is sent anywhere.")
+                    .clicked()
+                {
+                    self.export_session_log_dialog();
+                    ui.close_menu();
+                }
+                
+                ui.separator();
+                
+                if ui.button("Open Reference File...").on_hover_text("Load a second file read-only to compare values against").clicked() {
+                    self.open_reference_file_dialog();
+                    ui.close_menu();
+                }
+                
+                if ui.add_enabled(self.reference_file.is_some(), egui::Button::new("Close Reference File")).clicked() {
+                    self.reference_file = None;
+                    self.status_message = "Closed reference file".to_string();
+                    ui.close_menu();
+                }
             });
 
+            ui.menu_button("Edit", |ui| {
+                if ui.add_enabled(!self.undo_stack.is_empty(), egui::Button::new(format!("Undo ({})", self.shortcuts.keys_text(Command::Undo)))).clicked() {
+                    self.undo_and_log();
+                    ui.close_menu();
+                }
+                if ui.add_enabled(!self.redo_stack.is_empty(), egui::Button::new(format!("Redo ({})", self.shortcuts.keys_text(Command::Redo)))).clicked() {
+                    self.redo_and_log();
+                    ui.close_menu();
+                }
+                
+                ui.separator();
+                
+                let undo_all = format!("Undo All ({}) ({})", self.undo_stack.len(), self.shortcuts.keys_text(Command::UndoAll));
+                if ui.add_enabled(!self.undo_stack.is_empty(), egui::Button::new(undo_all)).clicked() {
+                    self.undo_all();
+                    ui.close_menu();
+                }
+                let redo_all = format!("Redo All ({}) ({})", self.redo_stack.len(), self.shortcuts.keys_text(Command::RedoAll));
+                if ui.add_enabled(!self.redo_stack.is_empty(), egui::Button::new(redo_all)).clicked() {
+                    self.redo_all();
+                    ui.close_menu();
+                }
+            });
+            
             ui.menu_button("Labels", |ui| {
                 if ui.button("Load Labels...").clicked() {
                     self.prompt_for_labels_file();
@@ -230,6 +1080,17 @@ impl PrcEditorApp {
                     ui.close_menu();
                 }
                 
+                let has_labels_path = self.param_labels_path.is_some();
+                if ui.add_enabled(has_labels_path, egui::Button::new("Reload from Disk")).clicked() {
+                    self.reload_labels_from_disk(true);
+                    ui.close_menu();
+                }
+                
+                if ui.add_enabled(has_labels_path, egui::Button::new("Reload from Disk (Merge)")).clicked() {
+                    self.reload_labels_from_disk(false);
+                    ui.close_menu();
+                }
+                
                 ui.separator();
                 
                 // Show current labels file path
@@ -246,6 +1107,39 @@ impl PrcEditorApp {
                     ui.label("No labels file loaded");
                 }
                 
+                let labels_dir = self.param_labels_path.as_deref().and_then(|path| Path::new(path).parent()).map(Path::to_path_buf);
+                if ui.add_enabled(labels_dir.is_some(), egui::Button::new("Open Labels Folder")).clicked() {
+                    self.open_folder("labels", labels_dir);
+                    ui.close_menu();
+                }
+                
+                let descriptions_button = ui.button(format!("Load Descriptions... ({})", self.descriptions.len()));
+                let descriptions_button = match &self.descriptions_path {
+                    Some(path) => descriptions_button.on_hover_text(format!("Merge field descriptions from a CSV; edits are saved to {}", path)),
+                    None => descriptions_button.on_hover_text("Merge field descriptions from a CSV"),
+                };
+                if descriptions_button.clicked() {
+                    self.load_descriptions_dialog();
+                    ui.close_menu();
+                }
+                
+                if ui.button(format!("Load Value Ranges... ({})", self.ranges.len()))
+                    .on_hover_text("Merge key,min,max[,step] rows from a CSV; edits outside a field's range are rejected")
+                    .clicked()
+                {
+                    self.load_ranges_dialog();
+                    ui.close_menu();
+                }
+                
+                let has_file = self.param_file.get_root().is_some();
+                if ui.add_enabled(has_file, egui::Button::new("Normalize Hash Values..."))
+                    .on_hover_text("Rewrite hash40 values that are only named through a masked variant to that variant's hash")
+                    .clicked()
+                {
+                    self.open_normalize_hashes();
+                    ui.close_menu();
+                }
+                
                 ui.separator();
                 
                 if ui.button("Edit").clicked() {
@@ -274,16 +1168,217 @@ impl PrcEditorApp {
                     self.download_labels();
                     ui.close_menu();
                 }
+
+                ui.separator();
+
+                let session_count = self.param_file.hash_labels.session_additions().len();
+                if ui.button(format!("Labels Added This Session ({})", session_count)).clicked() {
+                    self.show_session_labels = true;
+                    ui.close_menu();
+                }
+
+                if ui.button("Set Canonical Labels File...").clicked() {
+                    self.choose_canonical_labels_file();
+                    ui.close_menu();
+                }
+
+                let reset_button = ui.add_enabled(
+                    self.canonical_labels_path.is_some(),
+                    egui::Button::new("Reset Labels to Canonical..."),
+                );
+                let reset_button = match &self.canonical_labels_path {
+                    Some(path) => reset_button.on_hover_text(format!("Replace all labels with {}", path)),
+                    None => reset_button.on_disabled_hover_text("Set a canonical labels file first"),
+                };
+                if reset_button.clicked() {
+                    self.show_reset_labels_confirm = true;
+                    ui.close_menu();
+                }
+
+                ui.separator();
+
+                let has_file = self.param_file.get_root().is_some();
+                if ui.add_enabled(has_file, egui::Button::new("Import Labels Used in File...")).clicked() {
+                    self.import_used_labels_dialog();
+                    ui.close_menu();
+                }
+                
+                if ui.add_enabled(has_file, egui::Button::new("Batch Rename Keys...")).clicked() {
+                    self.batch_rename_keys_dialog();
+                    ui.close_menu();
+                }
+                
+                ui.separator();
+                
+                ui.menu_button("CSV Sort Order", |ui| {
+                    let sort_order = &mut self.param_file.hash_labels.csv_sort_order;
+                    let mut changed = ui.radio_value(sort_order, LabelSortOrder::Hash, "By Hash (default)").changed();
+                    changed |= ui.radio_value(sort_order, LabelSortOrder::Label, "By Label").changed();
+                    changed |= ui.radio_value(sort_order, LabelSortOrder::Insertion, "Insertion Order").changed();
+                    if changed {
+                        self.save_preferences();
+                    }
+                });
+            });
+
+            ui.menu_button("View", |ui| {
+                if ui.checkbox(&mut self.compact_mode, "Compact Mode").on_hover_text("Hide the shortcuts overlay and status bar extras").changed() {
+                    self.save_preferences();
+                }
+                
+                if ui.checkbox(&mut self.compact_density, "Compact Density").on_hover_text("Fit more rows on screen in the tree and editor grids").changed() {
+                    self.save_preferences();
+                }
+                
+                if ui.button(format!("Keyboard Shortcuts... ({})", self.shortcuts.keys_text(Command::ShortcutsHelp))).clicked() {
+                    self.show_shortcuts = true;
+                    ui.close_menu();
+                }
+                
+                if ui.checkbox(&mut self.show_tree_values, "Show Values in Tree").on_hover_text("Show leaf values as name (Type) = value").changed() {
+                    self.save_preferences();
+                }
+                
+                if ui.checkbox(&mut self.fold_single_child, "Fold Single-Child Chains")
+                    .on_hover_text("Show containers that hold only one container on a single row, e.g. A > B > C")
+                    .changed()
+                {
+                    self.save_preferences();
+                }
+                
+                if ui.checkbox(&mut self.flag_special_floats, "Flag NaN/Inf Floats")
+                    .on_hover_text("Highlight NaN and infinite floats and show their raw bits")
+                    .changed()
+                {
+                    self.save_preferences();
+                }
+                
+                if ui.checkbox(&mut self.auto_expand_on_select, "Auto-expand on Select").changed() {
+                    self.save_preferences();
+                }
+                
+                if ui.checkbox(&mut self.highlight_unlabeled, "Highlight Unlabeled Hashes").changed() {
+                    self.save_preferences();
+                }
+                
+                if ui.checkbox(&mut self.highlight_editing, "Highlight Node Being Edited").changed() {
+                    self.save_preferences();
+                }
+                
+                if ui.checkbox(&mut self.group_struct_fields, "Group Struct Fields").on_hover_text("Split the struct editor into sections by description category or label prefix").changed() {
+                    self.save_preferences();
+                }
+                
+                ui.menu_button("Integer Display", |ui| {
+                    let mut changed = ui.radio_value(&mut self.int_display, IntDisplay::Native, "As Stored").changed();
+                    changed |= ui.radio_value(&mut self.int_display, IntDisplay::FlippedSign, "Opposite Signedness").changed();
+                    changed |= ui.radio_value(&mut self.int_display, IntDisplay::Hex, "Hex").changed();
+                    if changed {
+                        self.save_preferences();
+                    }
+                });
+                
+                ui.menu_button("Float Precision", |ui| {
+                    let mut changed = ui.radio_value(&mut self.float_digits, None, "Exact").changed();
+                    for digits in [3, 4, 6, 9] {
+                        changed |= ui.radio_value(&mut self.float_digits, Some(digits), format!("{} Significant Digits", digits)).changed();
+                    }
+                    if changed {
+                        self.save_preferences();
+                    }
+                });
+                
+                if ui.add_enabled(self.highlight_unlabeled, egui::Button::new(format!("Next Unlabeled ({})", self.shortcuts.keys_text(Command::NextUnlabeled)))).clicked() {
+                    self.select_next_unlabeled();
+                    ui.close_menu();
+                }
+                
+                let has_file = self.param_file.get_root().is_some();
+                if ui.add_enabled(has_file, egui::Button::new("Struct Layouts..."))
+                    .on_hover_text("Group structs by their field keys to see repeated layouts")
+                    .clicked()
+                {
+                    self.show_struct_layouts = true;
+                    ui.close_menu();
+                }
+                if ui.add_enabled(has_file, egui::Button::new("Check Tree Consistency"))
+                    .on_hover_text("Compare the tree rows and hash index with the values that get saved")
+                    .clicked()
+                {
+                    self.check_tree_consistency();
+                    ui.close_menu();
+                }
+                
+                if ui.button(format!("Notes... ({})", self.notes.len()))
+                    .on_hover_text("Every node with a review note; right-click a node to add one")
+                    .clicked()
+                {
+                    self.show_notes = true;
+                    ui.close_menu();
+                }
+                
+                ui.separator();
+                
+                ui.horizontal(|ui| {
+                    ui.label("Undo history:");
+                    let response = ui.add(egui::DragValue::new(&mut self.undo_limit).range(1..=MAX_UNDO_LIMIT).suffix(" steps"));
+                    if response.changed() {
+                        self.trim_undo_stack();
+                        self.save_preferences();
+                    }
+                });
+                
+                ui.separator();
+                
+                if ui.button("Preferences...").clicked() {
+                    self.show_preferences = true;
+                    ui.close_menu();
+                }
+                
+                if ui.button("Open Config Folder").on_hover_text("Where preferences and the labels location are saved").clicked() {
+                    self.open_folder("config", Self::get_config_path().parent().map(Path::to_path_buf));
+                    ui.close_menu();
+                }
             });
         });
     }
 
+    /// Tighten spacing and row height for the panel `ui` belongs to when compact density is on
+    fn apply_density(&self, ui: &mut egui::Ui) {
+        if !self.compact_density {
+            return;
+        }
+        let spacing = ui.spacing_mut();
+        spacing.item_spacing = egui::vec2(4.0, 1.0);
+        spacing.button_padding = egui::vec2(3.0, 0.0);
+        spacing.interact_size.y = 14.0;
+        spacing.indent = 14.0;
+    }
+    
+    /// Editor grid spacing, squeezed when compact density is on
+    fn grid_spacing(&self, x: f32, y: f32) -> egui::Vec2 {
+        if self.compact_density {
+            egui::vec2(x / 2.0, 1.0)
+        } else {
+            egui::vec2(x, y)
+        }
+    }
+    
     fn show_main_content(&mut self, ui: &mut egui::Ui) {
+        if self.folder_files.is_some() {
+            egui::SidePanel::left("folder_files")
+                .resizable(true)
+                .default_width(220.0)
+                .min_width(150.0)
+                .show_inside(ui, |ui| self.show_folder_panel(ui));
+        }
+        
         egui::SidePanel::left("parameter_tree")
             .resizable(true)
             .default_width(self.tree_width)
             .min_width(200.0)
             .show_inside(ui, |ui| {
+                self.apply_density(ui);
                 ui.heading("Parameter Tree");
                 ui.separator();
                 
@@ -292,11 +1387,53 @@ impl PrcEditorApp {
                     self.build_tree_items();
                 }
                 
+                let unlabeled_key = Some((self.param_file.revision(), self.param_file.label_revision()));
+                if self.highlight_unlabeled && self.unlabeled_paths_key != unlabeled_key {
+                    self.unlabeled_paths = self.collect_unlabeled_paths();
+                    self.unlabeled_paths_key = unlabeled_key;
+                }
+                self.refresh_note_nodes();
+                
+                if self.param_file.get_root().is_some() {
+                    self.show_search_bar(ui);
+                    self.show_value_filter_bar(ui);
+                    ui.separator();
+                }
+                
+                if !self.multi_selection.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} selected", self.multi_selection.len()))
+                            .on_hover_text(self.multi_selection.join("\n"));
+                        if ui.button("Apply Math...").clicked() {
+                            self.show_apply_math = true;
+                        }
+                        ui.menu_button("Export", |ui| {
+                            if ui.button("As One File...").on_hover_text("Same-type selections go in a list, mixed ones in a struct").clicked() {
+                                self.export_selection_dialog();
+                                ui.close_menu();
+                            }
+                            if ui.button("As Separate Files...").clicked() {
+                                self.export_selection_separately_dialog();
+                                ui.close_menu();
+                            }
+                        });
+                        if ui.button("Clear").clicked() {
+                            self.multi_selection.clear();
+                        }
+                    });
+                    ui.separator();
+                }
+                
+                if !self.recent_edits.is_empty() {
+                    self.show_recent_edits(ui);
+                    ui.separator();
+                }
+                
                 // Make the scroll area use all available space
                 egui::ScrollArea::vertical()
                     .auto_shrink([false, false])  // Don't shrink in either direction
                     .show(ui, |ui| {
-                    if self.param_labels_path.is_none() {
+                    if self.param_labels_path.is_none() && !self.without_labels {
                         ui.vertical_centered(|ui| {
                             ui.add_space(20.0);
                             ui.colored_label(egui::Color32::YELLOW, "⚠ ParamLabels.csv Required");
@@ -306,11 +1443,15 @@ impl PrcEditorApp {
                             if ui.button("Select ParamLabels.csv").clicked() {
                                 self.prompt_for_labels_file();
                             }
+                            ui.add_space(5.0);
+                            if ui.button("Continue Without Labels").on_hover_text("Open a file with every hash shown as 0x...").clicked() {
+                                self.open_without_labels();
+                            }
                         });
                     } else if let Some(root) = self.param_file.get_root() {
                         // Clone the root to avoid borrowing issues
                         let root_clone = root.clone();
-                        self.show_tree_node(ui, &root_clone, "root".to_string());
+                        self.show_tree_node(ui, &root_clone.view(), "root".to_string());
                     } else if self.status_message.contains("Error") {
                         ui.colored_label(egui::Color32::LIGHT_RED, "Failed to parse file");
                         ui.label("Check console for details");
@@ -327,13 +1468,31 @@ impl PrcEditorApp {
             });
 
         egui::CentralPanel::default().show_inside(ui, |ui| {
-            ui.heading("Parameter Details");
+            self.apply_density(ui);
+            ui.horizontal(|ui| {
+                ui.heading("Parameter Details");
+                if self.pinned_details.is_none() {
+                    let can_pin = self.selected_node.is_some();
+                    if ui.add_enabled(can_pin, egui::Button::new("📌 Pin")).on_hover_text("Keep showing this node while selecting others").clicked() {
+                        self.pinned_details = self.selected_node.clone();
+                    }
+                }
+            });
+            if let Some(pinned_path) = self.pinned_details.clone() {
+                ui.horizontal(|ui| {
+                    ui.colored_label(egui::Color32::from_rgb(255, 140, 0), "📌 Pinned:");
+                    ui.monospace(&pinned_path);
+                    if ui.button("Unpin").clicked() {
+                        self.pinned_details = None;
+                    }
+                });
+            }
             ui.separator();
             
             // Main content area with shortcuts overlay
             
-            if let Some(selected_path) = self.selected_node.clone() {
-                self.show_parameter_details(ui, &selected_path);
+            if let Some(details_path) = self.pinned_details.clone().or_else(|| self.selected_node.clone()) {
+                self.show_parameter_details(ui, &details_path);
             } else {
                 ui.vertical_centered(|ui| {
                     ui.add_space(100.0);
@@ -341,9 +1500,14 @@ impl PrcEditorApp {
                 });
             }
             
+            if self.compact_mode {
+                return;
+            }
+            
             // Add shortcuts box as overlay in absolute bottom-right corner
+            let essentials = COMMANDS.iter().filter(|info| info.essential).count();
             let shortcuts_box_width = 280.0;
-            let shortcuts_box_height = 200.0;
+            let shortcuts_box_height = 60.0 + essentials as f32 * 14.5;
             
             // Use the UI's clip rect to get the actual drawable area and move closer to corner
             let clip_rect = ui.clip_rect();
@@ -371,33 +1535,22 @@ impl PrcEditorApp {
                             );
                             ui.add_space(8.0);
                             
-                            let shortcuts = [
-                                ("↑↓←→", "Navigate tree"),
-                                ("Enter", "Expand/collapse"),
-                                ("F2", "Rename node"),
-                                ("Del", "Delete node"),
-                                ("Ctrl+C", "Copy node"),
-                                ("Ctrl+X", "Cut node"),
-                                ("Ctrl+V", "Paste node"),
-                                ("Ctrl+P", "Paste to parent"),
-                                ("Ctrl+D", "Duplicate node"),
-                                ("Ctrl+S", "Save file"),
-                                ("Ctrl+Z", "Undo"),
-                                ("Ctrl+Y", "Redo"),
-                            ];
-                            
-                            for (key, desc) in shortcuts {
+                            for info in COMMANDS.iter().filter(|info| info.essential) {
                                 ui.horizontal(|ui| {
                                     ui.colored_label(
                                         egui::Color32::from_rgba_unmultiplied(150, 200, 255, 255),
-                                        egui::RichText::new(key).size(11.0).monospace()
+                                        egui::RichText::new(self.shortcuts.keys_text(info.command)).size(11.0).monospace()
                                     );
                                     ui.colored_label(
                                         egui::Color32::from_rgba_unmultiplied(180, 180, 180, 255),
-                                        egui::RichText::new(desc).size(11.0)
+                                        egui::RichText::new(info.action).size(11.0)
                                     );
                                 });
                             }
+                            ui.colored_label(
+                                egui::Color32::from_rgba_unmultiplied(140, 140, 140, 255),
+                                egui::RichText::new(format!("Press {} for all shortcuts", self.shortcuts.keys_text(Command::ShortcutsHelp))).size(11.0).italics()
+                            );
                         });
                     });
                 }
@@ -405,25 +1558,42 @@ impl PrcEditorApp {
         });
     }
 
-    fn show_tree_node(&mut self, ui: &mut egui::Ui, node: &ParamNode, path: String) {
+    fn show_tree_node(&mut self, ui: &mut egui::Ui, node: &NodeRef<'_>, path: String) {
+        // A folded chain is drawn as one row for its last container; the row stands in for
+        // the folded ancestors when highlighting
+        let (folded, node, path) = self.fold_single_child_chain(node.clone(), path);
+        let node = &node;
+        let row_paths: Vec<&String> = folded.iter().map(|(p, _)| p).chain(std::iter::once(&path)).collect();
+        
         let is_expanded = self.expanded_nodes.contains(&path);
-        let is_selected = self.selected_node.as_ref() == Some(&path);
+        let is_selected = self.selected_node.as_ref().is_some_and(|selected| row_paths.contains(&selected));
         let is_keyboard_selected = self.selected_index
             .and_then(|idx| self.tree_items.get(idx))
             .map(|selected_path| selected_path == &path)
             .unwrap_or(false);
+        let is_unlabeled = self.highlight_unlabeled && row_paths.iter().any(|p| self.unlabeled_paths.contains(*p));
+        let is_search_match = !self.search_query.is_empty() && row_paths.iter().any(|p| self.search_matches.contains(*p));
+        let is_multi_selected = self.multi_selection.contains(&path);
+        let is_being_edited = self.highlight_editing && self.editing_value.as_ref()
+            .is_some_and(|(edit_path, _)| row_paths.iter().any(|p| p.as_str() == edit_node_path(edit_path)));
+        let folded_prefix: String = folded.iter().map(|(_, label)| format!("{} > ", label)).collect();
+        let row_note = row_paths.iter().find_map(|p| self.note_at(p)).map(str::to_string);
 
         // Create the tree node header
         let response = if node.is_expandable() {
-            let icon = if is_expanded { "▼" } else { "▶" };
+            let is_empty = !node.has_children();
+            let icon = if is_expanded && !is_empty { "▼" } else { "▶" };
             ui.horizontal(|ui| {
-                if ui.button(icon).clicked() {
+                // Empty containers keep the arrow slot but there is nothing to expand
+                let arrow = ui.add_enabled(!is_empty, egui::Button::new(icon));
+                if arrow.clicked() {
                     if is_expanded {
                         self.expanded_nodes.remove(&path);
                     } else {
                         self.expanded_nodes.insert(path.clone());
                     }
                 }
+                arrow.on_disabled_hover_text("Empty - nothing to expand");
                 
                 let type_icon = match &node.value {
                     ParamValue::Struct(_) => "📁",
@@ -433,16 +1603,16 @@ impl PrcEditorApp {
                 
                 ui.label(type_icon);
                 
-                let label = if node.name.is_empty() || node.name.starts_with("0x") {
+                let label = if let Some(item_label) = self.list_item_label(&path, node) {
+                    truncate_chars(&item_label, 25)
+                } else if node.name.is_empty() || node.name.starts_with("0x") {
                     format!("0x{:X}", node.hash)
                 } else {
                     // Truncate long names for tree display
-                    if node.name.len() > 25 {
-                        format!("{}...", &node.name[..22])
-                    } else {
-                        node.name.clone()
-                    }
+                    truncate_chars(&node.name, 25)
                 };
+                let label = if is_empty { format!("{} (empty)", label) } else { label };
+                let label = format!("{}{}", folded_prefix, label);
                 
                 let label_response = ui.selectable_label(is_selected || is_keyboard_selected, label);
                 
@@ -452,6 +1622,34 @@ impl PrcEditorApp {
                     ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(1.0, egui::Color32::YELLOW));
                 }
                 
+                // Mark nodes with unlabeled hashes
+                if is_unlabeled {
+                    let rect = label_response.rect.expand(1.0);
+                    ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 140, 0)));
+                }
+                
+                // Mark search matches
+                if is_search_match {
+                    let rect = label_response.rect.expand(2.0);
+                    ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE));
+                }
+                
+                // Mark nodes in the Ctrl+click multi-selection
+                if is_multi_selected {
+                    let rect = label_response.rect.expand(3.0);
+                    ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(1.5, egui::Color32::LIGHT_GREEN));
+                }
+                
+                // Tint the node whose value or key is open in an editor
+                if is_being_edited {
+                    ui.painter().rect_filled(label_response.rect, 2.0, egui::Color32::from_rgba_unmultiplied(180, 100, 255, 48));
+                }
+                
+                // Flag nodes with a review note
+                if let Some(note) = &row_note {
+                    ui.colored_label(egui::Color32::YELLOW, "📝").on_hover_text(note);
+                }
+                
                 label_response
             }).inner
         } else {
@@ -459,24 +1657,21 @@ impl PrcEditorApp {
                 ui.add_space(20.0); // Indent for leaf nodes
                 ui.label("📄");
                 
-                let label = if node.name.is_empty() || node.name.starts_with("0x") {
+                let label = if let Some(item_label) = self.list_item_label(&path, node) {
+                    truncate_chars(&item_label, 20)
+                } else if node.name.is_empty() || node.name.starts_with("0x") {
                     format!("0x{:X}", node.hash)
                 } else {
                     // Truncate long names for tree display
-                    if node.name.len() > 20 {
-                        format!("{}...", &node.name[..17])
-                    } else {
-                        node.name.clone()
-                    }
+                    truncate_chars(&node.name, 20)
                 };
                 
-                // Simplified display for leaf nodes - just name and type
-                let _value_display = if matches!(node.value, ParamValue::Hash(_)) {
-                    node.get_value_string_with_labels(&self.param_file.hash_labels)
+                // Leaf nodes show name and type, plus the value when enabled in the View menu
+                let display_text = if self.show_tree_values {
+                    format!("{} ({}) = {}", label, node.get_type_name(), truncate_chars(&self.display_value(&path, node), 30))
                 } else {
-                    node.get_value_string()
+                    format!("{} ({})", label, node.get_type_name())
                 };
-                let display_text = format!("{} ({})", label, node.get_type_name());
                 
                 let label_response = ui.selectable_label(is_selected || is_keyboard_selected, display_text);
                 
@@ -486,31 +1681,293 @@ impl PrcEditorApp {
                     ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(1.0, egui::Color32::YELLOW));
                 }
                 
+                // Mark nodes with unlabeled hashes
+                if is_unlabeled {
+                    let rect = label_response.rect.expand(1.0);
+                    ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 140, 0)));
+                }
+                
+                // Mark search matches
+                if is_search_match {
+                    let rect = label_response.rect.expand(2.0);
+                    ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE));
+                }
+                
+                // Mark nodes in the Ctrl+click multi-selection
+                if is_multi_selected {
+                    let rect = label_response.rect.expand(3.0);
+                    ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(1.5, egui::Color32::LIGHT_GREEN));
+                }
+                
+                // Tint the node whose value or key is open in an editor
+                if is_being_edited {
+                    ui.painter().rect_filled(label_response.rect, 2.0, egui::Color32::from_rgba_unmultiplied(180, 100, 255, 48));
+                }
+                
+                // Flag nodes with a review note
+                if let Some(note) = &row_note {
+                    ui.colored_label(egui::Color32::YELLOW, "📝").on_hover_text(note);
+                }
+                
                 label_response
             }).inner
         };
 
-        // Handle selection
+        let row_top = row_paths[0].clone();
+        self.handle_tree_drag_and_drop(ui, &response, node, &path, &row_top);
+        
+        // Handle selection; Ctrl+click adds to or removes from the multi-selection
         if response.clicked() {
-            self.selected_node = Some(path.clone());
+            if ui.input(|i| i.modifiers.command) {
+                self.toggle_multi_selection(&path);
+            } else {
+                self.select_path(&path);
+            }
+        }
+        
+        let (unlabeled_key, unlabeled_value) = self.unlabeled_hashes_at(&path);
+        let display_key_fields = self.list_display_key_candidates(node);
+        let can_paste_values = matches!(node.value, ParamValue::Struct(_))
+            && matches!(self.clipboard_data.as_ref().map(|n| &n.value), Some(ParamValue::Struct(_)));
+        response.context_menu(|ui| {
+            if node.is_expandable() && node.has_children() {
+                if ui.button(format!("Expand All Under ({})", self.shortcuts.keys_text(Command::ExpandSubtree))).clicked() {
+                    self.expand_all_under(&path);
+                    ui.close_menu();
+                }
+                if ui.button(format!("Collapse All Under ({})", self.shortcuts.keys_text(Command::CollapseSubtree))).clicked() {
+                    self.collapse_all_under(&path);
+                    ui.close_menu();
+                }
+                if ui.button("Clear Children").on_hover_text("Remove every field or item; Ctrl+Z restores them").clicked() {
+                    self.request_clear_children(&path, node.child_count());
+                    ui.close_menu();
+                }
+            }
+            
+            let note = self.note_at(&path).map(str::to_string);
+            let note_button = if note.is_some() { "Edit Note..." } else { "Add Note..." };
+            if ui.button(note_button).on_hover_text("Attach a review note to this node; notes aren't saved into the param file").clicked() {
+                self.editing_note = Some((path.clone(), note.unwrap_or_default()));
+                ui.close_menu();
+            }
+            
+            ui.menu_button("Wrap In", |ui| {
+                if ui.button("Struct...").clicked() {
+                    // A struct field keeps its key inside the new struct by default
+                    let in_struct = self.get_parent_path(&path)
+                        .and_then(|parent| self.find_node_by_path(&parent))
+                        .is_some_and(|parent| matches!(parent.value, ParamValue::Struct(_)));
+                    let key = if in_struct { node.name.clone() } else { String::new() };
+                    self.wrap_in_struct = Some((path.clone(), key));
+                    ui.close_menu();
+                }
+                let wrap_list = ui.add_enabled(path != "root", egui::Button::new("List"))
+                    .on_disabled_hover_text("The root must stay a struct");
+                if wrap_list.clicked() {
+                    self.wrap_node_with_undo(&path, None);
+                    ui.close_menu();
+                }
+            });
+            
+            let is_list_item = self.get_parent_path(&path)
+                .and_then(|parent| self.find_node_by_path(&parent))
+                .is_some_and(|parent| matches!(parent.value, ParamValue::List(_)));
+            if is_list_item && ui.button("Duplicate N Times...").clicked() {
+                self.duplicate_item = Some((path.clone(), "1".to_string(), None));
+                ui.close_menu();
+            }
+            
+            if self.clipboard_data.is_some() && path != "root" {
+                if ui.button("Paste Before").clicked() {
+                    self.paste_beside(&path, DropPlacement::Before);
+                    ui.close_menu();
+                }
+                if ui.button("Paste After").clicked() {
+                    self.paste_beside(&path, DropPlacement::After);
+                    ui.close_menu();
+                }
+            }
+            
+            if can_paste_values && ui.button(format!("Paste Values ({})", self.shortcuts.keys_text(Command::PasteValues))).clicked() {
+                self.select_path(&path);
+                self.paste_values_into(&path);
+                ui.close_menu();
+            }
+            
+            if !node.is_expandable() {
+                if ui.button(format!("Copy Value ({})", self.shortcuts.keys_text(Command::CopyValue))).clicked() {
+                    self.copy_leaf_value(&path);
+                    ui.close_menu();
+                }
+                if let Some(copied) = &self.value_clipboard {
+                    let reason = node.value.coerce_from(copied).err();
+                    let paste = ui.add_enabled(reason.is_none(), egui::Button::new(format!("Paste Value ({})", self.shortcuts.keys_text(Command::PasteValue))))
+                        .on_disabled_hover_text(reason.unwrap_or_default());
+                    if paste.clicked() {
+                        self.paste_leaf_value(&path);
+                        ui.close_menu();
+                    }
+                }
+            }
+            
+            for (hash, text) in [(unlabeled_key, "Name Key Hash..."), (unlabeled_value, "Name Value Hash...")] {
+                if let Some(hash) = hash {
+                    if ui.button(text).clicked() {
+                        self.select_path(&path);
+                        self.naming_hash = Some((hash, String::new()));
+                        ui.close_menu();
+                    }
+                }
+            }
+            
+            if !display_key_fields.is_empty() {
+                ui.menu_button("Label Items By", |ui| {
+                    let current = self.list_display_keys.get(&path).copied();
+                    if ui.radio(current.is_none(), "[index]").clicked() {
+                        self.list_display_keys.remove(&path);
+                        ui.close_menu();
+                    }
+                    for (hash, name) in &display_key_fields {
+                        if ui.radio(current == Some(*hash), name).clicked() {
+                            self.list_display_keys.insert(path.clone(), *hash);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                
+                ui.menu_button("Sort Items By", |ui| {
+                    for (hash, name) in &display_key_fields {
+                        if ui.button(name).clicked() {
+                            self.sort_list_by_field(&path, *hash);
+                            ui.close_menu();
+                        }
+                    }
+                });
+            }
+        });
+        
+        if self.scroll_to_selected && is_selected {
+            response.scroll_to_me(Some(egui::Align::Center));
+            self.scroll_to_selected = false;
         }
 
         // Show children if expanded
-        if is_expanded && node.is_expandable() {
+        if is_expanded && node.has_children() {
             ui.indent(egui::Id::new(format!("{}_indent", path)), |ui| {
-                for (i, child) in node.children.iter().enumerate() {
+                for (i, child) in node.children(&self.param_file.hash_labels).iter().enumerate() {
                     let child_path = format!("{}[{}]", path, i);
+                    if self.hidden_by_value_filter(&child_path) {
+                        continue;
+                    }
                     self.show_tree_node(ui, child, child_path);
                 }
             });
         }
     }
 
+    /// Tree rows can be dragged onto each other: the top or bottom edge of a row inserts the
+    /// dragged node before or after it, the middle of a struct or list row moves it inside.
+    /// `row_top` is the first node of a folded row, which is where siblings are inserted.
+    fn handle_tree_drag_and_drop(&mut self, ui: &egui::Ui, response: &egui::Response, node: &NodeRef<'_>, path: &str, row_top: &str) {
+        if row_top != "root" {
+            let drag = response.interact(egui::Sense::drag());
+            drag.dnd_set_drag_payload(DraggedNode(row_top.to_string()));
+            if drag.dragged() {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+            }
+        }
+        
+        let (Some(dragged), Some(pointer)) = (response.dnd_hover_payload::<DraggedNode>(), ui.ctx().pointer_interact_pos()) else {
+            return;
+        };
+        let rect = response.rect;
+        let edge = rect.height() / 4.0;
+        let placement = if row_top == "root" {
+            DropPlacement::Into
+        } else if pointer.y < rect.top() + edge {
+            DropPlacement::Before
+        } else if pointer.y > rect.bottom() - edge {
+            DropPlacement::After
+        } else {
+            DropPlacement::Into
+        };
+        
+        let target = match placement {
+            DropPlacement::Into => Some((path.to_string(), node.child_count())),
+            _ => self.get_parent_path(row_top).zip(self.get_node_index_in_parent(row_top))
+                .map(|(parent, index)| (parent, if placement == DropPlacement::After { index + 1 } else { index })),
+        };
+        let Some((target_parent, target_index)) = target else {
+            return;
+        };
+        let check = if placement == DropPlacement::Into && !node.is_expandable() {
+            Err("Can only drop into a struct or list".to_string())
+        } else {
+            self.check_move(&dragged.0, &target_parent)
+        };
+        
+        let painter = ui.painter();
+        let stroke = egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE);
+        match (&check, placement) {
+            (Err(_), _) => {
+                painter.rect_stroke(rect.expand(2.0), 2.0, egui::Stroke::new(2.0, egui::Color32::RED));
+                ui.ctx().set_cursor_icon(egui::CursorIcon::NotAllowed);
+            }
+            (Ok(()), DropPlacement::Before) => {
+                painter.hline(rect.x_range(), rect.top(), stroke);
+            }
+            (Ok(()), DropPlacement::After) => {
+                painter.hline(rect.x_range(), rect.bottom(), stroke);
+            }
+            (Ok(()), DropPlacement::Into) => {
+                painter.rect_stroke(rect.expand(2.0), 2.0, stroke);
+            }
+        }
+        
+        if response.dnd_release_payload::<DraggedNode>().is_some() {
+            match check {
+                Ok(()) => {
+                    self.move_node_with_undo(&dragged.0, &target_parent, target_index);
+                }
+                Err(reason) => self.status_message = format!("Can't move {}: {}", dragged.0, reason),
+            }
+        }
+    }
+
+    /// Follow a chain of containers that each hold exactly one container, when folding is on.
+    /// Returns the (path, label) of each folded node, and the node and path the row represents.
+    fn fold_single_child_chain<'a>(&self, mut node: NodeRef<'a>, mut path: String) -> (Vec<(String, String)>, NodeRef<'a>, String) {
+        let mut folded = Vec::new();
+        if !self.fold_single_child {
+            return (folded, node, path);
+        }
+        
+        while node.child_count() == 1 {
+            let Some(child) = node.child(0, &self.param_file.hash_labels).filter(|c| c.is_expandable()) else {
+                break;
+            };
+            let label = self.list_item_label(&path, &node).unwrap_or_else(|| {
+                if node.name.is_empty() || node.name.starts_with("0x") {
+                    format!("0x{:X}", node.hash)
+                } else {
+                    truncate_chars(&node.name, 25)
+                }
+            });
+            let child_path = format!("{}[0]", path);
+            folded.push((std::mem::replace(&mut path, child_path), label));
+            node = child;
+        }
+        (folded, node, path)
+    }
+
     fn show_parameter_details(&mut self, ui: &mut egui::Ui, selected_path: &str) {
         // Parse the path to find the selected node
         if let Some(node) = self.find_node_by_path(selected_path) {
-            let node_clone = node.clone(); // Clone to avoid borrowing issues
+            let owned_node = node.to_owned_node(); // Clone to avoid borrowing issues
+            let node_clone = owned_node.view();
             
+            self.show_breadcrumb(ui, selected_path);
             ui.heading(&format!("Parameter: {}", if node_clone.name.is_empty() { format!("0x{:X}", node_clone.hash) } else { node_clone.name.clone() }));
             ui.separator();
             
@@ -518,7 +1975,7 @@ impl PrcEditorApp {
                 egui::Grid::new("param_details")
                     .num_columns(2)
                     .striped(true)
-                    .spacing([20.0, 4.0])
+                    .spacing(self.grid_spacing(20.0, 4.0))
                     .show(ui, |ui| {
                         ui.strong("Name:");
                         
@@ -557,9 +2014,7 @@ impl PrcEditorApp {
                                             format!("Node renamed to '{}' (hash: 0x{:X}) and saved to {}", final_name, new_hash, path_display)
                                         };
                                         self.status_message = message;
-                                        // Rebuild tree to show updated name
-                                        self.param_file.rebuild_tree_with_labels();
-                                    } else {
+                                    } else if self.pending_key_overwrite.is_none() {
                                         self.status_message = "Failed to update node name".to_string();
                                     }
                                 } else {
@@ -583,38 +2038,214 @@ impl PrcEditorApp {
                             }
                             
                             if response.hovered() {
-                                response.on_hover_text("Click to rename");
+                                response.on_hover_text(self.key_hover_text(node_clone.hash, "Click to rename"));
                             }
                         }
                         ui.end_row();
                         
-                        ui.strong("Hash:");
-                        ui.monospace(format!("0x{:X}", node_clone.hash));
-                        ui.end_row();
+                        if let Some(range) = self.value_range(selected_path) {
+                            self.show_range_row(ui, selected_path, node_clone.value, range);
+                        }
+                        
+                        if self.is_struct_field(selected_path) {
+                            self.show_description_row(ui, selected_path, node_clone.hash);
+                        }
+                        
+                        let (unlabeled_key, unlabeled_value) = self.unlabeled_hashes_at(selected_path);
+                        
+                        ui.strong("Hash:");
+                        ui.horizontal(|ui| {
+                            ui.monospace(format!("0x{:X}", node_clone.hash));
+                            if let Some(hash) = unlabeled_key {
+                                if ui.small_button("Name this hash").clicked() {
+                                    self.naming_hash = Some((hash, String::new()));
+                                }
+                            }
+                        });
+                        ui.end_row();
                         
                         ui.strong("Type:");
-                        ui.label(node_clone.get_type_name());
+                        if matches!(node_clone.value, ParamValue::RawString(_)) {
+                            ui.label("String (not UTF-8)").on_hover_text(
+                                "Bytes that aren't valid UTF-8 are shown and edited as \\xNN, and a backslash as \\\\. They are saved unchanged."
+                            );
+                        } else {
+                            ui.label(node_clone.get_type_name());
+                        }
                         ui.end_row();
                         
                         ui.strong("Value:");
-                        ui.monospace(node_clone.get_value_string_with_labels(&self.param_file.hash_labels));
+                        ui.horizontal(|ui| {
+                            let value_text = self.value_rich_text(&node_clone, self.display_value(selected_path, &node_clone));
+                            let response = ui.label(value_text);
+                            if self.flag_special_floats && node_clone.value.is_special_float() {
+                                response.on_hover_text("Special float value - edit the bits below to set or clear it exactly");
+                            }
+                            if self.float_digits.is_some() && matches!(node_clone.value, ParamValue::F32(_)) {
+                                let mut full = self.full_precision_paths.contains(selected_path);
+                                if ui.checkbox(&mut full, "Full precision").on_hover_text("Show this field's exact value").changed() {
+                                    if full {
+                                        self.full_precision_paths.insert(selected_path.to_string());
+                                    } else {
+                                        self.full_precision_paths.remove(selected_path);
+                                    }
+                                }
+                            }
+                            if let Some(hash) = unlabeled_value {
+                                if ui.small_button("Name this hash").clicked() {
+                                    self.naming_hash = Some((hash, String::new()));
+                                }
+                            }
+                        });
+                        ui.end_row();
+                        
+                        // Exact bit pattern of floats; the only way to keep a particular NaN payload
+                        if let ParamValue::F32(v) = node_clone.value {
+                            ui.strong("Bits:");
+                            let bits_edit_path = format!("{}_bits", selected_path);
+                            let editing_bits = self.editing_value.as_ref()
+                                .filter(|(path, _)| path == &bits_edit_path)
+                                .map(|(_, text)| text.clone());
+                            
+                            if let Some(mut edit_bits) = editing_bits {
+                                let response = ui.text_edit_singleline(&mut edit_bits);
+                                if response.lost_focus() || ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                    let hex = edit_bits.trim();
+                                    let hex = if hex.starts_with("0x") || hex.starts_with("0X") { hex.to_string() } else { format!("0x{}", hex) };
+                                    self.status_message = match node_clone.value.parse_float(&hex) {
+                                        Some(new_value) if self.update_node_value_with_undo(selected_path, new_value.clone()) => {
+                                            format!("Float bits set to {}", hex)
+                                        }
+                                        Some(_) => "Failed to update value".to_string(),
+                                        None => format!("Invalid float bits: {}", edit_bits),
+                                    };
+                                    self.editing_value = None;
+                                } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                                    self.editing_value = None;
+                                } else {
+                                    self.editing_value = Some((bits_edit_path, edit_bits));
+                                }
+                            } else {
+                                let bits = format!("0x{:08X}", v.to_bits());
+                                let response = ui.add(egui::Label::new(egui::RichText::new(&bits).monospace()).sense(egui::Sense::click()));
+                                if response.clicked() {
+                                    self.editing_value = Some((bits_edit_path, bits));
+                                }
+                                response.on_hover_text("Click to edit the raw IEEE 754 bits");
+                            }
+                            ui.end_row();
+                        }
+                        
+                        // Type byte + payload; the bytes are re-parsed and must describe a valid leaf
+                        if let Some(bytes) = self.raw_byte_editing.then(|| node_clone.value.leaf_bytes()).flatten() {
+                            ui.strong("Raw:");
+                            let raw_edit_path = format!("{}_raw", selected_path);
+                            let editing_raw = self.editing_value.as_ref()
+                                .filter(|(path, _)| path == &raw_edit_path)
+                                .map(|(_, text)| text.clone());
+                            
+                            if let Some(mut edit_raw) = editing_raw {
+                                let response = ui.add(egui::TextEdit::singleline(&mut edit_raw).font(egui::TextStyle::Monospace));
+                                if response.lost_focus() || ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                                    let parsed = parse_hex_bytes(&edit_raw).and_then(|bytes| ParamValue::from_leaf_bytes(&bytes));
+                                    self.status_message = match parsed {
+                                        Ok(new_value) => {
+                                            let type_name = new_value.type_name();
+                                            if self.update_node_value_with_undo(selected_path, new_value) {
+                                                format!("Raw bytes set ({})", type_name)
+                                            } else {
+                                                "Failed to update value".to_string()
+                                            }
+                                        }
+                                        Err(e) => format!("Invalid raw bytes: {}", e),
+                                    };
+                                    self.editing_value = None;
+                                } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                                    self.editing_value = None;
+                                } else {
+                                    self.editing_value = Some((raw_edit_path, edit_raw));
+                                }
+                            } else {
+                                let raw = format_hex_bytes(&bytes);
+                                let response = ui.add(egui::Label::new(egui::RichText::new(&raw).monospace()).sense(egui::Sense::click()));
+                                if response.clicked() {
+                                    self.editing_value = Some((raw_edit_path, raw));
+                                }
+                                response.on_hover_text("Type byte then little-endian payload (hash40 as 8 bytes, strings as their bytes). Click to edit");
+                            }
+                            ui.end_row();
+                        }
+                        
+                        if let Some(reference) = &self.reference_file {
+                            ui.strong("Reference:");
+                            match self.find_reference_node(selected_path) {
+                                Some(ref_node) => ui.monospace(self.display_value(selected_path, &ref_node)),
+                                None => ui.weak("(not present)"),
+                            }.on_hover_text(format!("Value at the same label-path in {}", reference.get_filename()));
+                            ui.end_row();
+                        }
+                        
+                        ui.strong("Size:");
+                        match self.node_serialized_size(selected_path, node_clone.value) {
+                            Some(size) => ui.label(format!("{} bytes", size))
+                                .on_hover_text("Serialized param data for this node (strings and struct ref tables are stored separately)"),
+                            None => ui.label("-"),
+                        };
                         ui.end_row();
                         
+                        // Padding after the param data belongs to the file rather than any node
+                        if selected_path == "root" {
+                            ui.strong("Trailing bytes:");
+                            match self.param_file.trailing_bytes() {
+                                Some(trailing) => {
+                                    let kept = if self.param_file.keep_trailing_bytes { "Saves write them back" } else { "Saves drop them" };
+                                    ui.label(trailing.to_string()).on_hover_text(format!("{} (Preferences > Saving)", kept));
+                                }
+                                None => {
+                                    ui.weak("none");
+                                }
+                            }
+                            ui.end_row();
+                        }
+                        
                         match &node_clone.value {
                             ParamValue::Struct(s) => {
                                 ui.strong("Fields:");
                                 ui.label(format!("{} fields", s.fields.len()));
                                 ui.end_row();
+                                
+                                ui.strong("Path:");
+                                ui.monospace(selected_path);
+                                ui.end_row();
                             }
                             ParamValue::List(l) => {
                                 ui.strong("Items:");
                                 ui.label(format!("{} items", l.values.len()));
                                 ui.end_row();
+                                
+                                ui.strong("Path:");
+                                ui.monospace(selected_path);
+                                ui.end_row();
                             }
                             _ => {}
                         }
                     });
                 
+                if let Some(values) = self.numeric_child_values(selected_path, node_clone.value) {
+                    ui.add_space(5.0);
+                    Self::show_value_chart(ui, &values);
+                }
+                
+                if self.naming_hash.is_some() {
+                    ui.add_space(5.0);
+                    self.show_name_hash_input(ui, selected_path);
+                }
+                
+                if node_clone.is_expandable() {
+                    ui.add_space(5.0);
+                    self.show_container_actions(ui, &node_clone, selected_path);
+                }
+                
                 ui.add_space(10.0);
                 
                 // Show editing interface based on parameter type
@@ -635,26 +2266,515 @@ impl PrcEditorApp {
         }
     }
     
-    fn show_struct_editor(&mut self, ui: &mut egui::Ui, node: &ParamNode, _selected_path: &str) {
+    /// Values of a container's children for the details chart, when at least three quarters
+    /// of them (and at least 3) are numbers; other children are NaN. Cached like the size.
+    fn numeric_child_values(&mut self, path: &str, value: &ParamValue) -> Option<Vec<f64>> {
+        let revision = self.param_file.revision();
+        let cached = self.value_chart_cache.as_ref()
+            .filter(|(cached_path, cached_revision, _)| cached_path == path && *cached_revision == revision);
+        
+        let values = match cached {
+            Some((_, _, values)) => values.clone(),
+            None => {
+                let children: Vec<&ParamValue> = match value {
+                    ParamValue::Struct(s) => s.fields.values().collect(),
+                    ParamValue::List(l) => l.values.iter().collect(),
+                    _ => Vec::new(),
+                };
+                let values: Vec<f64> = children.iter().map(|child| child.as_f64().unwrap_or(f64::NAN)).collect();
+                let numeric = values.iter().filter(|v| v.is_finite()).count();
+                let values = if numeric >= 3 && numeric * 4 >= values.len() * 3 { values } else { Vec::new() };
+                self.value_chart_cache = Some((path.to_string(), revision, values.clone()));
+                values
+            }
+        };
+        
+        (!values.is_empty()).then_some(values)
+    }
+    
+    /// Small bar chart of child values (a line once there are too many for bars), with the
+    /// hovered item's index and value in a tooltip
+    fn show_value_chart(ui: &mut egui::Ui, values: &[f64]) {
+        let finite = || values.iter().copied().filter(|v| v.is_finite());
+        let min = finite().fold(f64::INFINITY, f64::min);
+        let max = finite().fold(f64::NEG_INFINITY, f64::max);
+        let mean = finite().sum::<f64>() / finite().count() as f64;
+        
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(ui.available_width().min(400.0), 60.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        
+        // Zero sits on the baseline when every value is on one side of it
+        let low = min.min(0.0);
+        let high = max.max(0.0);
+        let span = if high > low { high - low } else { 1.0 };
+        let y_of = |v: f64| rect.bottom() - ((v - low) / span) as f32 * rect.height();
+        let step = rect.width() / values.len() as f32;
+        let color = ui.visuals().selection.bg_fill;
+        
+        if values.len() <= 64 {
+            for (i, v) in values.iter().enumerate().filter(|(_, v)| v.is_finite()) {
+                let x = rect.left() + i as f32 * step;
+                let bar = egui::Rect::from_x_y_ranges(x + 1.0..=x + step - 1.0, y_of(v.max(0.0).min(high))..=y_of(v.min(0.0).max(low)));
+                painter.rect_filled(bar, 0.0, color);
+            }
+        } else {
+            let points: Vec<egui::Pos2> = values.iter().enumerate()
+                .filter(|(_, v)| v.is_finite())
+                .map(|(i, v)| egui::pos2(rect.left() + (i as f32 + 0.5) * step, y_of(*v)))
+                .collect();
+            painter.add(egui::Shape::line(points, egui::Stroke::new(1.0, color)));
+        }
+        if low < 0.0 {
+            painter.hline(rect.x_range(), y_of(0.0), egui::Stroke::new(1.0, ui.visuals().weak_text_color()));
+        }
+        
+        if let Some(pointer) = response.hover_pos() {
+            let index = (((pointer.x - rect.left()) / step) as usize).min(values.len() - 1);
+            let text = if values[index].is_finite() { format!("[{}] {}", index, values[index]) } else { format!("[{}] not a number", index) };
+            response.on_hover_text_at_pointer(text);
+        }
+        
+        ui.weak(format!("min {} | max {} | mean {:.4}", min, max, mean));
+    }
+    
+    /// Serialized size of the node at `path`, cached until the selection or the tree changes
+    fn node_serialized_size(&mut self, path: &str, value: &ParamValue) -> Option<usize> {
+        let revision = self.param_file.revision();
+        if let Some((cached_path, cached_revision, size)) = &self.node_size_cache {
+            if cached_path == path && *cached_revision == revision {
+                return Some(*size);
+            }
+        }
+        
+        let size = self.param_file.serialized_size(value).ok()?;
+        self.node_size_cache = Some((path.to_string(), revision, size));
+        Some(size)
+    }
+    
+    /// How a save would compare with the opened file, cached until the tree changes
+    fn save_ordering(&mut self) -> Option<SaveOrdering> {
+        let revision = self.param_file.revision();
+        if let Some((cached_revision, ordering)) = self.save_ordering_cache {
+            if cached_revision == revision {
+                return ordering;
+            }
+        }
+        
+        let ordering = self.param_file.save_ordering();
+        self.save_ordering_cache = Some((revision, ordering));
+        ordering
+    }
+    
+    /// Short description of `ordering` for the status bar and save messages
+    fn save_ordering_text(ordering: SaveOrdering) -> &'static str {
+        match (ordering.byte_identical, ordering.hash_table_kept) {
+            (true, _) => "byte-identical round-trip",
+            (false, true) => "original hash order kept",
+            (false, false) => "hash table reordered",
+        }
+    }
+    
+    /// Value text for the node at `path`, honouring the integer display mode and float precision.
+    /// Flagged NaN/Inf floats include their bits, e.g. "NaN (0x7FC00000)".
+    fn display_value(&self, path: &str, node: &NodeRef<'_>) -> String {
+        let float_digits = self.float_digits.filter(|_| !self.full_precision_paths.contains(path));
+        self.format_value(node, float_digits)
+    }
+    
+    /// Value text to start editing from; floats are never rounded so nothing is lost on commit
+    fn edit_value_text(&self, node: &NodeRef<'_>) -> String {
+        self.format_value(node, None)
+    }
+    
+    fn format_value(&self, node: &NodeRef<'_>, float_digits: Option<usize>) -> String {
+        if let (ParamValue::F32(v), true) = (node.value, self.flag_special_floats && node.value.is_special_float()) {
+            return format!("{} (0x{:08X})", v, v.to_bits());
+        }
+        node.value.format_integer(self.int_display)
+            .unwrap_or_else(|| node.get_value_string_with_labels(&self.param_file.hash_labels, float_digits))
+    }
+    
+    /// Monospace value text, drawn in orange for flagged NaN/Inf floats
+    fn value_rich_text(&self, node: &NodeRef<'_>, text: String) -> egui::RichText {
+        let rich = egui::RichText::new(text).monospace();
+        if self.flag_special_floats && node.value.is_special_float() {
+            rich.color(egui::Color32::from_rgb(255, 140, 0))
+        } else {
+            rich
+        }
+    }
+    
+    /// Tree label for a list item whose list has a display key field, e.g. "[3] mario".
+    /// None when no display key is set or the item doesn't have that field.
+    fn list_item_label(&self, path: &str, node: &NodeRef<'_>) -> Option<String> {
+        let parent = self.get_parent_path(path)?;
+        let key_hash = *self.list_display_keys.get(&parent)?;
+        let field = node.children(&self.param_file.hash_labels).into_iter().find(|child| child.hash == key_hash)?;
+        let index = &path[parent.len()..];
+        Some(format!("{} {}", index, field.get_value_string_with_labels(&self.param_file.hash_labels, self.float_digits)))
+    }
+    
+    /// Fields of a list's first struct item that can label its items (hash, name)
+    fn list_display_key_candidates(&self, node: &NodeRef<'_>) -> Vec<(u64, String)> {
+        if !matches!(node.value, ParamValue::List(_)) {
+            return Vec::new();
+        }
+        let Some(first) = node.child(0, &self.param_file.hash_labels).filter(|item| matches!(item.value, ParamValue::Struct(_))) else {
+            return Vec::new();
+        };
+        
+        first.children(&self.param_file.hash_labels).iter()
+            .filter(|field| !field.is_expandable())
+            .map(|field| (field.hash, field.name.clone()))
+            .collect()
+    }
+    
+    /// Stable-sort a list's items by one of their fields as a single undo step
+    fn sort_list_by_field(&mut self, path: &str, field_hash: u64) {
+        let Some(ParamValue::List(mut list)) = self.param_file.get_node_value(path) else {
+            return;
+        };
+        list.sort_by_field(field_hash, &self.param_file.hash_labels);
+        
+        let field_name = self.param_file.hash_labels.hash_to_string(field_hash);
+        if !self.update_node_value_with_undo(path, ParamValue::List(list)) {
+            self.status_message = format!("Failed to sort {}", path);
+            return;
+        }
+        
+        // Item paths now point at different items: collapse them and move the selection up to the list
+        let inside_list = |p: &String| p != path && is_same_or_descendant(p, path);
+        self.expanded_nodes.retain(|p| !inside_list(p));
+        self.multi_selection.retain(|p| !inside_list(p));
+        self.list_display_keys.retain(|p, _| !inside_list(p));
+        if self.selected_node.as_ref().is_some_and(inside_list) {
+            self.select_path(path);
+        }
+        if self.pinned_details.as_ref().is_some_and(inside_list) {
+            self.pinned_details = None;
+        }
+        self.status_message = format!("Sorted {} by {} (Ctrl+Z to undo)", path, field_name);
+    }
+    
+    /// Whether the node at `path` is a field of a struct, rather than a list item or the root
+    fn is_struct_field(&self, path: &str) -> bool {
+        self.get_parent_path(path)
+            .and_then(|parent| self.find_node_by_path(&parent))
+            .is_some_and(|parent| matches!(parent.value, ParamValue::Struct(_)))
+    }
+    
+    /// Details row showing the field's description; click to edit, clear the text to remove it
+    fn show_description_row(&mut self, ui: &mut egui::Ui, path: &str, hash: u64) {
+        ui.strong("Description:");
+        let edit_path = format!("{}_desc", path);
+        let editing = self.editing_value.as_ref()
+            .filter(|(edit, _)| edit == &edit_path)
+            .map(|(_, text)| text.clone());
+        
+        if let Some(mut text) = editing {
+            ui.vertical(|ui| {
+                let response = ui.add(egui::TextEdit::multiline(&mut text).desired_rows(3).hint_text("What this field does"));
+                ui.weak("Click outside to save, Esc to cancel");
+                if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    self.editing_value = None;
+                } else if response.lost_focus() {
+                    if self.descriptions.get(hash).unwrap_or("") != text.trim() {
+                        self.set_description(hash, &text);
+                    }
+                    self.editing_value = None;
+                } else {
+                    self.editing_value = Some((edit_path, text));
+                }
+            });
+        } else {
+            let current = self.descriptions.get(hash).map(str::to_string);
+            let text = match &current {
+                Some(description) => egui::RichText::new(description),
+                None => egui::RichText::new("None - click to add").weak(),
+            };
+            let response = ui.add(egui::Label::new(text).wrap().sense(egui::Sense::click()))
+                .on_hover_text("Click to edit this field's description");
+            if response.clicked() {
+                self.editing_value = Some((edit_path, current.unwrap_or_default()));
+            }
+        }
+        ui.end_row();
+    }
+    
+    /// Key hash (for struct fields) and hash40 value at `path` that have no exact label
+    fn unlabeled_hashes_at(&self, path: &str) -> (Option<u64>, Option<u64>) {
+        let Some(node) = self.find_node_by_path(path) else {
+            return (None, None);
+        };
+        let labels = &self.param_file.hash_labels;
+        
+        let is_struct_field = self.is_struct_field(path);
+        let key = Some(node.hash).filter(|hash| is_struct_field && !labels.has_exact_label(*hash));
+        
+        let value = match node.value {
+            ParamValue::Hash(hash) if !labels.has_exact_label(*hash) => Some(*hash),
+            _ => None,
+        };
+        
+        (key, value)
+    }
+    
+    /// Labels near `path` to build label guesses from: sibling keys and hash40 values, and
+    /// when the parent is a list item, the value under the same key in the other items
+    fn label_suggestion_context(&self, path: &str) -> Vec<String> {
+        const MAX_CONTEXT_LABELS: usize = 64;
+        let labels = &self.param_file.hash_labels;
+        let mut context: Vec<String> = Vec::new();
+        let mut add = |hash: u64| {
+            if let Some(label) = labels.get_label(hash) {
+                if context.len() < MAX_CONTEXT_LABELS && !context.contains(label) {
+                    context.push(label.clone());
+                }
+            }
+        };
+        
+        let Some(parent_path) = self.get_parent_path(path) else {
+            return context;
+        };
+        let siblings: Vec<(Option<u64>, &ParamValue)> = match self.find_node_by_path(&parent_path).map(|parent| parent.value) {
+            Some(ParamValue::Struct(s)) => s.fields.iter().map(|(key, value)| (Some(*key), value)).collect(),
+            Some(ParamValue::List(l)) => l.values.iter().map(|value| (None, value)).collect(),
+            _ => Vec::new(),
+        };
+        for (key, value) in siblings {
+            if let Some(key) = key {
+                add(key);
+            }
+            if let ParamValue::Hash(hash) = value {
+                add(*hash);
+            }
+        }
+        
+        let key = self.find_node_by_path(path).map(|node| node.hash);
+        let grandparent = self.get_parent_path(&parent_path).and_then(|grandparent| self.find_node_by_path(&grandparent));
+        if let (Some(key), Some(ParamValue::List(items))) = (key, grandparent.map(|node| node.value)) {
+            for item in &items.values {
+                if let ParamValue::Struct(s) = item {
+                    if let Some(ParamValue::Hash(hash)) = s.fields.get(&key) {
+                        add(*hash);
+                    }
+                }
+            }
+        }
+        
+        context
+    }
+    
+    /// Inline input for giving the hash in `naming_hash` (found at `path`) a label
+    fn show_name_hash_input(&mut self, ui: &mut egui::Ui, path: &str) {
+        let Some((hash, mut label)) = self.naming_hash.clone() else {
+            return;
+        };
+        
+        if self.naming_suggestions.as_ref().map(|(suggested_for, _)| *suggested_for) != Some(hash) {
+            let context = self.label_suggestion_context(path);
+            let context: Vec<&str> = context.iter().map(String::as_str).collect();
+            self.naming_suggestions = Some((hash, self.param_file.hash_labels.suggest_labels(hash, &context)));
+        }
+        
+        let mut submit = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            ui.label(format!("Label for 0x{:X}:", hash));
+            let response = ui.text_edit_singleline(&mut label);
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                submit = true;
+            }
+            if ui.button("Save").clicked() {
+                submit = true;
+            }
+            if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                cancel = true;
+            }
+        });
+        
+        if let Some((_, suggestions)) = self.naming_suggestions.as_ref().filter(|(_, suggestions)| !suggestions.is_empty()) {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Suggestions:");
+                for suggestion in suggestions {
+                    if ui.small_button(suggestion).on_hover_text("Hashes to this value. Click to use it").clicked() {
+                        label = suggestion.clone();
+                    }
+                }
+            });
+        }
+        
+        if cancel {
+            self.naming_hash = None;
+            return;
+        }
+        
+        if !submit {
+            self.naming_hash = Some((hash, label));
+            return;
+        }
+        
+        let label = label.trim().to_string();
+        if label.is_empty() {
+            self.status_message = "Label cannot be empty".to_string();
+            self.naming_hash = Some((hash, label));
+            return;
+        }
+        
+        match self.change_label_with_undo(hash, Some(&label)) {
+            Ok(()) => {
+                self.status_message = match &self.param_labels_path {
+                    Some(path) => format!("Named 0x{:X} as '{}' and saved to {}", hash, label, path),
+                    None => format!("Named 0x{:X} as '{}' (not saved - no labels file loaded)", hash, label),
+                };
+            }
+            Err(e) => {
+                self.status_message = format!("Named 0x{:X} as '{}' but failed to save labels: {}", hash, label, e);
+            }
+        }
+        self.naming_hash = None;
+    }
+    
+    /// Clickable root-to-node breadcrumb; deep paths keep the root and the last few ancestors
+    fn show_breadcrumb(&mut self, ui: &mut egui::Ui, selected_path: &str) {
+        const MAX_SEGMENTS: usize = 6;
+        const TAIL_SEGMENTS: usize = 4;
+        
+        let segments = self.label_path_segments(selected_path);
+        let mut clicked_path = None;
+        
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+            let skip = if segments.len() > MAX_SEGMENTS { segments.len() - TAIL_SEGMENTS } else { 1 };
+            
+            for (i, (segment_path, label)) in segments.iter().enumerate() {
+                if i > 0 && i < skip {
+                    if i == 1 {
+                        ui.label(">");
+                        ui.weak("...").on_hover_text(format!("{} hidden levels", skip - 1));
+                    }
+                    continue;
+                }
+                if i > 0 {
+                    ui.label(">");
+                }
+                
+                let is_current = segment_path == selected_path;
+                let response = ui.selectable_label(is_current, truncate_chars(label, 20)).on_hover_text(label);
+                if response.clicked() && !is_current {
+                    clicked_path = Some(segment_path.clone());
+                }
+            }
+        });
+        
+        if let Some(path) = clicked_path {
+            self.expand_to_path(&path);
+            self.select_path(&path);
+            self.build_tree_items();
+            self.selected_index = self.tree_items.iter().position(|item| item == &path);
+        }
+    }
+    
+    /// Container-level actions: convert between struct and list, clear all children
+    fn show_container_actions(&mut self, ui: &mut egui::Ui, node: &NodeRef<'_>, selected_path: &str) {
+        let is_root = selected_path == "root";
+        let (converted, convert_label) = match &node.value {
+            ParamValue::Struct(s) => (s.to_list().map(ParamValue::List), "Convert to List"),
+            ParamValue::List(l) => (l.to_struct().map(ParamValue::Struct), "Convert to Struct"),
+            _ => return,
+        };
+        ui.horizontal(|ui| {
+            let can_convert = converted.is_some() && !is_root;
+            let convert_button = ui.add_enabled(can_convert, egui::Button::new(convert_label));
+            if convert_button.clicked() {
+                if let Some(new_value) = converted {
+                    if self.update_node_value_with_undo(selected_path, new_value) {
+                        self.status_message = format!("{} at {}", convert_label, selected_path);
+                    } else {
+                        self.status_message = format!("Failed to convert {}", selected_path);
+                    }
+                }
+            } else if !can_convert {
+                let reason = if is_root {
+                    "The root must stay a struct"
+                } else if matches!(node.value, ParamValue::Struct(_)) {
+                    "Only empty structs or structs whose fields share one type can become a list"
+                } else {
+                    "Only lists of single-field structs with distinct keys can become a struct"
+                };
+                convert_button.on_disabled_hover_text(reason);
+            }
+            
+            let has_unlabeled = self.labelable_children(selected_path).iter().any(|(_, hash)| !self.param_file.hash_labels.has_exact_label(*hash));
+            let sequence_button = ui.add_enabled(has_unlabeled, egui::Button::new("Label Unlabeled Children..."))
+                .on_disabled_hover_text("Every field key (or hash40 item) already has a label");
+            if sequence_button.clicked() {
+                self.sequence_labels = Some((selected_path.to_string(), self.sequence_label_base(selected_path)));
+            }
+            
+            let has_children = node.has_children();
+            if ui.add_enabled(has_children, egui::Button::new("Clear Children")).clicked() {
+                self.request_clear_children(selected_path, node.child_count());
+            }
+        });
+    }
+    
+    fn show_struct_editor(&mut self, ui: &mut egui::Ui, node: &NodeRef<'_>, _selected_path: &str) {
         ui.separator();
         ui.horizontal(|ui| {
             ui.heading("Fields");
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                if ui.button("+ Add Field").clicked() {
-                    self.status_message = "Add field functionality coming soon".to_string();
+                if ui.button("+ Add Field").on_hover_text("Insert with a field selected").clicked() {
+                    self.open_add_field(_selected_path);
                 }
             });
         });
         ui.add_space(5.0);
         
+        if self.add_field.as_ref().is_some_and(|(path, _)| path == _selected_path) {
+            self.show_add_field_form(ui, _selected_path);
+            ui.add_space(5.0);
+        }
+        
+        if !node.has_children() {
+            ui.weak("This struct has no fields. Use + Add Field to create one.");
+            return;
+        }
+        
         let mut new_editing_value = self.editing_value.clone();
         let mut new_status_message = None;
         
+        let children = node.children(&self.param_file.hash_labels);
+        let groups = if self.group_struct_fields { self.struct_field_groups(&children) } else { None };
+        let spacing = self.grid_spacing(15.0, 6.0);
         egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            // Sections only change what's shown together; fields keep their order in the file
+            if let Some(groups) = groups {
+                for (category, indices) in groups {
+                    egui::CollapsingHeader::new(format!("{} ({})", category, indices.len()))
+                        .id_source(("struct_field_group", &category))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            egui::Grid::new(("struct_fields", &category))
+                                .num_columns(5)
+                                .striped(true)
+                                .spacing(spacing)
+                                .min_col_width(120.0)
+                                .show(ui, |ui| {
+                                    for i in indices {
+                                        self.show_struct_field_row(ui, &children[i], i, _selected_path, &mut new_editing_value, &mut new_status_message);
+                                    }
+                                });
+                        });
+                }
+                return;
+            }
+            
             egui::Grid::new("struct_fields")
                 .num_columns(5)
                 .striped(true)
-                .spacing([15.0, 6.0])
+                .spacing(spacing)
                 .min_col_width(120.0)
                 .show(ui, |ui| {
                     ui.strong("Key");
@@ -664,211 +2784,8 @@ impl PrcEditorApp {
                     ui.strong("Actions");
                     ui.end_row();
                     
-                    for (i, child) in node.children.iter().enumerate() {
-                        let child_path = format!("{}[{}]", _selected_path, i);
-                        
-                        // Key/Name column - editable
-                        let key_edit_path = format!("{}_key", child_path);
-                        let is_editing_key = new_editing_value.as_ref()
-                            .map(|(path, _)| path == &key_edit_path)
-                            .unwrap_or(false);
-                        
-                        if is_editing_key {
-                            let mut edit_key = new_editing_value.as_ref().unwrap().1.clone();
-                            let response = ui.text_edit_singleline(&mut edit_key);
-                            
-                            if response.lost_focus() || ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                                // Generate hash for new key name
-                                let new_hash = self.param_file.hash_labels.add_label_and_save(&edit_key, self.param_labels_path.as_deref());
-                                
-                                // Actually update the node using the new method with undo tracking
-                                if self.update_node_key_with_undo(&child_path, edit_key.clone(), new_hash) {
-                                    let path_display = self.param_labels_path.as_deref().unwrap_or("ParamLabels.csv");
-                                    new_status_message = Some(format!("Key renamed to '{}' (hash: 0x{:X}) and saved to {}", edit_key, new_hash, path_display));
-                                    // Refresh tree to show updated keys
-                                    // self.refresh_tree();
-                                } else {
-                                    new_status_message = Some("Failed to update key".to_string());
-                                }
-                                new_editing_value = None;
-                            } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
-                                new_editing_value = None;
-                            } else {
-                                new_editing_value = Some((key_edit_path.clone(), edit_key));
-                            }
-                        } else {
-                            let display_name = if child.name.len() > 15 {
-                                format!("{}...", &child.name[..12])
-                            } else {
-                                child.name.clone()
-                            };
-                            
-                            let response = ui.add(
-                                egui::Label::new(egui::RichText::new(display_name).strong())
-                                    .sense(egui::Sense::click())
-                            );
-                            
-                            if response.clicked() {
-                                new_editing_value = Some((key_edit_path, child.name.clone()));
-                            }
-                            
-                            if response.hovered() {
-                                response.on_hover_text("Click to rename key");
-                            }
-                        }
-                        
-                        // Hash column (read-only)
-                        ui.monospace(format!("0x{:X}", child.hash));
-                        
-                        // Type column with dropdown
-                        egui::ComboBox::from_id_source(format!("type_{}", i))
-                            .selected_text(child.get_type_name())
-                            .show_ui(ui, |ui| {
-                                let types = ["bool", "sbyte", "byte", "short", "ushort", "int", "uint", "float", "hash40", "string", "list", "struct"];
-                                for type_name in types {
-                                    if ui.selectable_label(false, type_name).clicked() {
-                                        new_status_message = Some(format!("Type changed to {}", type_name));
-                                    }
-                                }
-                            });
-                        
-                        // Value column
-                        let is_editing = new_editing_value.as_ref()
-                            .map(|(path, _)| path == &child_path)
-                            .unwrap_or(false);
-                        
-                        if is_editing {
-                            let mut edit_value = new_editing_value.as_ref().unwrap().1.clone();
-                            let response = ui.text_edit_singleline(&mut edit_value);
-                            
-                            if response.lost_focus() || ui.input(|i| i.key_pressed(egui::Key::Enter)) {
-                                // If it's a Hash40 value and looks like a label, generate hash
-                                if matches!(child.value, ParamValue::Hash(_)) && !edit_value.starts_with("0x") {
-                                    let hash = self.param_file.hash_labels.add_label_and_save(&edit_value, self.param_labels_path.as_deref());
-                                    
-                                    // Actually update the hash value using the new method with undo tracking
-                                    if self.update_node_value_with_undo(&child_path, ParamValue::Hash(hash)) {
-                                        let path_display = self.param_labels_path.as_deref().unwrap_or("ParamLabels.csv");
-                                        new_status_message = Some(format!("Hash40 value set to '{}' (0x{:X}) and saved to {}", edit_value, hash, path_display));
-                                        // Refresh tree to show updated values
-                                        // self.refresh_tree();
-                                    } else {
-                                        new_status_message = Some("Failed to update hash value".to_string());
-                                    }
-                                } else {
-                                    // Try to parse the value based on the current type
-                                    let updated_value = match &child.value {
-                                        ParamValue::Bool(_) => {
-                                            if let Ok(val) = edit_value.parse::<bool>() {
-                                                Some(ParamValue::Bool(val))
-                                            } else if edit_value.to_lowercase() == "true" {
-                                                Some(ParamValue::Bool(true))
-                                            } else if edit_value.to_lowercase() == "false" {
-                                                Some(ParamValue::Bool(false))
-                                            } else { None }
-                                        }
-                                        ParamValue::I8(_) => {
-                                            if let Ok(val) = edit_value.parse::<i8>() {
-                                                Some(ParamValue::I8(val))
-                                            } else { None }
-                                        }
-                                        ParamValue::U8(_) => {
-                                            if let Ok(val) = edit_value.parse::<u8>() {
-                                                Some(ParamValue::U8(val))
-                                            } else { None }
-                                        }
-                                        ParamValue::I16(_) => {
-                                            if let Ok(val) = edit_value.parse::<i16>() {
-                                                Some(ParamValue::I16(val))
-                                            } else { None }
-                                        }
-                                        ParamValue::U16(_) => {
-                                            if let Ok(val) = edit_value.parse::<u16>() {
-                                                Some(ParamValue::U16(val))
-                                            } else { None }
-                                        }
-                                        ParamValue::I32(_) => {
-                                            if let Ok(val) = edit_value.parse::<i32>() {
-                                                Some(ParamValue::I32(val))
-                                            } else { None }
-                                        }
-                                        ParamValue::U32(_) => {
-                                            if let Ok(val) = edit_value.parse::<u32>() {
-                                                Some(ParamValue::U32(val))
-                                            } else { None }
-                                        }
-                                        ParamValue::F32(_) => {
-                                            if let Ok(val) = edit_value.parse::<f32>() {
-                                                Some(ParamValue::F32(val))
-                                            } else { None }
-                                        }
-                                        ParamValue::String(_) => {
-                                            Some(ParamValue::String(edit_value.clone()))
-                                        }
-                                        ParamValue::Hash(_) => {
-                                            if let Ok(val) = u64::from_str_radix(&edit_value.trim_start_matches("0x"), 16) {
-                                                Some(ParamValue::Hash(val))
-                                            } else { None }
-                                        }
-                                        _ => None,
-                                    };
-                                    
-                                    if let Some(new_value) = updated_value {
-                                        if self.update_node_value_with_undo(&child_path, new_value.clone()) {
-                                            new_status_message = Some(format!("Value updated to: {}", edit_value));
-                                            // Refresh tree to show updated values
-                                            // self.refresh_tree();
-                                        } else {
-                                            new_status_message = Some("Failed to update value".to_string());
-                                        }
-                                    } else {
-                                        new_status_message = Some(format!("Invalid value for type: {}", edit_value));
-                                    }
-                                }
-                                new_editing_value = None;
-                            } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
-                                new_editing_value = None;
-                            } else {
-                                new_editing_value = Some((child_path.clone(), edit_value));
-                            }
-                        } else {
-                            let value_str = child.get_value_string_with_labels(&self.param_file.hash_labels);
-                            let display_value = if value_str.len() > 25 {
-                                format!("{}...", &value_str[..22])
-                            } else {
-                                value_str.clone()
-                            };
-                            
-                            let response = ui.add(
-                                egui::Label::new(egui::RichText::new(display_value).monospace())
-                                    .sense(egui::Sense::click())
-                            );
-                            
-                            if response.clicked() {
-                                new_editing_value = Some((child_path.clone(), value_str));
-                            }
-                            
-                            if response.hovered() {
-                                response.on_hover_text("Click to edit");
-                            }
-                        }
-                        
-                        // Actions column
-                        ui.horizontal(|ui| {
-                            if ui.small_button("✏").on_hover_text("Edit Value").clicked() {
-                                let value_str = child.get_value_string_with_labels(&self.param_file.hash_labels);
-                                new_editing_value = Some((child_path.clone(), value_str));
-                            }
-                            if ui.small_button("🔄").on_hover_text("Rename Key").clicked() {
-                                let key_edit_path = format!("{}_key", child_path);
-                                new_editing_value = Some((key_edit_path, child.name.clone()));
-                            }
-                            if ui.small_button("🗑").on_hover_text("Delete").clicked() {
-                                new_status_message = Some(format!("Delete field: {}", child.name));
-                            }
-                        });
-                        
-                        ui.end_row();
+                    for (i, child) in children.iter().enumerate() {
+                        self.show_struct_field_row(ui, child, i, _selected_path, &mut new_editing_value, &mut new_status_message);
                     }
                 });
         });
@@ -879,7 +2796,328 @@ impl PrcEditorApp {
         }
     }
     
-    fn show_list_editor(&mut self, ui: &mut egui::Ui, node: &ParamNode, _selected_path: &str) {
+    /// One Key/Hash/Type/Value/Actions row of the struct editor for field `i` of the struct at
+    /// `struct_path`. Edits are collected in `new_editing_value` and `new_status_message`.
+    fn show_struct_field_row(
+        &mut self,
+        ui: &mut egui::Ui,
+        child: &NodeRef<'_>,
+        i: usize,
+        struct_path: &str,
+        new_editing_value: &mut Option<(String, String)>,
+        new_status_message: &mut Option<String>,
+    ) {
+        let child_path = format!("{}[{}]", struct_path, i);
+        
+        // Key/Name column - editable
+        let key_edit_path = format!("{}_key", child_path);
+        let is_editing_key = new_editing_value.as_ref()
+            .map(|(path, _)| path == &key_edit_path)
+            .unwrap_or(false);
+        
+        if is_editing_key {
+            let mut edit_key = new_editing_value.as_ref().unwrap().1.clone();
+            let response = ui.text_edit_singleline(&mut edit_key);
+            
+            if response.lost_focus() || ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                // Generate hash for new key name
+                let new_hash = self.param_file.hash_labels.add_label_and_save(&edit_key, self.param_labels_path.as_deref());
+                
+                // Actually update the node using the new method with undo tracking
+                if self.update_node_key_with_undo(&child_path, edit_key.clone(), new_hash) {
+                    let path_display = self.param_labels_path.as_deref().unwrap_or("ParamLabels.csv");
+                    *new_status_message = Some(format!("Key renamed to '{}' (hash: 0x{:X}) and saved to {}", edit_key, new_hash, path_display));
+                    // Refresh tree to show updated keys
+                    // self.refresh_tree();
+                } else if self.pending_key_overwrite.is_none() {
+                    *new_status_message = Some("Failed to update key".to_string());
+                }
+                *new_editing_value = None;
+            } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                *new_editing_value = None;
+            } else {
+                *new_editing_value = Some((key_edit_path.clone(), edit_key));
+            }
+        } else {
+            let display_name = truncate_chars(&child.name, 15);
+            
+            let response = ui.add(
+                egui::Label::new(egui::RichText::new(display_name).strong())
+                    .sense(egui::Sense::click())
+            );
+            
+            if response.clicked() {
+                *new_editing_value = Some((key_edit_path, child.name.clone()));
+            }
+            
+            if response.hovered() {
+                response.on_hover_text(self.key_hover_text(child.hash, "Click to rename key"));
+            }
+        }
+        
+        // Hash column (read-only)
+        ui.monospace(format!("0x{:X}", child.hash));
+        
+        // Type column with dropdown
+        egui::ComboBox::from_id_source(format!("type_{}", i))
+            .selected_text(child.get_type_name())
+            .show_ui(ui, |ui| {
+                let types = ["bool", "sbyte", "byte", "short", "ushort", "int", "uint", "float", "hash40", "string", "list", "struct"];
+                for type_name in types {
+                    if ui.selectable_label(false, type_name).clicked() {
+                        *new_status_message = Some(format!("Type changed to {}", type_name));
+                    }
+                }
+            });
+        
+        // Value column
+        let is_editing = new_editing_value.as_ref()
+            .map(|(path, _)| path == &child_path)
+            .unwrap_or(false);
+        
+        if is_editing {
+            let mut edit_value = new_editing_value.as_ref().unwrap().1.clone();
+            let response = ui.text_edit_singleline(&mut edit_value);
+            
+            if response.lost_focus() || ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                // Hash40 values take a 0x hash or a label
+                if matches!(child.value, ParamValue::Hash(_)) {
+                    *new_status_message = Some(self.apply_hash_edit(&child_path, &edit_value));
+                } else {
+                    // Try to parse the value based on the current type
+                    let updated_value = match &child.value {
+                        ParamValue::Bool(_) => {
+                            if let Ok(val) = edit_value.parse::<bool>() {
+                                Some(ParamValue::Bool(val))
+                            } else if edit_value.to_lowercase() == "true" {
+                                Some(ParamValue::Bool(true))
+                            } else if edit_value.to_lowercase() == "false" {
+                                Some(ParamValue::Bool(false))
+                            } else { None }
+                        }
+                        // Integers accept whichever form is displayed (signed, unsigned or hex)
+                        ParamValue::I8(_) | ParamValue::U8(_) | ParamValue::I16(_) |
+                        ParamValue::U16(_) | ParamValue::I32(_) | ParamValue::U32(_) => {
+                            child.value.parse_integer(&edit_value)
+                        }
+                        // Floats also accept NaN/inf and exact 0x bits
+                        ParamValue::F32(_) => child.value.parse_float(&edit_value),
+                        ParamValue::String(_) => {
+                            Some(ParamValue::String(edit_value.clone()))
+                        }
+                        // Edited in the escaped form it is shown in
+                        ParamValue::RawString(_) => unescape_raw_string(&edit_value).ok().map(|bytes| ParamValue::string_from_bytes(&bytes)),
+                        _ => None,
+                    };
+                    
+                    if let Some(violation) = updated_value.as_ref().and_then(|new_value| self.range_violation(&child_path, new_value)) {
+                        *new_status_message = Some(format!("Edit rejected: {}", violation));
+                    } else if let Some(new_value) = updated_value {
+                        if self.update_node_value_with_undo(&child_path, new_value.clone()) {
+                            *new_status_message = Some(format!("Value updated to: {}", edit_value));
+                            // Refresh tree to show updated values
+                            // self.refresh_tree();
+                        } else {
+                            *new_status_message = Some("Failed to update value".to_string());
+                        }
+                    } else {
+                        *new_status_message = Some(format!("Invalid value for type: {}", edit_value));
+                    }
+                }
+                *new_editing_value = None;
+            } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                *new_editing_value = None;
+            } else {
+                *new_editing_value = Some((child_path.clone(), edit_value));
+            }
+        } else {
+            let display_value = truncate_chars(&self.display_value(&child_path, child), 25);
+            
+            let response = ui.add(
+                egui::Label::new(self.value_rich_text(child, display_value))
+                    .sense(egui::Sense::click())
+            );
+            
+            if response.clicked() {
+                *new_editing_value = Some((child_path.clone(), self.edit_value_text(child)));
+            }
+            
+            if response.hovered() {
+                response.on_hover_text("Click to edit");
+            }
+        }
+        
+        // Actions column
+        ui.horizontal(|ui| {
+            if ui.small_button("✏").on_hover_text("Edit Value").clicked() {
+                *new_editing_value = Some((child_path.clone(), self.edit_value_text(child)));
+            }
+            if ui.small_button("🔄").on_hover_text("Rename Key").clicked() {
+                let key_edit_path = format!("{}_key", child_path);
+                *new_editing_value = Some((key_edit_path, child.name.clone()));
+            }
+            if ui.small_button("🗑").on_hover_text("Delete").clicked() {
+                *new_status_message = Some(format!("Delete field: {}", child.name));
+            }
+        });
+        ui.end_row();
+    }
+    
+    /// Sections for the struct editor: fields whose description starts with `[Category]` go
+    /// under that category, the rest under the part of their label before the first `_` when
+    /// another field shares it, and anything left under "Other". Sections are ordered by their
+    /// first field. None when that gives fewer than two sections, so the flat grid is used.
+    fn struct_field_groups(&self, children: &[NodeRef<'_>]) -> Option<Vec<(String, Vec<usize>)>> {
+        let prefix = |child: &NodeRef<'_>| {
+            let labelled = !child.name.starts_with("0x");
+            child.name.split_once('_').map(|(prefix, _)| prefix.to_string()).filter(|prefix| labelled && !prefix.is_empty())
+        };
+        let mut prefix_counts: HashMap<String, usize> = HashMap::new();
+        for child in children {
+            if let Some(prefix) = prefix(child) {
+                *prefix_counts.entry(prefix).or_default() += 1;
+            }
+        }
+        
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        let mut other = Vec::new();
+        for (i, child) in children.iter().enumerate() {
+            let category = self.descriptions.category(child.hash).map(str::to_string)
+                .or_else(|| prefix(child).filter(|prefix| prefix_counts[prefix] > 1));
+            match category {
+                Some(category) => match groups.iter_mut().find(|(name, _)| *name == category) {
+                    Some((_, indices)) => indices.push(i),
+                    None => groups.push((category, vec![i])),
+                },
+                None => other.push(i),
+            }
+        }
+        if !other.is_empty() {
+            groups.push(("Other".to_string(), other));
+        }
+        
+        (groups.len() > 1).then_some(groups)
+    }
+    
+    /// Open the Add Field form on the struct holding the node in the details panel (or on that
+    /// node when it is a struct itself), showing that struct in the details panel
+    fn add_sibling_field(&mut self) {
+        let Some(path) = self.pinned_details.clone().or_else(|| self.selected_node.clone()) else {
+            return;
+        };
+        let target = if self.is_struct_field(&path) {
+            self.get_parent_path(&path)
+        } else {
+            Some(path).filter(|path| matches!(self.find_node_by_path(path).map(|n| n.value), Some(ParamValue::Struct(_))))
+        };
+        let Some(target) = target else {
+            self.status_message = "Insert adds a field beside a struct field - select one first".to_string();
+            return;
+        };
+        
+        if self.pinned_details.is_some() {
+            self.pinned_details = Some(target.clone());
+        }
+        self.jump_to_path(&target);
+        self.open_add_field(&target);
+    }
+    
+    /// Open the inline Add Field form on the struct at `struct_path` with an empty key
+    fn open_add_field(&mut self, struct_path: &str) {
+        self.add_field = Some((struct_path.to_string(), String::new()));
+        self.add_field_focus = true;
+    }
+    
+    /// Key box and type picker for a new field. Enter adds it and clears the key for the next
+    /// one; Escape closes the form.
+    fn show_add_field_form(&mut self, ui: &mut egui::Ui, struct_path: &str) {
+        let Some((_, mut key)) = self.add_field.clone() else {
+            return;
+        };
+        
+        let mut add = false;
+        let mut close = false;
+        ui.horizontal(|ui| {
+            ui.label("Key:");
+            let response = ui.add(egui::TextEdit::singleline(&mut key).hint_text("label or 0x...").desired_width(180.0));
+            if std::mem::take(&mut self.add_field_focus) {
+                response.request_focus();
+            }
+            if response.lost_focus() {
+                if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    add = true;
+                } else if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    close = true;
+                }
+            }
+            
+            egui::ComboBox::from_id_source("add_field_type")
+                .selected_text(self.add_field_type)
+                .show_ui(ui, |ui| {
+                    for type_name in ParamValue::TYPE_NAMES {
+                        ui.selectable_value(&mut self.add_field_type, type_name, type_name);
+                    }
+                });
+            
+            add |= ui.add_enabled(!key.trim().is_empty(), egui::Button::new("Add")).clicked();
+            close |= ui.button("Close").clicked();
+        });
+        
+        if close {
+            self.add_field = None;
+            return;
+        }
+        if add && !key.trim().is_empty() && self.add_field_with_undo(struct_path, key.trim(), self.add_field_type) {
+            key.clear();
+        }
+        if add {
+            self.add_field_focus = true;
+        }
+        self.add_field = Some((struct_path.to_string(), key));
+    }
+    
+    /// Add a field of `type_name` keyed by `key` (a label or a 0x hash) to the end of a struct.
+    /// A key the struct already has is refused rather than renamed.
+    fn add_field_with_undo(&mut self, struct_path: &str, key: &str, type_name: &str) -> bool {
+        let Some(value) = ParamValue::default_of_type(type_name) else {
+            return false;
+        };
+        let hash = match key.strip_prefix("0x").map(|hex| u64::from_str_radix(hex, 16)) {
+            Some(Ok(hash)) => hash,
+            Some(Err(_)) => {
+                self.status_message = format!("Invalid hash: {}", key);
+                return false;
+            }
+            None => self.param_file.hash_labels.string_to_hash40(key),
+        };
+        
+        match self.find_node_by_path(struct_path).map(|node| node.value) {
+            Some(ParamValue::Struct(s)) if s.fields.contains_key(&hash) => {
+                self.status_message = format!("This struct already has a field '{}'", key);
+                return false;
+            }
+            Some(ParamValue::Struct(_)) => {}
+            _ => {
+                self.status_message = "Fields can only be added to a struct".to_string();
+                return false;
+            }
+        }
+        
+        if !key.starts_with("0x") {
+            self.param_file.hash_labels.add_label_and_save(key, self.param_labels_path.as_deref());
+        }
+        let name = self.param_file.hash_labels.hash_to_string(hash);
+        if !self.add_node_with_undo(struct_path, ParamNode::new(name, hash, value)) {
+            self.status_message = format!("Failed to add field '{}'", key);
+            return false;
+        }
+        
+        self.expanded_nodes.insert(struct_path.to_string());
+        self.status_message = format!("Added {} field '{}' to {}", type_name, key, struct_path);
+        true
+    }
+    
+    fn show_list_editor(&mut self, ui: &mut egui::Ui, node: &NodeRef<'_>, _selected_path: &str) {
         ui.separator();
         ui.horizontal(|ui| {
             ui.heading("Items");
@@ -891,6 +3129,11 @@ impl PrcEditorApp {
         });
         ui.add_space(5.0);
         
+        if !node.has_children() {
+            ui.weak("This list has no items. Use + Add Item to create one.");
+            return;
+        }
+        
         let mut new_editing_value = self.editing_value.clone();
         let mut new_status_message = None;
         
@@ -898,7 +3141,7 @@ impl PrcEditorApp {
             egui::Grid::new("list_items")
                 .num_columns(4)
                 .striped(true)
-                .spacing([15.0, 6.0])
+                .spacing(self.grid_spacing(15.0, 6.0))
                 .min_col_width(80.0)
                 .show(ui, |ui| {
                     ui.strong("Index");
@@ -907,7 +3150,7 @@ impl PrcEditorApp {
                     ui.strong("Actions");
                     ui.end_row();
                     
-                    for (i, child) in node.children.iter().enumerate() {
+                    for (i, child) in node.children(&self.param_file.hash_labels).iter().enumerate() {
                         let child_path = format!("{}[{}]", _selected_path, i);
                         
                         // Index column
@@ -943,20 +3186,15 @@ impl PrcEditorApp {
                                 new_editing_value = Some((child_path.clone(), edit_value));
                             }
                         } else {
-                            let value_str = child.get_value_string_with_labels(&self.param_file.hash_labels);
-                            let display_value = if value_str.len() > 25 {
-                                format!("{}...", &value_str[..22])
-                            } else {
-                                value_str.clone()
-                            };
+                            let display_value = truncate_chars(&self.display_value(&child_path, child), 25);
                             
                             let response = ui.add(
-                                egui::Label::new(egui::RichText::new(display_value).monospace())
+                                egui::Label::new(self.value_rich_text(child, display_value))
                                     .sense(egui::Sense::click())
                             );
                             
                             if response.clicked() {
-                                new_editing_value = Some((child_path.clone(), value_str));
+                                new_editing_value = Some((child_path.clone(), self.edit_value_text(child)));
                             }
                             
                             if response.hovered() {
@@ -967,8 +3205,7 @@ impl PrcEditorApp {
                         // Actions column
                         ui.horizontal(|ui| {
                             if ui.small_button("✏").on_hover_text("Edit").clicked() {
-                                let value_str = child.get_value_string_with_labels(&self.param_file.hash_labels);
-                                new_editing_value = Some((child_path.clone(), value_str));
+                                new_editing_value = Some((child_path.clone(), self.edit_value_text(child)));
                             }
                             if ui.small_button("🗑").on_hover_text("Delete").clicked() {
                                 new_status_message = Some(format!("Delete item {}", i));
@@ -986,7 +3223,7 @@ impl PrcEditorApp {
         }
     }
     
-    fn show_value_editor(&mut self, ui: &mut egui::Ui, node: &ParamNode, selected_path: &str) {
+    fn show_value_editor(&mut self, ui: &mut egui::Ui, node: &NodeRef<'_>, selected_path: &str) {
         ui.separator();
         ui.heading("Edit Value");
         ui.add_space(5.0);
@@ -997,7 +3234,7 @@ impl PrcEditorApp {
         egui::Grid::new("value_editor")
             .num_columns(3)
             .striped(false)
-            .spacing([15.0, 8.0])
+            .spacing(self.grid_spacing(15.0, 8.0))
             .show(ui, |ui| {
                 ui.strong("Type:");
                 
@@ -1036,14 +3273,14 @@ impl PrcEditorApp {
                         new_editing_value = Some((selected_path.to_string(), edit_value));
                     }
                 } else {
-                    let value_str = node.get_value_string_with_labels(&self.param_file.hash_labels);
+                    let value_str = self.display_value(selected_path, node);
                     let response = ui.add(
                         egui::Label::new(egui::RichText::new(&value_str).monospace())
                             .sense(egui::Sense::click())
                     );
                     
                     if response.clicked() {
-                        new_editing_value = Some((selected_path.to_string(), value_str));
+                        new_editing_value = Some((selected_path.to_string(), self.edit_value_text(node)));
                     }
                     
                     if response.hovered() {
@@ -1053,8 +3290,7 @@ impl PrcEditorApp {
                 
                 // Edit button
                 if ui.button("Edit").clicked() {
-                    let value_str = node.get_value_string_with_labels(&self.param_file.hash_labels);
-                    new_editing_value = Some((selected_path.to_string(), value_str));
+                    new_editing_value = Some((selected_path.to_string(), self.edit_value_text(node)));
                 }
                 
                 ui.end_row();
@@ -1066,13 +3302,126 @@ impl PrcEditorApp {
         }
     }
     
-    /// Delete a node at the given path
+    /// Every keyboard shortcut grouped by category, filtered by key or action. Bindings are
+    /// changed here; keys shared by several commands are flagged.
+    fn show_shortcuts_window(&mut self, ctx: &egui::Context) {
+        if !self.show_shortcuts {
+            return;
+        }
+        
+        let warning = egui::Color32::from_rgb(255, 140, 0);
+        let mut open = true;
+        let mut changed = false;
+        egui::Window::new("Keyboard Shortcuts")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Filter:");
+                    ui.text_edit_singleline(&mut self.shortcuts_filter);
+                });
+                ui.add_space(5.0);
+                
+                let filter = self.shortcuts_filter.trim().to_lowercase();
+                let matches = |info: &&CommandInfo, keys: &str| {
+                    filter.is_empty()
+                        || keys.to_lowercase().contains(&filter)
+                        || info.action.to_lowercase().contains(&filter)
+                        || info.category.to_lowercase().contains(&filter)
+                };
+                
+                let mut any = false;
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    let mut categories: Vec<&str> = COMMANDS.iter().map(|info| info.category).collect();
+                    categories.dedup();
+                    for category in categories {
+                        let commands: Vec<&CommandInfo> = COMMANDS.iter()
+                            .filter(|info| info.category == category)
+                            .filter(|info| matches(info, &self.shortcuts.keys_text(info.command)))
+                            .collect();
+                        if commands.is_empty() {
+                            continue;
+                        }
+                        any = true;
+                        
+                        ui.strong(category);
+                        egui::Grid::new(format!("shortcuts_{}", category)).num_columns(3).spacing([15.0, 2.0]).show(ui, |ui| {
+                            for info in commands {
+                                ui.horizontal(|ui| {
+                                    if self.capturing_shortcut.is_some_and(|(command, _)| command == info.command) {
+                                        ui.colored_label(egui::Color32::YELLOW, "Press a key... (Esc cancels)");
+                                    } else {
+                                        ui.monospace(self.shortcuts.keys_text(info.command));
+                                    }
+                                    let conflicts = self.shortcuts.conflicts_with(info.command);
+                                    if !conflicts.is_empty() {
+                                        let actions: Vec<&str> = conflicts.iter().map(|command| command_info(*command).action).collect();
+                                        ui.colored_label(warning, "⚠")
+                                            .on_hover_text(format!("Also bound to: {}\nPressing the key runs all of them", actions.join(", ")));
+                                    }
+                                });
+                                ui.label(info.action);
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("Change").on_hover_text("Replace the bindings with the next key pressed").clicked() {
+                                        self.capturing_shortcut = Some((info.command, false));
+                                    }
+                                    if ui.small_button("Add").on_hover_text("Bind the next key pressed as well").clicked() {
+                                        self.capturing_shortcut = Some((info.command, true));
+                                    }
+                                    if ui.small_button("Clear").on_hover_text("Leave the command without a shortcut").clicked() {
+                                        self.shortcuts.set_bindings(info.command, Vec::new());
+                                        changed = true;
+                                    }
+                                    let is_default = self.shortcuts.is_default(info.command);
+                                    if ui.add_enabled(!is_default, egui::Button::new("Reset").small()).clicked() {
+                                        self.shortcuts.reset(info.command);
+                                        changed = true;
+                                    }
+                                });
+                                ui.end_row();
+                            }
+                        });
+                        ui.add_space(6.0);
+                    }
+                });
+                if !any {
+                    ui.weak(format!("No shortcuts match '{}'", self.shortcuts_filter.trim()));
+                }
+                
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Reset All").clicked() {
+                        self.shortcuts.reset_all();
+                        self.capturing_shortcut = None;
+                        changed = true;
+                    }
+                    let conflicts = self.shortcuts.conflict_count();
+                    if conflicts > 0 {
+                        ui.colored_label(warning, format!("⚠ {} commands share a key with another command", conflicts));
+                    }
+                });
+            });
+        
+        if changed {
+            self.save_preferences();
+        }
+        // While rebinding, Escape only cancels the capture (it never reaches here)
+        if !open || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.show_shortcuts = false;
+            self.capturing_shortcut = None;
+        }
+    }
+
     fn delete_node(&mut self, path: &str) -> bool {
         // Cannot delete root
         if path == "root" {
             return false;
         }
         
+        // Sibling paths shift, so multi-selected paths may no longer point at the same nodes
+        self.multi_selection.clear();
+        
         let indices = match self.param_file.parse_node_path(path) {
             Some(indices) => indices,
             None => return false,
@@ -1084,7 +3433,7 @@ impl PrcEditorApp {
         
         // Get the node to delete for undo purposes
         let node_to_delete = match self.find_node_by_path(path) {
-            Some(node) => node.clone(),
+            Some(node) => node.to_owned_node(),
             None => return false,
         };
         
@@ -1100,6 +3449,20 @@ impl PrcEditorApp {
         // Delete from the underlying data structure
         if let Some(root) = &mut self.param_file.root {
             if Self::delete_from_param_value(&mut root.value, parent_indices, delete_index, 0) {
+                // Keep the selection and pin on the same node when an earlier sibling goes away
+                if let Some(selected) = self.selected_node.as_deref().filter(|selected| !is_same_or_descendant(selected, path)) {
+                    self.selected_node = Some(path_after_removal(selected, &parent_path, delete_index));
+                }
+                match self.pinned_details.as_deref() {
+                    Some(pinned) if is_same_or_descendant(pinned, path) => self.pinned_details = None,
+                    Some(pinned) => self.pinned_details = Some(path_after_removal(pinned, &parent_path, delete_index)),
+                    None => {}
+                }
+                self.recent_edits = self.recent_edits.iter()
+                    .filter(|recent| !is_same_or_descendant(recent, path))
+                    .map(|recent| path_after_removal(recent, &parent_path, delete_index))
+                    .collect();
+                
                 // Record undo action
                 self.push_undo_action(UndoAction::DeleteNode {
                     path: path.to_string(),
@@ -1108,8 +3471,7 @@ impl PrcEditorApp {
                     index: delete_index,
                 });
                 
-                // Also delete from the display tree
-                Self::delete_from_display_tree(&mut self.param_file.root, parent_indices, delete_index, 0);
+                self.param_file.mark_modified();
                 return true;
             }
         }
@@ -1165,38 +3527,108 @@ impl PrcEditorApp {
         false
     }
     
-    /// Delete from the display tree
-    fn delete_from_display_tree(
-        node: &mut Option<ParamNode>,
-        parent_indices: &[usize],
-        delete_index: usize,
-        depth: usize
-    ) -> bool {
-        if let Some(current_node) = node {
-            if depth == parent_indices.len() {
-                // We're at the parent, delete the child
-                if delete_index < current_node.children.len() {
-                    current_node.children.remove(delete_index);
-                    return true;
-                }
+    /// Reject pasting the clipboard node into itself or one of its descendants.
+    /// Sets the status message and returns false when the target is not allowed.
+    fn check_paste_target(&mut self, target_path: &str) -> bool {
+        // A cut node is already out of the tree, and its old path now belongs to a later sibling
+        if let Some(source_path) = self.clipboard.as_ref().filter(|_| !self.cut_mode) {
+            if is_same_or_descendant(target_path, source_path) {
+                self.status_message = format!("Cannot paste {} into itself or one of its descendants ({})", source_path, target_path);
                 return false;
             }
-            
-            // Continue recursing
-            let current_index = parent_indices[depth];
-            if current_index < current_node.children.len() {
-                let mut child_option = Some(std::mem::replace(&mut current_node.children[current_index], ParamNode::new("temp".to_string(), 0, ParamValue::Bool(false))));
-                let result = Self::delete_from_display_tree(&mut child_option, parent_indices, delete_index, depth + 1);
-                if let Some(updated_child) = child_option {
-                    current_node.children[current_index] = updated_child;
-                }
-                return result;
-            }
         }
-        false
+        true
     }
     
-    /// Paste a node into the target path
+    /// Why `source` can't be moved into the container at `target_parent`, if it can't
+    fn check_move(&self, source: &str, target_parent: &str) -> Result<(), String> {
+        let Some(source_parent) = self.get_parent_path(source) else {
+            return Err("the root can't be moved".to_string());
+        };
+        if is_same_or_descendant(target_parent, source) {
+            return Err("a node can't be moved into itself or one of its descendants".to_string());
+        }
+        let (Some(target), Some(node)) = (self.find_node_by_path(target_parent), self.find_node_by_path(source)) else {
+            return Err("node not found".to_string());
+        };
+        
+        match target.value {
+            ParamValue::Struct(s) => {
+                if self.find_node_by_path(&source_parent).is_some_and(|p| matches!(p.value, ParamValue::List(_))) {
+                    return Err("list items have no key to use as a struct field".to_string());
+                }
+                if target_parent != source_parent && s.fields.contains_key(&node.hash) {
+                    return Err(format!("{} already has a field named {}", target_parent, node.name));
+                }
+                Ok(())
+            }
+            ParamValue::List(_) => Ok(()),
+            _ => Err("can only drop into a struct or list".to_string()),
+        }
+    }
+    
+    /// Move `source` to `target_index` among the children of `target_parent` (both addressed
+    /// as before the move) as one undo step, and select it at its new path
+    fn move_node_with_undo(&mut self, source: &str, target_parent: &str, target_index: usize) -> bool {
+        if let Err(reason) = self.check_move(source, target_parent) {
+            self.status_message = format!("Can't move {}: {}", source, reason);
+            return false;
+        }
+        let (Some(from_parent), Some(from_index), Some(node)) = (
+            self.get_parent_path(source),
+            self.get_node_index_in_parent(source),
+            self.find_node_by_path(source),
+        ) else {
+            return false;
+        };
+        let hash = node.hash;
+        let same_parent = target_parent == from_parent;
+        let child_count = self.find_node_by_path(target_parent).map_or(0, |t| t.child_count()) - usize::from(same_parent);
+        
+        // Taking the node out shifts its later siblings, and anything under them, up by one
+        let to_parent = path_after_removal(target_parent, &from_parent, from_index);
+        let to_index = if same_parent && target_index > from_index { target_index - 1 } else { target_index }.min(child_count);
+        if same_parent && to_index == from_index {
+            return false; // Dropped back where it was
+        }
+        
+        let Some(new_path) = self.move_node_raw(&from_parent, from_index, hash, &to_parent, to_index) else {
+            self.status_message = format!("Failed to move {}", source);
+            return false;
+        };
+        self.push_undo_action(UndoAction::Move { from_parent, from_index, to_parent, to_index, hash });
+        self.select_path(&new_path);
+        self.status_message = format!("Moved {} to {}", source, new_path);
+        true
+    }
+    
+    /// Take the child at `from_parent[from_index]` out and insert it at `to_index` in
+    /// `to_parent`, which is addressed as after the removal. Returns the node's new path.
+    fn move_node_raw(&mut self, from_parent: &str, from_index: usize, hash: u64, to_parent: &str, to_index: usize) -> Option<String> {
+        let from_indices = self.param_file.parse_node_path(from_parent)?;
+        let to_indices = self.param_file.parse_node_path(to_parent)?;
+        let value = self.param_file.get_node_value(&format!("{}[{}]", from_parent, from_index))?;
+        let node = ParamNode::new(String::new(), hash, value);
+        
+        // Sibling paths shift, so multi-selected paths may no longer point at the same nodes
+        self.multi_selection.clear();
+        
+        let root = self.param_file.root.as_mut()?;
+        if !Self::delete_from_param_value(&mut root.value, &from_indices, from_index, 0) {
+            return None;
+        }
+        if !Self::restore_to_param_value(&mut root.value, &to_indices, node.clone(), to_index, 0) {
+            // Put it back rather than lose it
+            Self::restore_to_param_value(&mut root.value, &from_indices, node, from_index, 0);
+            return None;
+        }
+        
+        self.param_file.mark_modified();
+        self.build_tree_items();
+        Some(format!("{}[{}]", to_parent, to_index))
+    }
+    
+    /// Paste a node into the target path
     fn paste_node_into(&mut self, target_path: &str, node_to_paste: ParamNode) -> bool {
         // Get the target node to determine how to paste
         if let Some(target_node) = self.find_node_by_path(target_path) {
@@ -1219,7 +3651,179 @@ impl PrcEditorApp {
         false
     }
     
-
+    /// Paste the clipboard node into `sibling_path`'s parent just before or after it, as one
+    /// undo step. Indices are those of the tree as shown: a cut node has already been taken
+    /// out, so its later siblings have moved up and `sibling_path` is addressed accordingly.
+    fn paste_beside(&mut self, sibling_path: &str, placement: DropPlacement) -> bool {
+        let Some(mut node) = self.clipboard_data.clone() else {
+            self.status_message = "Nothing to paste".to_string();
+            return false;
+        };
+        let (Some(parent_path), Some(sibling_index)) = (self.get_parent_path(sibling_path), self.get_node_index_in_parent(sibling_path)) else {
+            self.status_message = "Root node has no siblings".to_string();
+            return false;
+        };
+        if !self.check_paste_target(&parent_path) {
+            return false;
+        }
+        let Some(parent) = self.find_node_by_path(&parent_path) else {
+            return false;
+        };
+        
+        // A struct field keeps its key unless a sibling already uses it
+        if let ParamValue::Struct(s) = &parent.value {
+            if s.fields.contains_key(&node.hash) {
+                node.name = self.generate_paste_name(&parent_path, &node.name);
+                node.hash = self.param_file.hash_labels.add_label_and_save(&node.name, self.param_labels_path.as_deref());
+            }
+        }
+        
+        let index = sibling_index + usize::from(placement == DropPlacement::After);
+        let Some(indices) = self.param_file.parse_node_path(&parent_path) else {
+            return false;
+        };
+        let Some(root) = self.param_file.root.as_mut() else {
+            return false;
+        };
+        if !Self::restore_to_param_value(&mut root.value, &indices, node, index, 0) {
+            self.status_message = format!("Failed to paste beside {}", sibling_path);
+            return false;
+        }
+        
+        self.param_file.mark_modified();
+        self.multi_selection.clear();
+        let pasted_path = format!("{}[{}]", parent_path, index);
+        self.push_undo_action(UndoAction::AddNode { path: pasted_path.clone() });
+        
+        let action = if self.cut_mode { "Moved" } else { "Pasted" };
+        self.status_message = format!("{} node to {}", action, pasted_path);
+        if self.cut_mode {
+            self.clipboard = None;
+            self.clipboard_data = None;
+            self.cut_origin = None;
+            self.cut_mode = false;
+        }
+        self.build_tree_items();
+        self.select_path(&pasted_path);
+        true
+    }
+    
+    /// "Paste values": copy the clipboard struct's leaf values into the struct at `target_path`,
+    /// matching fields by hash (list items by index) and leaving its structure alone.
+    /// All changed leaves form one undo step.
+    fn paste_values_into(&mut self, target_path: &str) {
+        let Some(source) = self.clipboard_data.as_ref().map(|n| n.value.clone()) else {
+            self.status_message = "Paste values: nothing to paste".to_string();
+            return;
+        };
+        let Some(target) = self.param_file.get_node_value(target_path) else {
+            return;
+        };
+        if !matches!((&source, &target), (ParamValue::Struct(_), ParamValue::Struct(_))) {
+            self.status_message = "Paste values needs a copied struct and a struct selected".to_string();
+            return;
+        }
+        
+        let mut updates = Vec::new();
+        let mut skipped = 0;
+        Self::collect_value_transplants(&source, &target, target_path.to_string(), &mut updates, &mut skipped);
+        
+        let mut actions = Vec::new();
+        for (path, old_value, new_value) in updates {
+            if self.param_file.update_node_value(&path, new_value.clone()) {
+                actions.push(UndoAction::UpdateValue { path, old_value, new_value });
+            }
+        }
+        let changed = actions.len();
+        if changed > 0 {
+            self.push_undo_action(UndoAction::Batch { actions });
+        }
+        
+        let mut message = format!("Pasted {} values into {}", changed, target_path);
+        if skipped > 0 {
+            message.push_str(&format!(" | {} mismatched fields skipped", skipped));
+        }
+        self.status_message = message;
+    }
+    
+    /// Copy the value of the leaf at `path` without its key, for Paste Value
+    fn copy_leaf_value(&mut self, path: &str) {
+        let Some(node) = self.find_node_by_path(path) else {
+            return;
+        };
+        if node.is_expandable() {
+            self.status_message = format!("Copy Value works on leaves; {} is a {}", path, node.get_type_name());
+            return;
+        }
+        
+        let text = self.display_value(path, &node);
+        let value = node.value.clone();
+        self.status_message = format!("Copied value {} ({})", text, value.type_name());
+        self.value_clipboard = Some(value);
+    }
+    
+    /// Set the leaf at `path` to the copied value, converted to the leaf's type where that
+    /// loses nothing (see `ParamValue::coerce_from`)
+    fn paste_leaf_value(&mut self, path: &str) {
+        let Some(copied) = self.value_clipboard.clone() else {
+            self.status_message = "Paste Value: no value copied - use Copy Value on a leaf first".to_string();
+            return;
+        };
+        let Some(node) = self.find_node_by_path(path) else {
+            return;
+        };
+        let value = match node.value.coerce_from(&copied) {
+            Ok(value) => value,
+            Err(reason) => {
+                self.status_message = format!("Can't paste value into {}: {}", path, reason);
+                return;
+            }
+        };
+        if let Some(violation) = self.range_violation(path, &value) {
+            self.status_message = format!("Paste rejected: {}", violation);
+            return;
+        }
+        
+        if self.update_node_value_with_undo(path, value) {
+            let text = self.find_node_by_path(path).map(|node| self.display_value(path, &node)).unwrap_or_default();
+            self.status_message = format!("Pasted value {} into {}", text, path);
+        } else {
+            self.status_message = format!("Failed to paste value into {}", path);
+        }
+    }
+    
+    /// Walk `source` and `target` together, recording (path, old, new) for every leaf whose
+    /// counterpart exists with the same type and a different value. Fields or items present
+    /// on only one side, and leaves whose types differ, are counted in `skipped`.
+    fn collect_value_transplants(
+        source: &ParamValue,
+        target: &ParamValue,
+        path: String,
+        updates: &mut Vec<(String, ParamValue, ParamValue)>,
+        skipped: &mut usize,
+    ) {
+        match (source, target) {
+            (ParamValue::Struct(s), ParamValue::Struct(t)) => {
+                *skipped += s.fields.keys().filter(|hash| !t.fields.contains_key(*hash)).count();
+                for (i, (hash, target_value)) in t.fields.iter().enumerate() {
+                    match s.fields.get(hash) {
+                        Some(source_value) => Self::collect_value_transplants(source_value, target_value, format!("{}[{}]", path, i), updates, skipped),
+                        None => *skipped += 1,
+                    }
+                }
+            }
+            (ParamValue::List(s), ParamValue::List(t)) => {
+                *skipped += s.values.len().abs_diff(t.values.len());
+                for (i, (source_value, target_value)) in s.values.iter().zip(&t.values).enumerate() {
+                    Self::collect_value_transplants(source_value, target_value, format!("{}[{}]", path, i), updates, skipped);
+                }
+            }
+            (ParamValue::Struct(_) | ParamValue::List(_), _) | (_, ParamValue::Struct(_) | ParamValue::List(_)) => *skipped += 1,
+            _ if std::mem::discriminant(source) != std::mem::discriminant(target) => *skipped += 1,
+            _ if source != target => updates.push((path, target.clone(), source.clone())),
+            _ => {}
+        }
+    }
     
     /// Add a node to the underlying ParamValue structure
     fn add_to_param_value(
@@ -1273,93 +3877,28 @@ impl PrcEditorApp {
         false
     }
     
-    /// Add a node to the display tree
-    #[allow(dead_code)]
-    fn add_to_display_tree(
-        node: &mut Option<ParamNode>,
-        indices: &[usize],
-        node_to_add: ParamNode,
-        depth: usize
-    ) -> bool {
-        if let Some(current_node) = node {
-            if depth == indices.len() {
-                // We're at the target, add the node
-                current_node.children.push(node_to_add);
-                return true;
-            }
-            
-            // Continue recursing
-            let current_index = indices[depth];
-            if current_index < current_node.children.len() {
-                let mut child_option = Some(std::mem::replace(&mut current_node.children[current_index], ParamNode::new("temp".to_string(), 0, ParamValue::Bool(false))));
-                let result = Self::add_to_display_tree(&mut child_option, indices, node_to_add, depth + 1);
-                if let Some(updated_child) = child_option {
-                    current_node.children[current_index] = updated_child;
-                }
-                return result;
-            }
-        }
-        false
+    fn find_node_by_path(&self, path: &str) -> Option<NodeRef<'_>> {
+        self.param_file.node_at(path)
     }
-    
-    fn find_node_by_path(&self, path: &str) -> Option<&ParamNode> {
-        let root = self.param_file.get_root()?;
-        
-        if path == "root" {
-            return Some(root);
-        }
-        
-        // Parse path like "root[0][1][2]" to navigate to the correct node
-        let mut current_node = root;
-        let path_parts: Vec<&str> = path.split("[").skip(1).collect(); // Skip "root" part
-        
-        for part in path_parts {
-            let index_str = part.trim_end_matches(']');
-            if let Ok(index) = index_str.parse::<usize>() {
-                if index < current_node.children.len() {
-                    current_node = &current_node.children[index];
-                } else {
-                    return None;
-                }
-            } else {
-                return None;
-            }
+
+    /// Whether another file can replace the current one. A pending cut or missing labels
+    /// are asked about first, and false is returned.
+    fn ready_to_open(&mut self) -> bool {
+        if !self.confirm_discard_cut() {
+            return false;
         }
         
-        Some(current_node)
-    }
-
-    /// Refresh the tree display from the underlying data structure
-    /// This should be called after making changes to ensure UI consistency
-    #[allow(dead_code)]
-    fn refresh_tree(&mut self) {
-        if let Some(_) = self.param_file.get_root() {
-            // Preserve expanded state and selection
-            let expanded_nodes = self.expanded_nodes.clone();
-            let selected_path = self.selected_node.clone();
-            
-            // Rebuild the tree from the underlying data structure
-            self.param_file.rebuild_tree();
-            
-            // Restore expanded state
-            self.expanded_nodes = expanded_nodes;
-            
-            // Try to restore selection if it still exists
-            if let Some(path) = selected_path {
-                if self.find_node_by_path(&path).is_some() {
-                    self.selected_node = Some(path);
-                } else {
-                    self.selected_node = None;
-                }
-            }
+        // Check if ParamLabels.csv is loaded first, unless the user opted out
+        if self.param_labels_path.is_none() && !self.without_labels {
+            self.status_message = "Please load ParamLabels.csv first before opening parameter files".to_string();
+            self.prompt_for_labels_file();
+            return false;
         }
+        true
     }
 
     fn open_file_dialog(&mut self) {
-        // Check if ParamLabels.csv is loaded first
-        if self.param_labels_path.is_none() {
-            self.status_message = "Please load ParamLabels.csv first before opening parameter files".to_string();
-            self.prompt_for_labels_file();
+        if !self.ready_to_open() {
             return;
         }
         
@@ -1368,347 +3907,1792 @@ impl PrcEditorApp {
             .add_filter("All files", &["*"])
             .pick_file()
         {
-            self.status_message = format!("Opening file: {}", file_path.display());
-            
-            match std::fs::read(&file_path) {
-                Ok(data) => {
-                    let filename = file_path.file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("unknown");
-                    
-                    match self.param_file.open(&data, filename) {
-                        Ok(()) => {
-                            self.status_message = format!("Successfully opened: {}", filename);
-                            self.selected_node = None;
-                            self.expanded_nodes.clear();
-                            // Rebuild tree with labels if they're already loaded
-                            if !self.param_file.hash_labels.is_empty() {
-                                self.param_file.rebuild_tree_with_labels();
-                            }
-                        }
-                        Err(e) => {
-                            self.status_message = format!("Error opening file: {}", e);
-                            // Clear any partial data
-                            self.param_file.root = None;
-                        }
-                    }
-                }
-                Err(e) => {
-                    self.status_message = format!("Error reading file: {}", e);
-                }
-            }
-        }
-    }
-
-    fn save_file_dialog(&mut self) {
-        if let Some(file_path) = FileDialog::new()
-            .add_filter("Param files", &["prc", "prcx", "stdat", "stdatx", "stprm", "stprmx"])
-            .add_filter("All files", &["*"])
-            .set_file_name(&format!("{}_modified", self.param_file.get_filename().replace(".prc", "")))
-            .save_file()
-        {
-            self.status_message = format!("Saving file: {}", file_path.display());
-            
-            match self.param_file.save(file_path.to_str().unwrap_or("output.prc")) {
-                Ok(()) => {
-                    self.status_message = format!("Successfully saved: {}", file_path.display());
-                }
-                Err(e) => {
-                    self.status_message = format!("Error saving file: {}", e);
-                }
-            }
+            self.open_path(&file_path);
         }
     }
-
-    fn download_labels(&mut self) {
-        // TODO: Implement label downloading from online source
-        self.status_message = "Label downloading not yet implemented".to_string();
-    }
     
-    /// Build a flattened list of visible tree items for keyboard navigation
-    fn build_tree_items(&mut self) {
-        self.tree_items.clear();
-        if let Some(root) = self.param_file.get_root() {
-            self.collect_visible_items(&root.clone(), "root".to_string(), 0);
+    fn open_folder_dialog(&mut self) {
+        let start = self.folder_files.as_ref().map(|(dir, _)| dir.clone())
+            .or_else(|| self.opened_file.as_ref().and_then(|(path, _)| path.parent()).map(Path::to_path_buf));
+        let mut dialog = FileDialog::new();
+        if let Some(start) = start {
+            dialog = dialog.set_directory(start);
         }
-        
-        // Update selected_index to match selected_node
-        if let Some(selected_path) = &self.selected_node {
-            self.selected_index = self.tree_items.iter().position(|item| item == selected_path);
-        } else if !self.tree_items.is_empty() {
-            // If no selection, select the first item
-            self.selected_index = Some(0);
-            self.selected_node = self.tree_items.first().cloned();
+        if let Some(dir) = dialog.pick_folder() {
+            self.folder_filter.clear();
+            self.list_folder(dir);
         }
     }
     
-    /// Recursively collect visible tree items
-    fn collect_visible_items(&mut self, node: &ParamNode, path: String, _depth: usize) {
-        self.tree_items.push(path.clone());
-        
-        // Only collect children if this node is expanded
-        if node.is_expandable() && self.expanded_nodes.contains(&path) {
-            for (i, child) in node.children.iter().enumerate() {
-                let child_path = format!("{}[{}]", path, i);
-                self.collect_visible_items(child, child_path, _depth + 1);
+    /// Read the param files in `dir` into the folder panel
+    fn list_folder(&mut self, dir: PathBuf) {
+        match list_param_files(&dir) {
+            Ok(files) => {
+                self.status_message = format!("{} param files in {}", files.len(), dir.display());
+                self.folder_files = Some((dir, files));
+            }
+            Err(e) => {
+                self.status_message = format!("Error reading folder {}: {}", dir.display(), e);
             }
         }
     }
     
-    /// Navigate up in the tree
-    fn navigate_up(&mut self) {
-        if let Some(current_index) = self.selected_index {
-            if current_index > 0 {
-                self.selected_index = Some(current_index - 1);
-                if let Some(new_path) = self.tree_items.get(current_index - 1) {
-                    self.selected_node = Some(new_path.clone());
+    /// Side panel listing the opened folder's param files; clicking one opens it
+    fn show_folder_panel(&mut self, ui: &mut egui::Ui) {
+        let Some((dir, files)) = &self.folder_files else {
+            return;
+        };
+        let folder_name = log_file_name(dir);
+        let dir_text = dir.display().to_string();
+        
+        let mut refresh = false;
+        let mut close = false;
+        ui.horizontal(|ui| {
+            ui.heading(truncate_chars(&folder_name, 20)).on_hover_text(&dir_text);
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                close = ui.small_button("✖").on_hover_text("Close folder").clicked();
+                refresh = ui.small_button("⟳").on_hover_text("Re-read the folder").clicked();
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            ui.text_edit_singleline(&mut self.folder_filter);
+        });
+        ui.separator();
+        
+        let filter = self.folder_filter.trim().to_lowercase();
+        let opened = self.opened_file.as_ref().map(|(path, _)| path);
+        let mut clicked = None;
+        let mut shown = 0;
+        egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+            for (path, size) in files {
+                let name = log_file_name(path);
+                if !filter.is_empty() && !name.to_lowercase().contains(&filter) {
+                    continue;
                 }
+                shown += 1;
+                ui.horizontal(|ui| {
+                    if ui.selectable_label(opened == Some(path), &name).on_hover_text(path.display().to_string()).clicked() {
+                        clicked = Some(path.clone());
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.weak(format_file_size(*size));
+                    });
+                });
+            }
+            if files.is_empty() {
+                ui.weak("No param files in this folder");
+            } else if shown == 0 {
+                ui.weak(format!("No files match '{}'", self.folder_filter.trim()));
+            }
+        });
+        
+        if close {
+            self.folder_files = None;
+        } else if refresh {
+            let dir = dir.clone();
+            self.list_folder(dir);
+        } else if let Some(path) = clicked {
+            if opened != Some(&path) && self.ready_to_open() {
+                self.open_path(&path);
             }
-        } else if !self.tree_items.is_empty() {
-            self.selected_index = Some(0);
-            self.selected_node = self.tree_items.first().cloned();
         }
     }
     
-    /// Navigate down in the tree
-    fn navigate_down(&mut self) {
-        if let Some(current_index) = self.selected_index {
-            if current_index + 1 < self.tree_items.len() {
-                self.selected_index = Some(current_index + 1);
-                if let Some(new_path) = self.tree_items.get(current_index + 1) {
-                    self.selected_node = Some(new_path.clone());
+    /// Read and open the param file at `file_path`, resetting per-file view state
+    fn open_path(&mut self, file_path: &Path) {
+        self.status_message = format!("Opening file: {}", file_path.display());
+        
+        // Taken before reading so a write that lands mid-read still shows up as a change
+        let stamp = FileStamp::read(file_path);
+        match std::fs::read(file_path) {
+            Ok(data) => {
+                let filename = file_path.file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or("unknown");
+                
+                match self.param_file.open(&data, filename) {
+                    Ok(()) => {
+                        self.log_event(&format!("Opened {} ({} bytes)", filename, data.len()));
+                        self.start_round_trip_check(data);
+                        self.opened_file = Some((file_path.to_path_buf(), stamp));
+                        self.pinned_details = None;
+                        self.recent_edits.clear();
+                        self.add_field = None;
+                        self.status_message = if self.param_labels_path.is_none() {
+                            format!("Opened {} without labels - hashes are shown as 0x... (load ParamLabels.csv to name them)", filename)
+                        } else {
+                            format!("Successfully opened: {}", filename)
+                        };
+                        self.selected_node = None;
+                        self.expanded_nodes.clear();
+                        self.nav_back.clear();
+                        self.nav_forward.clear();
+                        self.list_display_keys.clear();
+                        self.multi_selection.clear();
+                        self.full_precision_paths.clear();
+                        self.hash_locations = None;
+                        // Edits and a cut from the previous file don't apply to this one
+                        self.undo_stack.clear();
+                        self.redo_stack.clear();
+                        self.cut_origin = None;
+                        self.cut_mode = false;
+                        self.load_notes();
+                    }
+                    Err(e) => {
+                        // A failed open leaves the current file as it was
+                        self.log_event(&format!("Failed to open {}: {}", filename, e));
+                        self.status_message = format!("Error opening file: {}", e);
+                    }
                 }
             }
-        } else if !self.tree_items.is_empty() {
-            self.selected_index = Some(0);
-            self.selected_node = self.tree_items.first().cloned();
+            Err(e) => {
+                self.status_message = format!("Error reading file: {}", e);
+            }
         }
     }
+
+    /// Check on another thread whether the bytes just opened survive a save and re-open.
+    /// A check still running for an earlier file is abandoned.
+    fn start_round_trip_check(&mut self, data: Vec<u8>) {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = ParamFile::round_trips(&data).map_err(|e| e.to_string());
+            let _ = sender.send(result);
+        });
+        self.round_trip_pending = Some(receiver);
+        self.round_trip_result = None;
+    }
     
-    /// Navigate left (collapse current node or go to parent)
-    fn navigate_left(&mut self) {
-        if let Some(selected_path) = &self.selected_node.clone() {
-            // If current node is expanded, collapse it
-            if self.expanded_nodes.contains(selected_path) {
-                self.expanded_nodes.remove(selected_path);
-                // Rebuild tree items since visibility changed
-                self.build_tree_items();
-            } else {
-                // Go to parent node
-                if let Some(parent_path) = self.get_parent_path(selected_path) {
-                    self.selected_node = Some(parent_path.clone());
-                    self.selected_index = self.tree_items.iter().position(|item| item == &parent_path);
-                }
+    /// Pick up a finished round-trip check, asking for another frame while one is running
+    fn poll_round_trip_check(&mut self, ctx: &egui::Context) {
+        let Some(receiver) = &self.round_trip_pending else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(result) => {
+                self.round_trip_result = Some(result);
+                self.round_trip_pending = None;
+            }
+            Err(mpsc::TryRecvError::Empty) => ctx.request_repaint_after(std::time::Duration::from_millis(100)),
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.round_trip_result = Some(Err("the check stopped unexpectedly".to_string()));
+                self.round_trip_pending = None;
             }
         }
     }
     
-    /// Navigate right (expand current node or go to first child)
-    fn navigate_right(&mut self) {
-        if let Some(selected_path) = &self.selected_node.clone() {
-            if let Some(node) = self.find_node_by_path(selected_path) {
-                if node.is_expandable() {
-                    if !self.expanded_nodes.contains(selected_path) {
-                        // Expand the node
-                        self.expanded_nodes.insert(selected_path.clone());
-                        // Rebuild tree items since visibility changed
-                        self.build_tree_items();
-                    } else if !node.children.is_empty() {
-                        // Go to first child
-                        let first_child_path = format!("{}[0]", selected_path);
-                        self.selected_node = Some(first_child_path.clone());
-                        self.selected_index = self.tree_items.iter().position(|item| item == &first_child_path);
-                    }
+    /// Status bar badge for the round-trip check of the opened file
+    fn show_round_trip_badge(&self, ui: &mut egui::Ui) {
+        let response = match (&self.round_trip_pending, &self.round_trip_result) {
+            (Some(_), _) => ui.weak("Checking round-trip..."),
+            (None, Some(Ok(true))) => ui.colored_label(egui::Color32::from_rgb(0, 180, 0), "✔ Round-trips"),
+            (None, Some(Ok(false))) => ui.colored_label(egui::Color32::RED, "⚠ Doesn't round-trip"),
+            (None, Some(Err(_))) => ui.colored_label(egui::Color32::RED, "⚠ Round-trip check failed"),
+            (None, None) => return,
+        };
+        let detail = match &self.round_trip_result {
+            Some(Ok(false)) => "\nSaving and re-opening this file as it was opened changes its values - edits may not save as shown. Please report the file.",
+            Some(Err(e)) => &format!("\nThe saved copy could not be read back: {}", e),
+            _ => "",
+        };
+        response.on_hover_text(format!("Whether the file as opened (before any edits) saves and re-opens to the same values{}", detail));
+    }
+    
+    /// Skip the ParamLabels.csv requirement and go straight to the open dialog
+    fn open_without_labels(&mut self) {
+        self.without_labels = true;
+        self.open_file_dialog();
+    }
+
+    /// Write the file's struct/list hierarchy as a GraphViz DOT graph
+    fn export_dot_dialog(&mut self) {
+        let Some(file_path) = FileDialog::new()
+            .add_filter("GraphViz DOT", &["dot", "gv"])
+            .set_file_name(format!("{}.dot", file_stem_or(self.param_file.get_filename(), "params")))
+            .save_file()
+        else {
+            return;
+        };
+        
+        match self.param_file.export_dot().and_then(|dot| Ok(std::fs::write(&file_path, dot)?)) {
+            Ok(()) => {
+                self.status_message = format!("Exported structure to {}", file_path.display());
+            }
+            Err(e) => {
+                self.status_message = format!("Error exporting structure: {}", e);
+            }
+        }
+    }
+    
+    /// Multi-selected nodes to export, leaving out any inside another selected node
+    fn selection_export_roots(&self) -> Vec<(String, ParamNode)> {
+        self.multi_selection.iter()
+            .filter(|path| !self.multi_selection.iter().any(|other| other != *path && is_same_or_descendant(path, other)))
+            .filter_map(|path| self.find_node_by_path(path).map(|node| (path.clone(), node.to_owned_node())))
+            .collect()
+    }
+    
+    /// Root wrapping the selected nodes: a list when they all have the same type, otherwise a
+    /// struct keyed by their own keys (list items and repeated keys get `selection_N` instead)
+    fn selection_fragment(&mut self, nodes: &[(String, ParamNode)]) -> (ParamValue, &'static str) {
+        let same_type = nodes.windows(2).all(|pair| pair[0].1.value.type_name() == pair[1].1.value.type_name());
+        if same_type {
+            let values = nodes.iter().map(|(_, node)| node.value.clone()).collect();
+            return (ParamValue::List(ParamList { values }), "list");
+        }
+        
+        let mut fields = indexmap::IndexMap::new();
+        for (i, (path, node)) in nodes.iter().enumerate() {
+            let hash = if self.is_struct_field(path) && !fields.contains_key(&node.hash) {
+                node.hash
+            } else {
+                self.param_file.hash_labels.add_label_and_save(&format!("selection_{}", i), self.param_labels_path.as_deref())
+            };
+            fields.insert(hash, node.value.clone());
+        }
+        (ParamValue::Struct(ParamStruct { type_hash: 0, fields }), "struct")
+    }
+    
+    /// Save the multi-selected subtrees together as one param file
+    fn export_selection_dialog(&mut self) {
+        let nodes = self.selection_export_roots();
+        if nodes.is_empty() {
+            self.status_message = "Nothing selected to export".to_string();
+            return;
+        }
+        
+        let Some(file_path) = FileDialog::new()
+            .add_filter("Param files", &["prc", "prcx", "stdat", "stdatx", "stprm", "stprmx"])
+            .set_title("Export selection as one file")
+            .set_file_name(default_save_name(self.param_file.get_filename(), "selection"))
+            .save_file()
+        else {
+            return;
+        };
+        
+        let (fragment, wrapper) = self.selection_fragment(&nodes);
+        let result = ParamFile::bytes_of(fragment)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(std::fs::write(&file_path, bytes)?));
+        let skipped = self.multi_selection.len() - nodes.len();
+        self.status_message = match result {
+            Ok(()) => {
+                let mut message = format!("Exported {} nodes wrapped in a {} to {}", nodes.len(), wrapper, file_path.display());
+                if skipped > 0 {
+                    message.push_str(&format!(" | {} nested inside other selections left out", skipped));
                 }
+                message
             }
+            Err(e) => format!("Error exporting selection: {}", e),
+        };
+    }
+    
+    /// Save each multi-selected subtree as its own param file in a chosen folder, named after its key
+    fn export_selection_separately_dialog(&mut self) {
+        let nodes = self.selection_export_roots();
+        if nodes.is_empty() {
+            self.status_message = "Nothing selected to export".to_string();
+            return;
+        }
+        let Some(dir) = FileDialog::new().set_title("Folder to export the selected nodes to").pick_folder() else {
+            return;
+        };
+        
+        let extension = param_extension(self.param_file.get_filename());
+        let mut used_names = HashSet::new();
+        let mut written = Vec::new();
+        let mut failures = Vec::new();
+        for (path, node) in nodes {
+            let key = match self.label_path_segments(&path).pop() {
+                Some((_, label)) if path != "root" => label,
+                _ => "root".to_string(),
+            };
+            let stem: String = key.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' }).collect();
+            let mut name = format!("{}.{}", stem, extension);
+            let mut n = 1;
+            while !used_names.insert(name.clone()) {
+                n += 1;
+                name = format!("{}_{}.{}", stem, n, extension);
+            }
+            
+            let result = ParamFile::bytes_of(node.value)
+                .map_err(anyhow::Error::from)
+                .and_then(|bytes| Ok(std::fs::write(dir.join(&name), bytes)?));
+            match result {
+                Ok(()) => written.push(name),
+                Err(e) => failures.push(format!("{}: {}", path, e)),
+            }
+        }
+        
+        let mut message = format!("Exported {} files to {}: {}", written.len(), dir.display(), written.join(", "));
+        if !failures.is_empty() {
+            message.push_str(&format!(" | {} failed: {}", failures.len(), failures.join("; ")));
         }
+        self.status_message = message;
     }
     
-    /// Get the parent path of a given path
-    fn get_parent_path(&self, path: &str) -> Option<String> {
-        if path == "root" {
-            return None;
+    /// Save the differences from the reference file to the open file as a JSON patch
+    fn export_patch_dialog(&mut self) {
+        let Some(reference) = &self.reference_file else {
+            return;
+        };
+        let patch = self.param_file.diff_patch(reference);
+        if patch.is_empty() {
+            self.status_message = format!("No differences from {}", reference.get_filename());
+            return;
         }
         
-        // Find the last '[' and remove everything from there
-        if let Some(last_bracket) = path.rfind('[') {
-            Some(path[..last_bracket].to_string())
+        let Some(file_path) = FileDialog::new()
+            .add_filter("Param patch", &["json"])
+            .set_file_name(format!("{}.patch.json", file_stem_or(self.param_file.get_filename(), "params")))
+            .save_file()
+        else {
+            return;
+        };
+        
+        let result = patch.to_json()
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(std::fs::write(&file_path, json)?));
+        self.status_message = match result {
+            Ok(()) => format!(
+                "Exported patch with {} changed and {} removed entries to {}",
+                patch.set.len(), patch.removed.len(), file_path.display()
+            ),
+            Err(e) => format!("Error exporting patch: {}", e),
+        };
+    }
+    
+    /// Save a partial param file holding only what changed since the file was opened
+    fn export_changes_dialog(&mut self) {
+        let Some((changed, removed)) = self.param_file.changes_since_open() else {
+            self.status_message = "No opened file to compare against".to_string();
+            return;
+        };
+        let removed_note = if removed > 0 {
+            format!(" | {} removed fields can't be expressed and are left out", removed)
         } else {
-            None
+            String::new()
+        };
+        let Some(changed) = changed else {
+            self.status_message = format!("Nothing changed since opening{}", removed_note);
+            return;
+        };
+        
+        let Some(file_path) = FileDialog::new()
+            .add_filter("Param files", &["prc", "prcx", "stdat", "stdatx", "stprm", "stprmx"])
+            .set_title("Export changes only (partial file)")
+            .set_file_name(default_save_name(self.param_file.get_filename(), "changes"))
+            .save_file()
+        else {
+            return;
+        };
+        
+        let result = ParamFile::bytes_of(changed)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(std::fs::write(&file_path, bytes)?));
+        self.status_message = match result {
+            Ok(()) => format!(
+                "Exported a partial file with only the changed params to {} - it is not a complete replacement{}",
+                file_path.display(), removed_note
+            ),
+            Err(e) => format!("Error exporting changes: {}", e),
+        };
+    }
+    
+    /// Append a line to the session log, stamped with the time since the app started
+    fn log_event(&mut self, text: &str) {
+        let elapsed = self.session_start.elapsed().as_secs();
+        self.session_log.push(format!("[{:02}:{:02}:{:02}] {}", elapsed / 3600, elapsed / 60 % 60, elapsed % 60, text));
+        if self.session_log.len() > MAX_SESSION_LOG {
+            let excess = self.session_log.len() - MAX_SESSION_LOG;
+            self.session_log.drain(..excess);
         }
     }
     
-    /// Push an action to the undo stack and clear redo stack
-    fn push_undo_action(&mut self, action: UndoAction) {
-        self.undo_stack.push(action);
-        self.redo_stack.clear(); // Clear redo stack when new action is performed
+    /// Write the session log to a text file the user picks. File names are logged without
+    /// their folders so the log carries no user or machine names.
+    fn export_session_log_dialog(&mut self) {
+        let Some(file_path) = FileDialog::new()
+            .add_filter("Text", &["txt"])
+            .set_title("Export edit log")
+            .set_file_name("prc-editor-edit-log.txt")
+            .save_file()
+        else {
+            return;
+        };
         
-        // Limit undo stack size to prevent memory issues
-        if self.undo_stack.len() > 100 {
-            self.undo_stack.remove(0);
+        let mut text = format!(
+            "PRC Editor {} edit log\n\
+             Written locally when requested from File > Export Edit Log; nothing is uploaded.\n\
+             Contains file names, node paths and values only. Times are since the editor started.\n\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        for entry in &self.session_log {
+            text.push_str(entry);
+            text.push('\n');
         }
+        
+        self.status_message = match std::fs::write(&file_path, text) {
+            Ok(()) => format!("Exported {} log entries to {}", self.session_log.len(), file_path.display()),
+            Err(e) => format!("Error exporting edit log: {}", e),
+        };
     }
     
-    /// Perform undo operation
-    fn undo(&mut self) -> bool {
-        if let Some(action) = self.undo_stack.pop() {
-            match action.clone() {
-                UndoAction::DeleteNode { path, node, parent_path, index } => {
-                    // Restore the deleted node
-                    if self.restore_node_at_index(&parent_path, node, index) {
-                        self.redo_stack.push(UndoAction::AddNode { path });
-                        self.status_message = "Undid delete operation".to_string();
-                        self.build_tree_items();
-                        return true;
-                    }
-                }
-                UndoAction::AddNode { path } => {
-                    // Remove the added node
-                    if let Some(node) = self.find_node_by_path(&path).cloned() {
-                        if let Some(parent_path) = self.get_parent_path(&path) {
-                            if let Some(index) = self.get_node_index_in_parent(&path) {
-                                if self.delete_node(&path) {
-                                    self.redo_stack.push(UndoAction::DeleteNode { 
-                                        path: path.clone(), 
-                                        node, 
-                                        parent_path, 
-                                        index 
-                                    });
-                                    self.status_message = "Undid add operation".to_string();
-                                    self.build_tree_items();
-                                    return true;
-                                }
-                            }
-                        }
-                    }
-                }
-                UndoAction::UpdateValue { path, old_value, new_value } => {
-                    // Restore the old value
-                    if self.param_file.update_node_value(&path, old_value.clone()) {
-                        self.redo_stack.push(UndoAction::UpdateValue { 
-                            path, 
-                            old_value: new_value, 
-                            new_value: old_value 
-                        });
-                        self.status_message = "Undid value change".to_string();
-                        self.param_file.rebuild_tree_with_labels();
-                        return true;
-                    }
-                }
-                UndoAction::UpdateKey { path, old_name, old_hash, new_name, new_hash } => {
-                    // Restore the old key
-                    if self.param_file.update_node_key(&path, old_name.clone(), old_hash) {
-                        self.redo_stack.push(UndoAction::UpdateKey { 
-                            path, 
-                            old_name: new_name, 
-                            old_hash: new_hash, 
-                            new_name: old_name, 
-                            new_hash: old_hash 
-                        });
-                        self.status_message = "Undid key change".to_string();
-                        self.param_file.rebuild_tree_with_labels();
-                        return true;
-                    }
-                }
-            }
+    fn apply_patch_dialog(&mut self) {
+        let Some(file_path) = FileDialog::new()
+            .add_filter("Param patch", &["json"])
+            .add_filter("All files", &["*"])
+            .set_title("Select patch to apply")
+            .pick_file()
+        else {
+            return;
+        };
+        
+        let result = std::fs::read_to_string(&file_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Ok(ParamPatch::from_json(&text)?));
+        match result {
+            Ok(patch) => self.apply_patch(&patch),
+            Err(e) => self.status_message = format!("Error reading patch: {}", e),
         }
-        false
     }
     
-    /// Perform redo operation
-    fn redo(&mut self) -> bool {
-        if let Some(action) = self.redo_stack.pop() {
-            match action.clone() {
-                UndoAction::DeleteNode { path, node, parent_path, index } => {
-                    // Re-delete the node
+    /// Apply removals then sets as one undo step. Entries whose label-path can't be found
+    /// are collected into `patch_conflicts` instead of being dropped quietly.
+    fn apply_patch(&mut self, patch: &ParamPatch) {
+        let mut actions = Vec::new();
+        let mut conflicts = Vec::new();
+        
+        for label_path in &patch.removed {
+            match self.param_file.locate_label_path(label_path) {
+                Some(PatchTarget::Existing(path)) if path != "root" => {
+                    // delete_node records its own undo step; take it back to batch it
                     if self.delete_node(&path) {
-                        self.undo_stack.push(UndoAction::DeleteNode { path, node, parent_path, index });
-                        self.status_message = "Redid delete operation".to_string();
-                        self.build_tree_items();
-                        return true;
+                        actions.extend(self.undo_stack.pop());
+                    } else {
+                        conflicts.push(format!("remove {}: could not delete", label_path));
                     }
                 }
-                UndoAction::AddNode { path: _ } => {
-                    // This would require re-adding the node, which is complex
-                    // For now, just indicate it's not supported
-                    self.status_message = "Redo add operation not yet supported".to_string();
-                    return false;
-                }
-                UndoAction::UpdateValue { path, old_value, new_value } => {
-                    // Re-apply the new value
-                    if self.param_file.update_node_value(&path, new_value.clone()) {
-                        self.undo_stack.push(UndoAction::UpdateValue { 
-                            path, 
-                            old_value: old_value, 
-                            new_value: new_value 
-                        });
-                        self.status_message = "Redid value change".to_string();
-                        self.param_file.rebuild_tree_with_labels();
-                        return true;
+                _ => conflicts.push(format!("remove {}: not found", label_path)),
+            }
+        }
+        
+        for (label_path, new_value) in &patch.set {
+            match self.param_file.locate_label_path(label_path) {
+                Some(PatchTarget::Existing(path)) => {
+                    let Some(old_value) = self.param_file.get_node_value(&path) else {
+                        continue;
+                    };
+                    if old_value != *new_value && self.param_file.update_node_value(&path, new_value.clone()) {
+                        actions.push(UndoAction::UpdateValue { path, old_value, new_value: new_value.clone() });
                     }
                 }
-                UndoAction::UpdateKey { path, old_name, old_hash, new_name, new_hash } => {
-                    // Re-apply the new key
-                    if self.param_file.update_node_key(&path, new_name.clone(), new_hash) {
-                        self.undo_stack.push(UndoAction::UpdateKey { 
-                            path, 
-                            old_name, 
-                            old_hash, 
-                            new_name, 
-                            new_hash 
-                        });
-                        self.status_message = "Redid key change".to_string();
-                        self.param_file.rebuild_tree_with_labels();
-                        return true;
+                Some(PatchTarget::Append { parent, hash }) => {
+                    let node = ParamNode::from_value(hash, new_value.clone(), &self.param_file.hash_labels);
+                    if self.add_node_with_undo(&parent, node) {
+                        actions.extend(self.undo_stack.pop());
+                    } else {
+                        conflicts.push(format!("set {}: could not add", label_path));
                     }
                 }
+                None => conflicts.push(format!("set {}: parent not found", label_path)),
             }
         }
-        false
+        
+        let applied = actions.len();
+        if applied > 0 {
+            self.push_undo_action(UndoAction::Batch { actions });
+            self.build_tree_items();
+        }
+        
+        self.status_message = if conflicts.is_empty() {
+            format!("Applied patch: {} changes", applied)
+        } else {
+            format!("Applied patch: {} changes, {} conflicts", applied, conflicts.len())
+        };
+        self.patch_conflicts = conflicts;
     }
     
-    /// Get the index of a node within its parent
-    fn get_node_index_in_parent(&self, path: &str) -> Option<usize> {
-        if let Some(parent_path) = self.get_parent_path(path) {
-            if let Some(_parent_node) = self.find_node_by_path(&parent_path) {
-                // Extract the index from the path
-                if let Some(last_bracket) = path.rfind('[') {
-                    if let Some(close_bracket) = path.rfind(']') {
-                        let index_str = &path[last_bracket + 1..close_bracket];
-                        return index_str.parse::<usize>().ok();
+    /// Patch entries that didn't apply, from the last Apply Patch
+    fn show_patch_conflicts_window(&mut self, ctx: &egui::Context) {
+        if self.patch_conflicts.is_empty() {
+            return;
+        }
+        
+        let mut open = true;
+        egui::Window::new("Patch Conflicts")
+            .default_size([450.0, 300.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(format!("{} patch entries didn't match this file and were not applied:", self.patch_conflicts.len()));
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for conflict in &self.patch_conflicts {
+                        ui.monospace(conflict);
                     }
+                });
+            });
+        
+        if !open {
+            self.patch_conflicts.clear();
+        }
+    }
+    
+    /// Load a second param file that is only used for value lookups
+    fn open_reference_file_dialog(&mut self) {
+        let Some(file_path) = FileDialog::new()
+            .add_filter("Param files", &["prc", "prcx", "stdat", "stdatx", "stprm", "stprmx"])
+            .add_filter("All files", &["*"])
+            .set_title("Select reference file")
+            .pick_file()
+        else {
+            return;
+        };
+        
+        let filename = file_path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown");
+        
+        let result = std::fs::read(&file_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| {
+                let mut reference = ParamFile::new();
+                reference.open(&data, filename)?;
+                Ok(reference)
+            });
+        
+        match result {
+            Ok(reference) => {
+                self.reference_file = Some(reference);
+                self.status_message = format!("Loaded reference file: {}", filename);
+            }
+            Err(e) => {
+                self.status_message = format!("Error opening reference file: {}", e);
+            }
+        }
+    }
+    
+    /// Find the node in the reference file at the same label-path as `path` in the open file.
+    /// Struct fields are matched by key hash and list items by index.
+    fn find_reference_node(&self, path: &str) -> Option<NodeRef<'_>> {
+        let reference_file = self.reference_file.as_ref()?;
+        let mut current = self.param_file.get_root()?.view();
+        let mut reference = reference_file.get_root()?.view();
+        
+        for part in path.split('[').skip(1) {
+            let index = part.trim_end_matches(']').parse::<usize>().ok()?;
+            let child = current.child(index, &self.param_file.hash_labels)?;
+            
+            reference = match (current.value, reference.value) {
+                (ParamValue::Struct(_), ParamValue::Struct(s)) => {
+                    let reference_index = s.fields.get_index_of(&child.hash)?;
+                    reference.child(reference_index, &reference_file.hash_labels)?
                 }
+                (ParamValue::List(_), ParamValue::List(_)) => reference.child(index, &reference_file.hash_labels)?,
+                _ => return None,
+            };
+            current = child;
+        }
+        
+        Some(reference)
+    }
+    
+    fn save_file_dialog(&mut self) {
+        if let Some(file_path) = FileDialog::new()
+            .add_filter("Param files", &["prc", "prcx", "stdat", "stdatx", "stprm", "stprmx"])
+            .add_filter("All files", &["*"])
+            .set_file_name(default_save_name(self.param_file.get_filename(), "modified"))
+            .save_file()
+        {
+            if self.changed_since_opened(&file_path) {
+                // Ask before clobbering another program's changes
+                self.external_change_save = Some(file_path);
+            } else {
+                self.save_to_path(&file_path);
             }
         }
-        None
     }
     
-    /// Restore a node at a specific index in its parent
-    fn restore_node_at_index(&mut self, parent_path: &str, node: ParamNode, index: usize) -> bool {
-        let parent_indices = match self.param_file.parse_node_path(parent_path) {
-            Some(indices) => indices,
-            None => return false,
+    /// Whether `path` is the file that was opened and it has changed on disk since it was
+    /// opened (or last saved from here)
+    fn changed_since_opened(&self, path: &Path) -> bool {
+        match &self.opened_file {
+            Some((opened_path, stamp)) if same_file_path(opened_path, path) => FileStamp::read(path) != *stamp,
+            _ => false,
+        }
+    }
+    
+    fn save_to_path(&mut self, file_path: &Path) {
+        self.status_message = format!("Saving file: {}", file_path.display());
+        let ordering = self.save_ordering();
+        
+        let output_path = file_path.to_str().unwrap_or("output.prc");
+        let result = if self.verify_after_save {
+            self.param_file.save_verified(output_path)
+        } else {
+            self.param_file.save(output_path)
         };
         
-        // Add to the underlying data structure at the specific index
-        if let Some(root) = &mut self.param_file.root {
-            if Self::restore_to_param_value(&mut root.value, &parent_indices, node.clone(), index, 0) {
-                // Rebuild the display tree to show the restored node
-                self.param_file.rebuild_tree_with_labels();
-                return true;
+        match result {
+            Ok(()) => {
+                let verified = if self.verify_after_save { "verified, " } else { "" };
+                let ordering = ordering.map_or("new file", Self::save_ordering_text);
+                self.status_message = format!("Successfully saved: {} ({}{})", file_path.display(), verified, ordering);
+                self.log_event(&format!("Saved {} ({}{})", log_file_name(file_path), verified, ordering));
+                
+                // What's on disk is now our own write
+                if let Some((opened_path, stamp)) = &mut self.opened_file {
+                    if same_file_path(opened_path, file_path) {
+                        *stamp = FileStamp::read(file_path);
+                    }
+                }
+                
+                if self.write_xml_sidecar {
+                    let sidecar_note = self.write_xml_sidecar_for(file_path);
+                    self.status_message.push_str(&sidecar_note);
+                }
+            }
+            Err(e) => {
+                self.log_event(&format!("Failed to save {}: {}", log_file_name(file_path), e));
+                self.status_message = format!("Error saving file: {}", e);
             }
         }
-        
-        false
     }
     
-    /// Restore a node to the underlying ParamValue structure at a specific index
-    fn restore_to_param_value(
-        value: &mut ParamValue,
-        indices: &[usize],
-        node_to_restore: ParamNode,
-        target_index: usize,
-        depth: usize
+    /// Ask whether to overwrite a file another program changed since it was opened
+    fn show_external_change_window(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.external_change_save.clone() else {
+            return;
+        };
+        
+        let mut open = true;
+        let mut overwrite = false;
+        let mut reload = false;
+        let mut cancel = false;
+        egui::Window::new("File Changed on Disk")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("{} changed on disk since you opened it.", path.display()));
+                ui.label("Overwrite it with your version, or reload it and lose your edits?");
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    overwrite = ui.button("Overwrite").clicked();
+                    reload = ui.button("Reload from Disk").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if overwrite {
+            self.save_to_path(&path);
+        } else if reload {
+            // Same checks as File > Open; a pending cut is asked about before anything is lost
+            if self.ready_to_open() {
+                self.open_path(&path);
+            }
+        } else if cancel || !open {
+            self.status_message = "Save cancelled".to_string();
+        }
+        if overwrite || reload || cancel || !open {
+            self.external_change_save = None;
+        }
+    }
+
+    /// Write `<name>.prcx` next to a saved binary. Failures are reported, not propagated,
+    /// so the binary save still counts as successful.
+    fn write_xml_sidecar_for(&self, saved_path: &std::path::Path) -> String {
+        let is_xml = saved_path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("prcx"));
+        if is_xml {
+            return String::new();
+        }
+        
+        let sidecar_path = saved_path.with_extension("prcx");
+        let result = self.param_file.export_xml()
+            .and_then(|xml| std::fs::write(&sidecar_path, xml).map_err(Into::into));
+        match result {
+            Ok(()) => format!(" + XML sidecar: {}", sidecar_path.display()),
+            Err(e) => format!(" (XML sidecar failed: {})", e),
+        }
+    }
+
+    fn download_labels(&mut self) {
+        // TODO: Implement label downloading from online source
+        self.status_message = "Label downloading not yet implemented".to_string();
+    }
+
+    /// Import labels from another CSV, keeping only hashes used by the current file
+    fn import_used_labels_dialog(&mut self) {
+        if let Some(file_path) = FileDialog::new()
+            .add_filter("CSV files", &["csv"])
+            .add_filter("All files", &["*"])
+            .set_title("Select CSV to import labels from")
+            .pick_file()
+        {
+            let csv_content = match std::fs::read_to_string(&file_path) {
+                Ok(content) => content,
+                Err(e) => {
+                    self.status_message = format!("Error reading selected file: {}", e);
+                    return;
+                }
+            };
+
+            let used_hashes = self.param_file.collect_used_hashes();
+            let wanted: HashSet<u64> = used_hashes.iter()
+                .copied()
+                .filter(|hash| self.param_file.hash_labels.get_label(*hash).is_none())
+                .collect();
+
+            match self.param_file.hash_labels.import_from_csv_filtered(&csv_content, &wanted) {
+                Ok(imported) => {
+                    let labeled = used_hashes.iter()
+                        .filter(|hash| self.param_file.hash_labels.get_label(**hash).is_some())
+                        .count();
+
+                    let save_note = match &self.param_labels_path {
+                        Some(path) if imported > 0 => match self.param_file.hash_labels.save_to_csv(path) {
+                            Ok(()) => format!(" and saved to {}", path),
+                            Err(e) => format!(" but failed to save: {}", e),
+                        },
+                        _ => String::new(),
+                    };
+
+                    self.status_message = format!(
+                        "Imported {} labels{} - {} of {} hashes in this file are now labeled",
+                        imported, save_note, labeled, used_hashes.len()
+                    );
+
+                    if imported > 0 {
+                        self.param_file.refresh_names_only();
+                    }
+                }
+                Err(e) => {
+                    self.status_message = format!("Error importing labels: {}", e);
+                }
+            }
+        }
+    }
+    
+    /// Build a flattened list of visible tree items for keyboard navigation
+    fn build_tree_items(&mut self) {
+        self.drop_stale_paths();
+        self.refresh_value_filter();
+        
+        let mut items = Vec::new();
+        if let Some(root) = self.param_file.get_root() {
+            self.collect_visible_items(&root.view(), "root".to_string(), &mut items);
+        }
+        self.tree_items = items;
+        
+        // Update selected_index to match selected_node
+        if let Some(selected_path) = &self.selected_node {
+            self.selected_index = self.tree_items.iter().position(|item| item == selected_path);
+        } else if !self.tree_items.is_empty() {
+            // If no selection, select the first item
+            self.selected_index = Some(0);
+            self.selected_node = self.tree_items.first().cloned();
+        }
+    }
+    
+    /// First place the state derived from the value tree has drifted from it: the tree rows
+    /// against the rows the tree and expansion give now, then the cached hash index. Drift
+    /// means an edit reached the view but not the values that get saved, or the reverse.
+    fn tree_consistency_error(&self) -> Option<String> {
+        let mut expected = Vec::new();
+        if let Some(root) = self.param_file.get_root() {
+            self.collect_visible_items(&root.view(), "root".to_string(), &mut expected);
+        }
+        
+        for (row, (shown, path)) in self.tree_items.iter().zip(&expected).enumerate() {
+            if shown != path {
+                return Some(format!("row {}: tree shows {}, values give {}", row, shown, path));
+            }
+        }
+        if self.tree_items.len() != expected.len() {
+            let row = self.tree_items.len().min(expected.len());
+            return Some(match self.tree_items.get(row) {
+                Some(shown) => format!("row {}: tree shows {}, which no longer exists", row, shown),
+                None => format!("row {}: tree is missing {}", row, expected[row]),
+            });
+        }
+        
+        self.param_file.hash_index_divergence()
+    }
+    
+    fn check_tree_consistency(&mut self) {
+        self.status_message = match self.tree_consistency_error() {
+            Some(divergence) => format!("⚠ Tree out of sync with the values - {}", divergence),
+            None => format!("Tree consistent with the values ({} rows)", self.tree_items.len()),
+        };
+    }
+    
+    /// Whether `path` still resolves to a node. Paths are positions, so after a structural
+    /// edit a stored path can point past the end of its parent.
+    fn path_is_valid(&self, path: &str) -> bool {
+        self.find_node_by_path(path).is_some()
+    }
+    
+    /// Fix up stored paths after a structural edit: a selection that no longer resolves moves
+    /// to its nearest surviving ancestor; a stale pin, multi-selection entries and edits are dropped
+    fn drop_stale_paths(&mut self) {
+        if let Some(selected) = self.selected_node.clone().filter(|selected| !self.path_is_valid(selected)) {
+            let mut ancestor = self.get_parent_path(&selected);
+            while let Some(path) = ancestor.as_deref().filter(|path| !self.path_is_valid(path)) {
+                ancestor = self.get_parent_path(path);
+            }
+            self.naming_hash = None;
+            self.selected_node = ancestor;
+            self.selected_index = None;
+        }
+        
+        if self.pinned_details.as_deref().is_some_and(|pinned| !self.path_is_valid(pinned)) {
+            self.pinned_details = None;
+        }
+        
+        self.multi_selection.retain(|path| self.param_file.node_at(path).is_some());
+        self.recent_edits.retain(|path| self.param_file.node_at(path).is_some());
+        if self.add_field.as_ref().is_some_and(|(path, _)| !matches!(self.param_file.node_at(path).map(|n| n.value), Some(ParamValue::Struct(_)))) {
+            self.add_field = None;
+        }
+        
+        let stale_edit = self.editing_value.as_ref().is_some_and(|(edit_path, _)| !self.path_is_valid(edit_node_path(edit_path)));
+        if stale_edit {
+            self.editing_value = None;
+        }
+    }
+    
+    /// Recursively collect visible tree items
+    fn collect_visible_items(&self, node: &NodeRef<'_>, path: String, items: &mut Vec<String>) {
+        // Folded chains are a single row, keyed by their last container
+        let (_, node, path) = self.fold_single_child_chain(node.clone(), path);
+        let node = &node;
+        items.push(path.clone());
+        
+        // Only collect children if this node is expanded
+        if node.is_expandable() && self.expanded_nodes.contains(&path) {
+            for (i, child) in node.children(&self.param_file.hash_labels).iter().enumerate() {
+                let child_path = format!("{}[{}]", path, i);
+                if self.hidden_by_value_filter(&child_path) {
+                    continue;
+                }
+                self.collect_visible_items(child, child_path, items);
+            }
+        }
+    }
+    
+    /// Select a node, expanding it when auto-expand on select is enabled
+    fn select_path(&mut self, path: &str) {
+        if self.selected_node.as_deref() != Some(path) {
+            self.naming_hash = None;
+            // A new selection starts a new branch of history, like following a link
+            if let Some(previous) = self.selected_node.take() {
+                self.nav_back.push(previous);
+                if self.nav_back.len() > MAX_NAV_HISTORY {
+                    self.nav_back.remove(0);
+                }
+            }
+            self.nav_forward.clear();
+        }
+        self.selected_node = Some(path.to_string());
+        
+        if self.auto_expand_on_select {
+            let has_children = self.find_node_by_path(path).map(|n| n.has_children()).unwrap_or(false);
+            if has_children {
+                self.expanded_nodes.insert(path.to_string());
+            }
+        }
+    }
+    
+    /// Move back (or forward) through the selection history without recording the move itself.
+    /// Entries whose node no longer exists are dropped.
+    fn navigate_history(&mut self, back: bool) {
+        loop {
+            let target = if back { self.nav_back.pop() } else { self.nav_forward.pop() };
+            let Some(path) = target else {
+                self.status_message = if back { "No previous selection" } else { "No next selection" }.to_string();
+                return;
+            };
+            if self.find_node_by_path(&path).is_none() {
+                continue;
+            }
+            
+            if let Some(current) = self.selected_node.take() {
+                if back {
+                    self.nav_forward.push(current);
+                } else {
+                    self.nav_back.push(current);
+                }
+            }
+            
+            self.naming_hash = None;
+            self.expand_to_path(&path);
+            self.selected_node = Some(path.clone());
+            self.build_tree_items();
+            self.selected_index = self.tree_items.iter().position(|item| item == &path);
+            self.scroll_to_selected = true;
+            self.status_message = format!("{}: {}", if back { "Back" } else { "Forward" }, path);
+            return;
+        }
+    }
+    
+    /// Navigate up in the tree
+    fn navigate_up(&mut self) {
+        if let Some(current_index) = self.selected_index {
+            if current_index > 0 {
+                self.selected_index = Some(current_index - 1);
+                if let Some(new_path) = self.tree_items.get(current_index - 1).cloned() {
+                    self.select_path(&new_path);
+                }
+            }
+        } else if !self.tree_items.is_empty() {
+            self.selected_index = Some(0);
+            self.selected_node = self.tree_items.first().cloned();
+        }
+    }
+    
+    /// Navigate down in the tree
+    fn navigate_down(&mut self) {
+        if let Some(current_index) = self.selected_index {
+            if current_index + 1 < self.tree_items.len() {
+                self.selected_index = Some(current_index + 1);
+                if let Some(new_path) = self.tree_items.get(current_index + 1).cloned() {
+                    self.select_path(&new_path);
+                }
+            }
+        } else if !self.tree_items.is_empty() {
+            self.selected_index = Some(0);
+            self.selected_node = self.tree_items.first().cloned();
+        }
+    }
+    
+    /// Navigate left (collapse current node or go to parent)
+    fn navigate_left(&mut self) {
+        if let Some(selected_path) = &self.selected_node.clone() {
+            // If current node is expanded, collapse it (empty containers go straight to the parent)
+            let has_children = self.find_node_by_path(selected_path).map(|n| n.has_children()).unwrap_or(false);
+            if has_children && self.expanded_nodes.contains(selected_path) {
+                self.expanded_nodes.remove(selected_path);
+                // Rebuild tree items since visibility changed
+                self.build_tree_items();
+            } else {
+                // Go to parent node, skipping parents folded into the same row
+                let mut parent = self.get_parent_path(selected_path);
+                while let Some(parent_path) = parent.as_ref().filter(|p| !self.tree_items.contains(p)) {
+                    parent = self.get_parent_path(parent_path);
+                }
+                if let Some(parent_path) = parent {
+                    self.select_path(&parent_path);
+                    self.selected_index = self.tree_items.iter().position(|item| item == &parent_path);
+                }
+            }
+        }
+    }
+    
+    /// Navigate right (expand current node or go to first child)
+    fn navigate_right(&mut self) {
+        if let Some(selected_path) = &self.selected_node.clone() {
+            if let Some(node) = self.find_node_by_path(selected_path) {
+                if node.is_expandable() {
+                    if !node.has_children() {
+                        // Nothing to enter - don't leave an empty container marked as expanded
+                        self.status_message = format!("{} is empty", selected_path);
+                    } else if !self.expanded_nodes.contains(selected_path) {
+                        // Expand the node
+                        self.expanded_nodes.insert(selected_path.clone());
+                        // Rebuild tree items since visibility changed
+                        self.build_tree_items();
+                    } else {
+                        // Go to first child (the end of its chain when single-child chains are folded)
+                        let first_child = node.child(0, &self.param_file.hash_labels);
+                        let first_child_path = match first_child {
+                            Some(child) => self.fold_single_child_chain(child, format!("{}[0]", selected_path)).2,
+                            None => format!("{}[0]", selected_path),
+                        };
+                        self.select_path(&first_child_path);
+                        self.selected_index = self.tree_items.iter().position(|item| item == &first_child_path);
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Get the parent path of a given path
+    fn get_parent_path(&self, path: &str) -> Option<String> {
+        if path == "root" {
+            return None;
+        }
+        
+        // Find the last '[' and remove everything from there
+        if let Some(last_bracket) = path.rfind('[') {
+            Some(path[..last_bracket].to_string())
+        } else {
+            None
+        }
+    }
+    
+    /// Search box with match counter and previous/next buttons
+    fn show_search_bar(&mut self, ui: &mut egui::Ui) {
+        self.refresh_search_matches();
+        
+        ui.horizontal(|ui| {
+            ui.label("🔍");
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.search_query)
+                    .hint_text(format!("Search names and values ({})", self.shortcuts.keys_text(Command::Search)))
+                    .desired_width(180.0)
+            );
+            
+            if self.focus_search {
+                response.request_focus();
+                self.focus_search = false;
+            }
+            
+            // Escape clears the query; the box gives up focus so arrow keys drive the tree again
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.search_query.clear();
+                self.scroll_to_selected = true;
+            }
+            
+            // Enter / Shift+Enter steps through matches and keeps focus in the box
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                let forward = !ui.input(|i| i.modifiers.shift);
+                self.step_search_match(forward);
+                response.request_focus();
+            }
+            
+            self.show_case_sensitive_toggle(ui);
+            
+            if self.search_query.is_empty() {
+                return;
+            }
+            
+            let counter = match self.current_search_match() {
+                Some(index) => format!("{} of {}", index + 1, self.search_matches.len()),
+                None if self.search_matches.is_empty() => "No matches".to_string(),
+                None => format!("{} matches", self.search_matches.len()),
+            };
+            ui.label(counter);
+            
+            let has_matches = !self.search_matches.is_empty();
+            if ui.add_enabled(has_matches, egui::Button::new("▲")).on_hover_text(format!("Previous match ({})", self.shortcuts.keys_text(Command::PreviousMatch))).clicked() {
+                self.step_search_match(false);
+            }
+            if ui.add_enabled(has_matches, egui::Button::new("▼")).on_hover_text(format!("Next match ({})", self.shortcuts.keys_text(Command::NextMatch))).clicked() {
+                self.step_search_match(true);
+            }
+            if ui.small_button("✖").on_hover_text("Clear search").clicked() {
+                self.search_query.clear();
+            }
+        });
+    }
+    
+    /// Expand down to `path`, select it and scroll it into view
+    fn jump_to_path(&mut self, path: &str) {
+        self.expand_to_path(path);
+        self.select_path(path);
+        self.build_tree_items();
+        self.selected_index = self.tree_items.iter().position(|item| item == path);
+        self.scroll_to_selected = true;
+    }
+    
+    /// Paths of struct fields keyed by `hash` and hash40 values equal to `hash`, in tree order
+    fn find_nodes_with_hash(&self, hash: u64) -> Vec<String> {
+        let mut paths: Vec<String> = Vec::new();
+        for path in self.param_file.hash_index().paths_using(hash) {
+            // A field keyed by the hash that also holds it as its value is one node
+            if paths.last().map(String::as_str) != Some(path) {
+                paths.push(path.to_string());
+            }
+        }
+        paths
+    }
+    
+    /// Label editor "Find in file": jump to the first use of `hash` and remember the rest
+    fn locate_hash_in_tree(&mut self, hash: u64) {
+        let name = self.param_file.hash_labels.hash_to_string(hash);
+        let paths = self.find_nodes_with_hash(hash);
+        
+        match paths.first().cloned() {
+            Some(first) => {
+                self.jump_to_path(&first);
+                self.status_message = format!("{} is used {} time(s) - showing {}", name, paths.len(), first);
+                self.hash_locations = Some((hash, paths));
+            }
+            None => {
+                self.status_message = format!("{} is not used in this file", name);
+                self.hash_locations = None;
+            }
+        }
+    }
+    
+    /// "Aa" toggle shared by the tree search and the label editor filter
+    fn show_case_sensitive_toggle(&mut self, ui: &mut egui::Ui) {
+        if ui.selectable_label(self.case_sensitive_search, "Aa")
+            .on_hover_text("Match case")
+            .clicked()
+        {
+            self.case_sensitive_search = !self.case_sensitive_search;
+            self.save_preferences();
+        }
+    }
+    
+    /// Recompute the cached match list if the query, case sensitivity or the tree changed
+    fn refresh_search_matches(&mut self) {
+        self.refresh_value_filter();
+        let query = if self.case_sensitive_search {
+            self.search_query.clone()
+        } else {
+            self.search_query.to_lowercase()
+        };
+        // Names and hash40 values match by their labels
+        let key = (query, self.case_sensitive_search, self.param_file.revision(), self.param_file.label_revision());
+        if self.search_cache_key.as_ref() == Some(&key) {
+            return;
+        }
+        
+        self.search_matches.clear();
+        if !key.0.is_empty() {
+            if let Some(root) = self.param_file.get_root() {
+                let mut matches = Vec::new();
+                for (i, child) in root.view().children(&self.param_file.hash_labels).iter().enumerate() {
+                    self.collect_search_matches(child, format!("root[{}]", i), &key.0, &mut matches);
+                }
+                self.search_matches = matches;
+            }
+        }
+        self.search_cache_key = Some(key);
+    }
+    
+    /// `query` is already lowercased when the search is case-insensitive
+    fn collect_search_matches(&self, node: &NodeRef<'_>, path: String, query: &str, matches: &mut Vec<String>) {
+        // Everything under a node the value filter hides is hidden too
+        if self.hidden_by_value_filter(&path) {
+            return;
+        }
+        let contains = |text: &str| if self.case_sensitive_search {
+            text.contains(query)
+        } else {
+            text.to_lowercase().contains(query)
+        };
+        let value_matches = !node.is_expandable()
+            && contains(&node.get_value_string_with_labels(&self.param_file.hash_labels, None));
+        if contains(&node.name) || value_matches {
+            matches.push(path.clone());
+        }
+        
+        for (i, child) in node.children(&self.param_file.hash_labels).iter().enumerate() {
+            self.collect_search_matches(child, format!("{}[{}]", path, i), query, matches);
+        }
+    }
+    
+    /// Type, comparison and value the tree is narrowed by: only leaves that pass, and the
+    /// containers holding them, are shown
+    fn show_value_filter_bar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("⚖").on_hover_text("Show only leaves whose value compares as chosen, with their parents.\nNumbers compare by value; bools, hash40s and strings only by = and ≠");
+            
+            egui::ComboBox::from_id_source("value_filter_type")
+                .width(70.0)
+                .selected_text(self.value_filter_type.unwrap_or("Any type"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.value_filter_type, None, "Any type");
+                    for type_name in ParamValue::TYPE_NAMES.into_iter().filter(|name| !matches!(*name, "List" | "Struct")) {
+                        ui.selectable_value(&mut self.value_filter_type, Some(type_name), type_name);
+                    }
+                });
+            egui::ComboBox::from_id_source("value_filter_comparison")
+                .width(30.0)
+                .selected_text(self.value_filter_comparison.symbol())
+                .show_ui(ui, |ui| {
+                    for comparison in ValueComparison::ALL {
+                        ui.selectable_value(&mut self.value_filter_comparison, comparison, comparison.symbol());
+                    }
+                });
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.value_filter_operand)
+                    .hint_text("Filter by value")
+                    .desired_width(80.0)
+            );
+            if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.clear_value_filter();
+            }
+            
+            self.refresh_value_filter();
+            let Some((matches, kept)) = &self.value_filter_paths else {
+                return;
+            };
+            ui.label(format!("{} leaves", matches));
+            if ui.add_enabled(*matches > 0, egui::Button::new("Expand").small()).on_hover_text("Expand every container holding a match").clicked() {
+                let parents: Vec<String> = kept.iter().filter_map(|path| self.get_parent_path(path)).collect();
+                self.expanded_nodes.extend(parents);
+                self.build_tree_items();
+            }
+            if ui.small_button("✖").on_hover_text("Clear value filter").clicked() {
+                self.clear_value_filter();
+            }
+        });
+    }
+    
+    fn clear_value_filter(&mut self) {
+        self.value_filter_operand.clear();
+        self.value_filter_type = None;
+        self.scroll_to_selected = true;
+    }
+    
+    /// Whether a type is chosen or a value typed in the value filter
+    fn value_filter_active(&self) -> bool {
+        !self.value_filter_operand.trim().is_empty() || self.value_filter_type.is_some()
+    }
+    
+    /// Whether the value filter keeps `path` out of the tree
+    fn hidden_by_value_filter(&self, path: &str) -> bool {
+        self.value_filter_paths.as_ref().is_some_and(|(_, kept)| !kept.contains(path))
+    }
+    
+    /// Recompute the paths the value filter keeps if the filter or the tree changed
+    fn refresh_value_filter(&mut self) {
+        let key = (
+            self.value_filter_comparison,
+            self.value_filter_operand.trim().to_string(),
+            self.value_filter_type,
+            self.param_file.revision(),
+            self.param_file.label_revision(),
+        );
+        if self.value_filter_key.as_ref() == Some(&key) {
+            return;
+        }
+        
+        self.value_filter_paths = None;
+        if self.value_filter_active() && self.param_file.get_root().is_some() {
+            let operand = self.value_filter_operand.trim();
+            let mut matches = 0;
+            let mut kept = HashSet::from(["root".to_string()]);
+            self.param_file.walk_leaves(|path, _, value| {
+                let passes = self.value_filter_type.is_none_or(|type_name| value.type_name() == type_name)
+                    && (operand.is_empty() || value.compare_with(self.value_filter_comparison, operand, &self.param_file.hash_labels));
+                if !passes {
+                    return;
+                }
+                matches += 1;
+                // The leaf and the containers leading to it; stop at one already kept
+                let mut path = Some(path.to_string());
+                while let Some(kept_path) = path.filter(|path| kept.insert(path.clone())) {
+                    path = self.get_parent_path(&kept_path);
+                }
+            });
+            self.value_filter_paths = Some((matches, kept));
+        }
+        self.value_filter_key = Some(key);
+        // Search matches are limited to what the filter shows
+        self.search_cache_key = None;
+    }
+    
+    /// Position of the selected node in the match list
+    fn current_search_match(&self) -> Option<usize> {
+        let selected = self.selected_node.as_ref()?;
+        self.search_matches.iter().position(|path| path == selected)
+    }
+    
+    /// Select the next (or previous) search match in tree order, wrapping around
+    fn step_search_match(&mut self, forward: bool) {
+        self.refresh_search_matches();
+        if self.search_query.is_empty() {
+            return;
+        }
+        if self.search_matches.is_empty() {
+            self.status_message = format!("No matches for '{}'", self.search_query);
+            return;
+        }
+        
+        let count = self.search_matches.len();
+        let index = match self.current_search_match() {
+            Some(current) if forward => (current + 1) % count,
+            Some(current) => (current + count - 1) % count,
+            None if forward => 0,
+            None => count - 1,
+        };
+        
+        let path = self.search_matches[index].clone();
+        self.jump_to_path(&path);
+        self.status_message = format!("Match {} of {}", index + 1, count);
+    }
+    
+    /// Resolve a path like "root[0][2]" into (path, label) pairs from the root down to the node
+    fn label_path_segments(&self, path: &str) -> Vec<(String, String)> {
+        let mut segments = Vec::new();
+        let Some(mut current_node) = self.param_file.get_root().map(|root| root.view()) else {
+            return segments;
+        };
+        
+        let mut current_path = "root".to_string();
+        segments.push((current_path.clone(), "root".to_string()));
+        
+        for part in path.split('[').skip(1) {
+            let Some(index) = part.trim_end_matches(']').parse::<usize>().ok() else {
+                break;
+            };
+            let Some(child) = current_node.child(index, &self.param_file.hash_labels) else {
+                break;
+            };
+            
+            current_path = format!("{}[{}]", current_path, index);
+            let label = if child.name.is_empty() { format!("0x{:X}", child.hash) } else { child.name.clone() };
+            segments.push((current_path.clone(), label));
+            current_node = child;
+        }
+        
+        segments
+    }
+    
+    /// Expand `path` and every container below it
+    fn expand_all_under(&mut self, path: &str) {
+        let Some(node) = self.find_node_by_path(path) else {
+            return;
+        };
+        let mut paths = Vec::new();
+        let mut pending = vec![(node, path.to_string())];
+        while let Some((node, node_path)) = pending.pop() {
+            if !node.has_children() {
+                continue;
+            }
+            for (i, child) in node.children(&self.param_file.hash_labels).into_iter().enumerate() {
+                if child.is_expandable() {
+                    pending.push((child, format!("{}[{}]", node_path, i)));
+                }
+            }
+            paths.push(node_path);
+        }
+        
+        self.status_message = format!("Expanded {} nodes under {}", paths.len(), path);
+        self.expanded_nodes.extend(paths);
+    }
+    
+    /// Collapse `path` and every descendant, so it reopens one level at a time
+    fn collapse_all_under(&mut self, path: &str) {
+        let before = self.expanded_nodes.len();
+        self.expanded_nodes.retain(|expanded| !is_same_or_descendant(expanded, path));
+        self.status_message = format!("Collapsed {} nodes under {}", before - self.expanded_nodes.len(), path);
+        
+        // Keep keyboard selection on a visible row
+        if self.selected_node.as_deref().is_some_and(|selected| selected != path && is_same_or_descendant(selected, path)) {
+            self.select_path(path);
+        }
+    }
+    
+    /// Expand every ancestor of a path so the node is visible in the tree
+    fn expand_to_path(&mut self, path: &str) {
+        let mut current = self.get_parent_path(path);
+        while let Some(parent_path) = current {
+            current = self.get_parent_path(&parent_path);
+            self.expanded_nodes.insert(parent_path);
+        }
+    }
+    
+    /// Collect paths of nodes whose struct key or hash40 value has no exact label, in tree order
+    fn collect_unlabeled_paths(&self) -> Vec<String> {
+        let hash_labels = &self.param_file.hash_labels;
+        let mut paths: Vec<String> = Vec::new();
+        for (hash, path) in self.param_file.hash_index().uses() {
+            if !hash_labels.has_exact_label(hash) && paths.last().map(String::as_str) != Some(path) {
+                paths.push(path.to_string());
+            }
+        }
+        paths
+    }
+    
+    /// Select the next unlabeled node after the current selection, wrapping around
+    fn select_next_unlabeled(&mut self) {
+        let paths = self.collect_unlabeled_paths();
+        if paths.is_empty() {
+            self.status_message = "No unlabeled hashes in this file".to_string();
+            return;
+        }
+        
+        let current_position = self.selected_node.as_ref()
+            .and_then(|selected| paths.iter().position(|p| p == selected));
+        let next_index = match current_position {
+            Some(index) => (index + 1) % paths.len(),
+            None => 0,
+        };
+        
+        let next_path = paths[next_index].clone();
+        self.expand_to_path(&next_path);
+        self.select_path(&next_path);
+        self.build_tree_items();
+        self.status_message = format!("Unlabeled hash {} of {}: {}", next_index + 1, paths.len(), next_path);
+    }
+    
+    /// Set (or with None, remove) the label for `hash`, recording an undo action and saving the CSV
+    fn change_label_with_undo(&mut self, hash: u64, label: Option<&str>) -> anyhow::Result<()> {
+        let old = self.param_file.hash_labels.label_state(hash);
+        if old.label.as_deref() == label {
+            return Ok(());
+        }
+        
+        match label {
+            Some(label) => self.param_file.hash_labels.add_label_for_hash(hash, label),
+            None => self.param_file.hash_labels.remove_label(hash),
+        }
+        self.param_file.refresh_names_only();
+        let new = self.param_file.hash_labels.label_state(hash);
+        self.push_undo_action(UndoAction::Label { hash, old, new });
+        
+        if let Some(path) = &self.param_labels_path {
+            self.param_file.hash_labels.save_to_csv(path)?;
+        }
+        Ok(())
+    }
+    
+    /// Apply a label state from the undo/redo stacks; returns a note about saving the CSV
+    fn apply_label(&mut self, hash: u64, state: &LabelState) -> String {
+        self.param_file.hash_labels.restore_label(hash, state);
+        self.param_file.refresh_names_only();
+        
+        match &self.param_labels_path {
+            Some(path) => match self.param_file.hash_labels.save_to_csv(path) {
+                Ok(()) => String::new(),
+                Err(e) => format!(" (failed to save labels: {})", e),
+            },
+            None => String::new(),
+        }
+    }
+    
+    /// Push an action to the undo stack and clear redo stack
+    fn push_undo_action(&mut self, action: UndoAction) {
+        self.log_event(&format!("Edit: {}", action.describe()));
+        self.undo_stack.push(action);
+        self.redo_stack.clear(); // Clear redo stack when new action is performed
+        
+        self.trim_undo_stack();
+    }
+    
+    /// Drop the oldest undo actions beyond the configured limit
+    fn trim_undo_stack(&mut self) {
+        // Limit undo stack size to prevent memory issues
+        if self.undo_stack.len() > self.undo_limit {
+            let excess = self.undo_stack.len() - self.undo_limit;
+            self.undo_stack.drain(..excess);
+        }
+    }
+    
+    /// Perform undo operation
+    fn undo(&mut self) -> bool {
+        if let Some(action) = self.undo_stack.pop() {
+            match action.clone() {
+                UndoAction::DeleteNode { path, node, parent_path, index } => {
+                    // Undoing a cut's delete brings the node back, so it no longer needs restoring
+                    if self.cut_origin.as_ref() == Some(&(parent_path.clone(), index)) {
+                        self.cut_origin = None;
+                    }
+                    
+                    // Restore the deleted node
+                    if self.restore_node_at_index(&parent_path, node, index) {
+                        self.redo_stack.push(UndoAction::AddNode { path });
+                        self.status_message = "Undid delete operation".to_string();
+                        self.build_tree_items();
+                        return true;
+                    }
+                }
+                UndoAction::AddNode { path } => {
+                    // Remove the added node
+                    if let Some(removed) = self.remove_added_node(&path) {
+                        self.redo_stack.push(removed);
+                        self.status_message = "Undid add operation".to_string();
+                        self.build_tree_items();
+                        return true;
+                    }
+                }
+                UndoAction::UpdateValue { path, old_value, new_value } => {
+                    // Restore the old value
+                    if self.param_file.update_node_value(&path, old_value.clone()) {
+                        // Redo entries keep the original direction; redo re-applies new_value
+                        self.redo_stack.push(UndoAction::UpdateValue { path, old_value, new_value });
+                        self.status_message = "Undid value change".to_string();
+                        return true;
+                    }
+                }
+                UndoAction::UpdateKey { path, old_name, old_hash, new_name, new_hash } => {
+                    // Restore the old key
+                    if self.param_file.update_node_key(&path, old_name.clone(), old_hash) {
+                        self.redo_stack.push(UndoAction::UpdateKey { path, old_name, old_hash, new_name, new_hash });
+                        self.status_message = "Undid key change".to_string();
+                        return true;
+                    }
+                }
+                UndoAction::Move { from_parent, from_index, to_parent, to_index, hash } => {
+                    // The reverse move: out of the destination, back to the original slot
+                    if let Some(path) = self.move_node_raw(&to_parent, to_index, hash, &from_parent, from_index) {
+                        self.redo_stack.push(UndoAction::Move { from_parent, from_index, to_parent, to_index, hash });
+                        self.status_message = "Undid move".to_string();
+                        self.select_path(&path);
+                        return true;
+                    }
+                }
+                UndoAction::Batch { actions } => {
+                    // Undo each inner action through the normal path, gathering their redo entries
+                    let count = actions.len();
+                    let (undo_len, redo_len) = (self.undo_stack.len(), self.redo_stack.len());
+                    self.undo_stack.extend(actions);
+                    let mut redo_actions = Vec::new();
+                    for _ in 0..count {
+                        if !self.undo() {
+                            break;
+                        }
+                        redo_actions.extend(self.redo_stack.pop());
+                    }
+                    
+                    if redo_actions.len() == count {
+                        redo_actions.reverse();
+                        self.redo_stack.push(UndoAction::Batch { actions: redo_actions });
+                        self.status_message = format!("Undid batch of {} changes", count);
+                        return true;
+                    }
+                    
+                    // All or nothing: redo the part that was undone and keep the batch on the undo stack
+                    let failure = std::mem::take(&mut self.status_message);
+                    let undone = redo_actions.len();
+                    self.redo_stack.extend(redo_actions);
+                    for _ in 0..undone {
+                        if !self.redo() {
+                            break;
+                        }
+                    }
+                    self.undo_stack.truncate(undo_len);
+                    self.redo_stack.truncate(redo_len);
+                    self.undo_stack.push(action);
+                    self.build_tree_items();
+                    self.status_message = format!("Couldn't undo batch of {} changes: {}", count, failure);
+                    return false;
+                }
+                UndoAction::Label { hash, old, new } => {
+                    let save_note = self.apply_label(hash, &old);
+                    self.redo_stack.push(UndoAction::Label { hash, old, new });
+                    self.status_message = format!("Undid label change for 0x{:X}{}", hash, save_note);
+                    return true;
+                }
+            }
+            // A failed action stays where it was
+            self.undo_stack.push(action);
+        }
+        false
+    }
+    
+    /// Undo the latest action and record it in the session log
+    fn undo_and_log(&mut self) -> bool {
+        let description = self.undo_stack.last().map(UndoAction::describe);
+        let undone = self.undo();
+        if let Some(description) = description.filter(|_| undone) {
+            self.log_event(&format!("Undo: {}", description));
+        }
+        undone
+    }
+    
+    /// Redo the latest undone action and record it in the session log
+    fn redo_and_log(&mut self) -> bool {
+        let description = self.redo_stack.last().map(UndoAction::describe);
+        let redone = self.redo();
+        if let Some(description) = description.filter(|_| redone) {
+            self.log_event(&format!("Redo: {}", description));
+        }
+        redone
+    }
+    
+    /// Undo until the stack is empty, stopping at the first action that can't be undone
+    fn undo_all(&mut self) {
+        let total = self.undo_stack.len();
+        let mut undone = 0;
+        while !self.undo_stack.is_empty() && self.undo_and_log() {
+            undone += 1;
+        }
+        
+        self.status_message = if total == 0 {
+            "Nothing to undo".to_string()
+        } else if undone == total {
+            format!("Reverted {} changes", undone)
+        } else {
+            format!("Undid {} of {} changes; stopped at one that couldn't be undone ({})", undone, total, self.status_message)
+        };
+    }
+    
+    /// Redo until the stack is empty, stopping at the first action that can't be redone
+    fn redo_all(&mut self) {
+        let total = self.redo_stack.len();
+        let mut redone = 0;
+        while !self.redo_stack.is_empty() && self.redo_and_log() {
+            redone += 1;
+        }
+        
+        self.status_message = if total == 0 {
+            "Nothing to redo".to_string()
+        } else if redone == total {
+            format!("Reapplied {} changes", redone)
+        } else {
+            format!("Redid {} of {} changes; stopped at one that couldn't be redone ({})", redone, total, self.status_message)
+        };
+    }
+    
+    fn redo(&mut self) -> bool {
+        if let Some(action) = self.redo_stack.pop() {
+            match action.clone() {
+                UndoAction::DeleteNode { path, node, parent_path, index } => {
+                    // Undoing an add left this entry; put the node back
+                    if self.restore_node_at_index(&parent_path, node, index) {
+                        self.undo_stack.push(UndoAction::AddNode { path });
+                        self.status_message = "Redid add operation".to_string();
+                        self.build_tree_items();
+                        return true;
+                    }
+                }
+                UndoAction::AddNode { path } => {
+                    // Undoing a delete left this entry; remove the node again
+                    if let Some(removed) = self.remove_added_node(&path) {
+                        self.undo_stack.push(removed);
+                        self.status_message = "Redid delete operation".to_string();
+                        self.build_tree_items();
+                        return true;
+                    }
+                }
+                UndoAction::UpdateValue { path, old_value, new_value } => {
+                    // Re-apply the new value
+                    if self.param_file.update_node_value(&path, new_value.clone()) {
+                        self.undo_stack.push(UndoAction::UpdateValue { 
+                            path, 
+                            old_value: old_value, 
+                            new_value: new_value 
+                        });
+                        self.status_message = "Redid value change".to_string();
+                        return true;
+                    }
+                }
+                UndoAction::UpdateKey { path, old_name, old_hash, new_name, new_hash } => {
+                    // Re-apply the new key
+                    if self.param_file.update_node_key(&path, new_name.clone(), new_hash) {
+                        self.undo_stack.push(UndoAction::UpdateKey { 
+                            path, 
+                            old_name, 
+                            old_hash, 
+                            new_name, 
+                            new_hash 
+                        });
+                        self.status_message = "Redid key change".to_string();
+                        return true;
+                    }
+                }
+                UndoAction::Move { from_parent, from_index, to_parent, to_index, hash } => {
+                    if let Some(path) = self.move_node_raw(&from_parent, from_index, hash, &to_parent, to_index) {
+                        self.undo_stack.push(UndoAction::Move { from_parent, from_index, to_parent, to_index, hash });
+                        self.status_message = "Redid move".to_string();
+                        self.select_path(&path);
+                        return true;
+                    }
+                }
+                UndoAction::Batch { actions } => {
+                    // Redo inner actions in their original order
+                    let count = actions.len();
+                    let (undo_len, redo_len) = (self.undo_stack.len(), self.redo_stack.len());
+                    self.redo_stack.extend(actions.into_iter().rev());
+                    let mut undo_actions = Vec::new();
+                    for _ in 0..count {
+                        if !self.redo() {
+                            break;
+                        }
+                        undo_actions.extend(self.undo_stack.pop());
+                    }
+                    
+                    if undo_actions.len() == count {
+                        self.undo_stack.push(UndoAction::Batch { actions: undo_actions });
+                        self.status_message = format!("Redid batch of {} changes", count);
+                        return true;
+                    }
+                    
+                    // All or nothing: undo the part that was redone and keep the batch on the redo stack
+                    let failure = std::mem::take(&mut self.status_message);
+                    let redone = undo_actions.len();
+                    self.undo_stack.extend(undo_actions);
+                    for _ in 0..redone {
+                        if !self.undo() {
+                            break;
+                        }
+                    }
+                    self.undo_stack.truncate(undo_len);
+                    self.redo_stack.truncate(redo_len);
+                    self.redo_stack.push(action);
+                    self.build_tree_items();
+                    self.status_message = format!("Couldn't redo batch of {} changes: {}", count, failure);
+                    return false;
+                }
+                UndoAction::Label { hash, old, new } => {
+                    let save_note = self.apply_label(hash, &new);
+                    self.undo_stack.push(UndoAction::Label { hash, old, new });
+                    self.status_message = format!("Redid label change for 0x{:X}{}", hash, save_note);
+                    return true;
+                }
+            }
+            // A failed action stays where it was
+            self.redo_stack.push(action);
+        }
+        false
+    }
+    
+    /// Remove the node an add put at `path`, returning the entry that restores it
+    fn remove_added_node(&mut self, path: &str) -> Option<UndoAction> {
+        let node = self.find_node_by_path(path).map(|n| n.to_owned_node())?;
+        let parent_path = self.get_parent_path(path)?;
+        let index = self.get_node_index_in_parent(path)?;
+        
+        // delete_node records an undo step and clears redo; neither belongs to an undo or redo
+        let stacks = (std::mem::take(&mut self.undo_stack), std::mem::take(&mut self.redo_stack));
+        let deleted = self.delete_node(path);
+        (self.undo_stack, self.redo_stack) = stacks;
+        deleted.then(|| UndoAction::DeleteNode { path: path.to_string(), node, parent_path, index })
+    }
+    
+    /// Get the index of a node within its parent
+    fn get_node_index_in_parent(&self, path: &str) -> Option<usize> {
+        if let Some(parent_path) = self.get_parent_path(path) {
+            if let Some(_parent_node) = self.find_node_by_path(&parent_path) {
+                // Extract the index from the path
+                if let Some(last_bracket) = path.rfind('[') {
+                    if let Some(close_bracket) = path.rfind(']') {
+                        let index_str = &path[last_bracket + 1..close_bracket];
+                        return index_str.parse::<usize>().ok();
+                    }
+                }
+            }
+        }
+        None
+    }
+    
+    /// Restore a node at a specific index in its parent
+    fn restore_node_at_index(&mut self, parent_path: &str, node: ParamNode, index: usize) -> bool {
+        self.multi_selection.clear(); // Later siblings shift down
+        let parent_indices = match self.param_file.parse_node_path(parent_path) {
+            Some(indices) => indices,
+            None => return false,
+        };
+        
+        // Add to the underlying data structure at the specific index
+        if let Some(root) = &mut self.param_file.root {
+            if Self::restore_to_param_value(&mut root.value, &parent_indices, node.clone(), index, 0) {
+                self.param_file.mark_modified();
+                return true;
+            }
+        }
+        
+        false
+    }
+    
+    /// Restore a node to the underlying ParamValue structure at a specific index
+    fn restore_to_param_value(
+        value: &mut ParamValue,
+        indices: &[usize],
+        node_to_restore: ParamNode,
+        target_index: usize,
+        depth: usize
     ) -> bool {
         if depth == indices.len() {
             // We're at the target parent, restore the node at the specific index
@@ -1719,336 +5703,2052 @@ impl PrcEditorApp {
                     let mut new_fields = indexmap::IndexMap::new();
                     let mut current_index = 0;
                     
-                    // Copy existing fields, inserting the restored node at the target index
-                    for (hash, field_value) in s.fields.iter() {
-                        if current_index == target_index {
-                            new_fields.insert(node_to_restore.hash, node_to_restore.value.clone());
-                            current_index += 1;
+                    // Copy existing fields, inserting the restored node at the target index
+                    for (hash, field_value) in s.fields.iter() {
+                        if current_index == target_index {
+                            new_fields.insert(node_to_restore.hash, node_to_restore.value.clone());
+                            current_index += 1;
+                        }
+                        new_fields.insert(*hash, field_value.clone());
+                        current_index += 1;
+                    }
+                    
+                    // If target index is at the end
+                    if target_index >= s.fields.len() {
+                        new_fields.insert(node_to_restore.hash, node_to_restore.value);
+                    }
+                    
+                    s.fields = new_fields;
+                    return true;
+                }
+                ParamValue::List(ref mut l) => {
+                    if target_index <= l.values.len() {
+                        l.values.insert(target_index, node_to_restore.value);
+                        return true;
+                    }
+                }
+                _ => return false,
+            }
+        }
+        
+        // Continue recursing
+        let current_index = indices[depth];
+        match value {
+            ParamValue::Struct(ref mut s) => {
+                if let Some((_, field_value)) = s.fields.get_index_mut(current_index) {
+                    return Self::restore_to_param_value(field_value, indices, node_to_restore, target_index, depth + 1);
+                }
+            }
+            ParamValue::List(ref mut l) => {
+                if current_index < l.values.len() {
+                    return Self::restore_to_param_value(&mut l.values[current_index], indices, node_to_restore, target_index, depth + 1);
+                }
+            }
+            _ => {}
+        }
+        
+        false
+    }
+
+    /// Update a node's value with undo tracking
+    fn update_node_value_with_undo(&mut self, path: &str, new_value: ParamValue) -> bool {
+        // Get the old value for undo
+        if let Some(old_value) = self.param_file.get_node_value(path) {
+            if self.param_file.update_node_value(path, new_value.clone()) {
+                // Record undo action
+                self.push_undo_action(UndoAction::UpdateValue {
+                    path: path.to_string(),
+                    old_value,
+                    new_value,
+                });
+                self.note_recent_edit(path);
+                return true;
+            }
+        }
+        false
+    }
+    
+    /// Set the hash40 value at `path` from typed text: 0x hex is used as-is, anything else is a
+    /// label that is hashed and added to the labels file. Saving rebuilds the hash table from the
+    /// tree, so the new hash needs no separate registration. Returns the status message.
+    fn apply_hash_edit(&mut self, path: &str, text: &str) -> String {
+        let text = text.trim();
+        if text.is_empty() {
+            return "Hash40 value can't be empty".to_string();
+        }
+        
+        let hex = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X"));
+        let hash = match hex {
+            Some(hex) => match u64::from_str_radix(hex, 16) {
+                Ok(hash) => hash,
+                Err(_) => return format!("Invalid hash40 value: {}", text),
+            },
+            None => self.param_file.hash_labels.add_label_and_save(text, self.param_labels_path.as_deref()),
+        };
+        
+        if !self.update_node_value_with_undo(path, ParamValue::Hash(hash)) {
+            return "Failed to update hash value".to_string();
+        }
+        match hex {
+            Some(_) => format!("Hash40 value set to 0x{:X}", hash),
+            None => {
+                let path_display = self.param_labels_path.as_deref().unwrap_or("ParamLabels.csv");
+                format!("Hash40 value set to '{}' (0x{:X}) and saved to {}", text, hash, path_display)
+            }
+        }
+    }
+    
+    /// Update a node's key with undo tracking. A key that collides with a sibling field is
+    /// not applied; it is held for the overwrite confirmation window instead.
+    fn update_node_key_with_undo(&mut self, path: &str, new_name: String, new_hash: u64) -> bool {
+        if self.param_file.key_collision(path, new_hash).is_some() {
+            self.status_message = format!("Key '{}' already exists in this struct - confirm to overwrite it", new_name);
+            self.pending_key_overwrite = Some((path.to_string(), new_name, new_hash));
+            return false;
+        }
+        
+        // Get the old key for undo
+        if let Some(node) = self.find_node_by_path(path) {
+            let old_name = node.name.clone();
+            let old_hash = node.hash;
+            
+            if self.param_file.update_node_key(path, new_name.clone(), new_hash) {
+                // Record undo action
+                self.push_undo_action(UndoAction::UpdateKey {
+                    path: path.to_string(),
+                    old_name,
+                    old_hash,
+                    new_name,
+                    new_hash,
+                });
+                self.note_recent_edit(path);
+                return true;
+            }
+        }
+        false
+    }
+    
+    /// Move `path` to the front of the recently edited list, dropping an older entry for it
+    fn note_recent_edit(&mut self, path: &str) {
+        self.recent_edits.retain(|recent| recent != path);
+        self.recent_edits.insert(0, path.to_string());
+        self.recent_edits.truncate(MAX_RECENT_EDITS);
+    }
+    
+    /// Collapsible list of recently edited nodes; clicking one selects it again
+    fn show_recent_edits(&mut self, ui: &mut egui::Ui) {
+        let mut jump_to = None;
+        egui::CollapsingHeader::new(format!("Recently Edited ({})", self.recent_edits.len()))
+            .id_source("recent_edits")
+            .show(ui, |ui| {
+                for path in &self.recent_edits {
+                    let labels: Vec<String> = self.label_path_segments(path).into_iter().map(|(_, label)| label).collect();
+                    let name = labels.last().cloned().unwrap_or_else(|| path.clone());
+                    let selected = self.selected_node.as_deref() == Some(path.as_str());
+                    if ui.selectable_label(selected, truncate_chars(&name, 40)).on_hover_text(labels.join(" > ")).clicked() {
+                        jump_to = Some(path.clone());
+                    }
+                }
+            });
+        if let Some(path) = jump_to {
+            self.jump_to_path(&path);
+        }
+    }
+    
+    /// Rename struct keys in bulk from an `old_label,new_label` mapping CSV as a single undo step
+    fn batch_rename_keys_dialog(&mut self) {
+        let Some(file_path) = FileDialog::new()
+            .add_filter("CSV files", &["csv"])
+            .add_filter("All files", &["*"])
+            .set_title("Select rename mapping (old_label,new_label)")
+            .pick_file()
+        else {
+            return;
+        };
+        
+        let mapping = match std::fs::read_to_string(&file_path).map_err(anyhow::Error::from).and_then(|c| HashLabels::parse_rename_mapping(&c)) {
+            Ok(mapping) => mapping,
+            Err(e) => {
+                self.status_message = format!("Error reading rename mapping: {}", e);
+                return;
+            }
+        };
+        
+        // Resolve both sides of the mapping to hashes, registering the new labels
+        let mut renames: HashMap<u64, (String, u64)> = HashMap::new();
+        let mut old_labels = Vec::new();
+        for (old_label, new_label) in &mapping {
+            let Ok(old_hash) = self.param_file.hash_labels.parse_hash_or_label(old_label) else {
+                continue;
+            };
+            let new_hash = self.param_file.hash_labels.add_label(new_label);
+            renames.insert(old_hash, (new_label.clone(), new_hash));
+            old_labels.push((old_hash, old_label.clone()));
+        }
+        
+        // Find matching struct fields, skipping any rename that would collide with a sibling key
+        let mut matches = Vec::new();
+        let mut collisions = Vec::new();
+        if let Some(root) = self.param_file.get_root() {
+            Self::collect_rename_matches(&root.value, "root".to_string(), &renames, &mut matches, &mut collisions);
+        }
+        
+        let mut actions = Vec::new();
+        let mut matched_hashes = HashSet::new();
+        for (path, old_hash, new_name, new_hash) in matches {
+            let Some(old_name) = self.find_node_by_path(&path).map(|n| n.name.clone()) else {
+                continue;
+            };
+            if self.param_file.update_node_key(&path, new_name.clone(), new_hash) {
+                matched_hashes.insert(old_hash);
+                actions.push(UndoAction::UpdateKey { path, old_name, old_hash, new_name, new_hash });
+            } else {
+                collisions.push(path);
+            }
+        }
+        
+        if let Some(labels_path) = self.param_labels_path.as_deref() {
+            let _ = self.param_file.hash_labels.save_to_csv(labels_path);
+        }
+        
+        let renamed = actions.len();
+        if !actions.is_empty() {
+            self.push_undo_action(UndoAction::Batch { actions });
+            self.build_tree_items();
+        }
+        
+        let misses: Vec<&str> = old_labels.iter()
+            .filter(|(hash, _)| !matched_hashes.contains(hash))
+            .map(|(_, label)| label.as_str())
+            .collect();
+        let mut message = format!("Renamed {} keys from {} mapping entries", renamed, mapping.len());
+        if !misses.is_empty() {
+            message.push_str(&format!(" | {} unmatched: {}", misses.len(), truncate_chars(&misses.join(", "), 80)));
+        }
+        if !collisions.is_empty() {
+            message.push_str(&format!(" | {} skipped (key already exists): {}", collisions.len(), truncate_chars(&collisions.join(", "), 80)));
+        }
+        self.status_message = message;
+    }
+    
+    /// Collect (path, old_hash, new_name, new_hash) for struct fields with a mapped key
+    fn collect_rename_matches(
+        value: &ParamValue,
+        path: String,
+        renames: &HashMap<u64, (String, u64)>,
+        matches: &mut Vec<(String, u64, String, u64)>,
+        collisions: &mut Vec<String>,
+    ) {
+        match value {
+            ParamValue::Struct(s) => {
+                let mut taken: HashSet<u64> = s.fields.keys().copied().collect();
+                for (i, (hash, child)) in s.fields.iter().enumerate() {
+                    let child_path = format!("{}[{}]", path, i);
+                    if let Some((new_name, new_hash)) = renames.get(hash) {
+                        if *new_hash != *hash {
+                            if taken.contains(new_hash) {
+                                collisions.push(child_path.clone());
+                            } else {
+                                taken.insert(*new_hash);
+                                matches.push((child_path.clone(), *hash, new_name.clone(), *new_hash));
+                            }
+                        }
+                    }
+                    Self::collect_rename_matches(child, child_path, renames, matches, collisions);
+                }
+            }
+            ParamValue::List(l) => {
+                for (i, child) in l.values.iter().enumerate() {
+                    Self::collect_rename_matches(child, format!("{}[{}]", path, i), renames, matches, collisions);
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    /// Add a node with undo tracking
+    fn add_node_with_undo(&mut self, target_path: &str, node_to_add: ParamNode) -> bool {
+        let target_indices = match self.param_file.parse_node_path(target_path) {
+            Some(indices) => indices,
+            None => return false,
+        };
+        
+        // Get the current size to calculate the new index
+        let new_index = if let Some(target_node) = self.find_node_by_path(target_path) {
+            match &target_node.value {
+                ParamValue::Struct(s) => s.fields.len(),
+                ParamValue::List(l) => l.values.len(),
+                _ => return false,
+            }
+        } else {
+            return false;
+        };
+        
+        // Add to the underlying data structure
+        if let Some(root) = &mut self.param_file.root {
+            if Self::add_to_param_value(&mut root.value, &target_indices, node_to_add.clone(), 0) {
+                self.param_file.mark_modified();
+                // Calculate the path where the node was added
+                let added_path = format!("{}[{}]", target_path, new_index);
+                
+                // Record undo action
+                self.push_undo_action(UndoAction::AddNode {
+                    path: added_path,
+                });
+                return true;
+            }
+        }
+        
+        false
+    }
+
+    /// Generate a sequential name for a new node to avoid duplicates
+    fn generate_sequential_name(&self, parent_path: &str, _base_name: &str) -> String {
+        // Get the parent node to check existing children
+        if let Some(parent_node) = self.find_node_by_path(parent_path) {
+            // Find the highest numeric name among all children
+            let mut max_number = 0;
+            
+            for child in parent_node.children(&self.param_file.hash_labels) {
+                // Try to parse the child name as a number
+                if let Ok(number) = child.name.parse::<u32>() {
+                    max_number = max_number.max(number);
+                }
+                
+                // Also try to extract numbers from names like "[18]" 
+                let trimmed = child.name.trim_start_matches('[').trim_end_matches(']');
+                if let Ok(number) = trimmed.parse::<u32>() {
+                    max_number = max_number.max(number);
+                }
+            }
+            
+            // Return the next sequential number with brackets
+            format!("[{}]", max_number + 1)
+        } else {
+            // Fallback if we can't find the parent
+            "[1]".to_string()
+        }
+    }
+    
+    /// Ensure a name is unique by adding _copy suffix if needed
+    fn ensure_unique_name(&self, node_path: &str, desired_name: &str) -> String {
+        // Get the parent path to check for siblings
+        if let Some(parent_path) = self.get_parent_path(node_path) {
+            if let Some(parent_node) = self.find_node_by_path(&parent_path) {
+                // Check if the desired name conflicts with any sibling (excluding self)
+                let current_node_index = self.get_node_index_in_parent(node_path);
+                
+                let name_conflicts = parent_node.children(&self.param_file.hash_labels).iter().enumerate().any(|(i, child)| {
+                    // Don't compare with self
+                    if let Some(current_index) = current_node_index {
+                        if i == current_index {
+                            return false;
+                        }
+                    }
+                    child.name == desired_name
+                });
+                
+                if name_conflicts {
+                    // Add _# suffix starting from _2
+                    let mut copy_counter = 2;
+                    loop {
+                        let copy_name = format!("{}_{}", desired_name, copy_counter);
+                        
+                        let copy_exists = parent_node.children(&self.param_file.hash_labels).iter().enumerate().any(|(i, child)| {
+                            // Don't compare with self
+                            if let Some(current_index) = current_node_index {
+                                if i == current_index {
+                                    return false;
+                                }
+                            }
+                            child.name == copy_name
+                        });
+                        
+                        if !copy_exists {
+                            return copy_name;
+                        }
+                        
+                        copy_counter += 1;
+                        if copy_counter > 100 {
+                            break; // Prevent infinite loop
+                        }
+                    }
+                }
+            }
+        }
+        
+        // No conflict or couldn't check, return original name
+        desired_name.to_string()
+    }
+    
+    /// Generate a name for pasting, preserving original names when possible
+    fn generate_paste_name(&self, parent_path: &str, original_name: &str) -> String {
+        // If the original name is not a number or bracketed number, try to preserve it
+        let is_numeric = original_name.parse::<u32>().is_ok();
+        let is_bracketed_numeric = {
+            let trimmed = original_name.trim_start_matches('[').trim_end_matches(']');
+            original_name.starts_with('[') && original_name.ends_with(']') && trimmed.parse::<u32>().is_ok()
+        };
+        
+        if !is_numeric && !is_bracketed_numeric {
+            // Check if this name already exists in the parent
+            if let Some(parent_node) = self.find_node_by_path(parent_path) {
+                let name_exists = parent_node.children(&self.param_file.hash_labels).iter().any(|child| child.name == original_name);
+                if !name_exists {
+                    // Original name doesn't exist, we can use it as-is
+                    return original_name.to_string();
+                }
+                
+                // If it exists, try adding _# suffix for text names starting from _2
+                let mut copy_counter = 2;
+                loop {
+                    let copy_name = format!("{}_{}", original_name, copy_counter);
+                    
+                    let copy_exists = parent_node.children(&self.param_file.hash_labels).iter().any(|child| child.name == copy_name);
+                    if !copy_exists {
+                        return copy_name;
+                    }
+                    
+                    copy_counter += 1;
+                    if copy_counter > 100 {
+                        break; // Prevent infinite loop
+                    }
+                }
+            }
+        }
+        
+        // For numeric names or when name conflicts exist, generate sequential name
+        self.generate_sequential_name(parent_path, original_name)
+    }
+    
+    /// Find the Smash Ultimate Blender plugin directory
+    fn find_blender_addon_directory() -> Option<PathBuf> {
+        // Get the user's AppData/Roaming directory
+        if let Some(mut appdata_dir) = dirs::config_dir() {
+            // On Windows, this gives us AppData/Roaming
+            // On other platforms, we'll try to find Blender config
+            
+            #[cfg(target_os = "windows")]
+            {
+                appdata_dir.push("Blender Foundation");
+                appdata_dir.push("Blender");
+            }
+            
+            #[cfg(not(target_os = "windows"))]
+            {
+                // On Linux/Mac, Blender config is usually in ~/.config/blender/
+                appdata_dir.push("blender");
+            }
+            
+            // Try to find any Blender version directory
+            if let Ok(entries) = std::fs::read_dir(&appdata_dir) {
+                let mut blender_versions: Vec<_> = entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
+                    .filter_map(|entry| {
+                        let name = entry.file_name().to_string_lossy().to_string();
+                        // Look for version patterns like "4.2", "3.6", etc.
+                        if name.chars().next().map(|c| c.is_ascii_digit()).unwrap_or(false) {
+                            Some((name, entry.path()))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                
+                // Sort by version (newest first)
+                blender_versions.sort_by(|a, b| b.0.cmp(&a.0));
+                
+                // Try each version directory
+                for (_, version_path) in blender_versions {
+                    let mut addon_path = version_path;
+                    addon_path.push("scripts");
+                    addon_path.push("addons");
+                    addon_path.push("smash_ultimate_blender");
+                    addon_path.push("dependencies");
+                    addon_path.push("pyprc");
+                    
+                    if addon_path.exists() {
+                        return Some(addon_path);
+                    }
+                }
+            }
+        }
+        
+        None
+    }
+    
+    /// Get the path to the configuration file
+    fn get_config_path() -> std::path::PathBuf {
+        // Store config in the same directory as the executable
+        let mut config_path = std::env::current_exe()
+            .unwrap_or_else(|_| std::path::PathBuf::from("."));
+        config_path.pop(); // Remove the executable name
+        config_path.push("prc_editor_config.txt");
+        config_path
+    }
+    
+    /// Save the ParamLabels.csv path to a config file
+    fn save_labels_path(&self, path: &str) {
+        let config_path = Self::get_config_path();
+        let _ = std::fs::write(&config_path, path);
+    }
+    
+    /// Report a failed write of ParamLabels.csv in the status bar, and offer to move the file
+    /// somewhere writable unless the user already chose to keep it
+    fn poll_labels_save_failure(&mut self) {
+        let Some(error) = self.param_file.hash_labels.take_save_failure() else {
+            return;
+        };
+        let path = self.param_labels_path.as_deref().unwrap_or("ParamLabels.csv");
+        self.status_message = format!("⚠ Labels not saved - couldn't write {}: {}", path, error);
+        if !self.keep_unwritable_labels {
+            self.labels_save_failure = Some(error);
+        }
+    }
+    
+    /// ParamLabels.csv in the user's config folder, which the user can always write to
+    fn writable_labels_location() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("prc_editor").join("ParamLabels.csv"))
+    }
+    
+    /// Write the labels to `new_path` and use it from now on, remembering it for next time.
+    /// Descriptions and ranges next to the old file are copied along, since they're looked
+    /// for next to the labels. The old file is left as it was.
+    fn relocate_labels(&mut self, new_path: &Path) {
+        if let Some(dir) = new_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                self.status_message = format!("Couldn't create {}: {}", dir.display(), e);
+                return;
+            }
+        }
+        let new_path_string = new_path.to_string_lossy().to_string();
+        if let Err(e) = self.param_file.hash_labels.save_to_csv(&new_path_string) {
+            // Still in the prompt, which says why
+            self.param_file.hash_labels.take_save_failure();
+            self.labels_save_failure = Some(e.to_string());
+            self.status_message = format!("Couldn't write labels to {}: {}", new_path.display(), e);
+            return;
+        }
+        
+        let old_path = self.param_labels_path.replace(new_path_string.clone());
+        let old_dir = old_path.as_deref().and_then(|path| Path::new(path).parent()).map(Path::to_path_buf);
+        let new_dir = new_path.parent().map(Path::to_path_buf).unwrap_or_default();
+        if let Some(old_dir) = old_dir {
+            for name in [DESCRIPTIONS_FILE_NAME, RANGES_FILE_NAME] {
+                let (from, to) = (old_dir.join(name), new_dir.join(name));
+                if from.is_file() && !to.exists() {
+                    let _ = std::fs::copy(&from, &to);
+                }
+            }
+            // Description edits went to the old folder too
+            if self.descriptions_path.as_deref().map(Path::new) == Some(old_dir.join(DESCRIPTIONS_FILE_NAME).as_path()) {
+                self.descriptions_path = Some(new_dir.join(DESCRIPTIONS_FILE_NAME).to_string_lossy().to_string());
+            }
+        }
+        
+        self.save_labels_path(&new_path_string);
+        self.labels_save_failure = None;
+        self.status_message = if self.load_saved_labels_path().as_deref() == Some(new_path_string.as_str()) {
+            format!("Labels now saved to {}", new_path.display())
+        } else {
+            format!("Labels now saved to {} for this session, but {} couldn't be updated - choose it again next time", new_path.display(), Self::get_config_path().display())
+        };
+    }
+    
+    fn show_unwritable_labels_window(&mut self, ctx: &egui::Context) {
+        let Some(error) = self.labels_save_failure.clone() else {
+            return;
+        };
+        
+        let path = self.param_labels_path.clone().unwrap_or_default();
+        let suggested = Self::writable_labels_location();
+        let mut open = true;
+        let mut move_to = None;
+        let mut choose = false;
+        let mut keep = false;
+        egui::Window::new("Labels Can't Be Saved")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("The labels file couldn't be written:");
+                ui.monospace(&path);
+                ui.colored_label(egui::Color32::LIGHT_RED, &error);
+                ui.add_space(5.0);
+                ui.label("Labels you add won't be kept after the editor closes. Save them to a folder you can write to and use that file from now on?");
+                ui.weak("The current file is left as it is.");
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if let Some(suggested) = &suggested {
+                        if ui.button("Use Config Folder").on_hover_text(suggested.display().to_string()).clicked() {
+                            move_to = Some(suggested.clone());
+                        }
+                    }
+                    choose = ui.button("Choose Location...").clicked();
+                    keep = ui.button("Keep Current File").on_hover_text("Don't ask again this session").clicked();
+                });
+            });
+        
+        if choose {
+            let mut dialog = FileDialog::new()
+                .add_filter("CSV files", &["csv"])
+                .set_title("Save ParamLabels.csv to")
+                .set_file_name("ParamLabels.csv");
+            if let Some(dir) = suggested.as_deref().and_then(Path::parent).filter(|dir| dir.is_dir()) {
+                dialog = dialog.set_directory(dir);
+            }
+            move_to = dialog.save_file();
+        }
+        if let Some(new_path) = move_to {
+            self.relocate_labels(&new_path);
+        } else if keep || !open {
+            self.keep_unwritable_labels = true;
+            self.labels_save_failure = None;
+        }
+    }
+    
+    /// Load the saved ParamLabels.csv path from the config file
+    fn load_saved_labels_path(&self) -> Option<String> {
+        let config_path = Self::get_config_path();
+        match std::fs::read_to_string(&config_path) {
+            Ok(content) => {
+                let path = content.trim().to_string();
+                if !path.is_empty() {
+                    Some(path)
+                } else {
+                    None
+                }
+            }
+            Err(_) => None, // Config file doesn't exist yet
+        }
+    }
+    
+    /// Returns true if the clipboard can be replaced. While a cut node is still waiting to be
+    /// pasted, opens the restore/discard prompt instead and returns false.
+    fn confirm_discard_cut(&mut self) -> bool {
+        if self.cut_origin.is_none() {
+            return true;
+        }
+        
+        self.show_cut_warning = true;
+        self.status_message = "The cut node hasn't been pasted yet - restore or discard it first".to_string();
+        false
+    }
+    
+    fn show_cut_warning_window(&mut self, ctx: &egui::Context) {
+        if !self.show_cut_warning {
+            return;
+        }
+        
+        let node_name = self.clipboard_data.as_ref().map(|n| n.name.clone()).unwrap_or_default();
+        let mut open = true;
+        let mut restore = false;
+        let mut discard = false;
+        
+        egui::Window::new("Unpasted Cut Node")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("The cut node '{}' hasn't been pasted and will be lost.", node_name));
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    restore = ui.button("Restore to Original Location").clicked();
+                    discard = ui.button("Discard").clicked();
+                });
+            });
+        
+        if restore {
+            self.restore_cut_node();
+        } else if discard {
+            self.cut_origin = None;
+            self.cut_mode = false;
+            self.status_message = format!("Discarded cut node '{}'", node_name);
+        }
+        
+        self.show_cut_warning = open && !restore && !discard;
+    }
+    
+    /// List labels added or changed by hand since the labels were loaded
+    fn show_session_labels_window(&mut self, ctx: &egui::Context) {
+        if !self.show_session_labels {
+            return;
+        }
+        
+        let mut open = true;
+        egui::Window::new("Labels Added This Session")
+            .default_size([500.0, 300.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let additions = self.param_file.hash_labels.session_additions();
+                if additions.is_empty() {
+                    ui.label("No labels have been added this session.");
+                    return;
+                }
+                
+                ui.label(format!("{} labels added or changed:", additions.len()));
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("session_labels_grid").striped(true).show(ui, |ui| {
+                        for (hash, label) in additions {
+                            ui.monospace(format!("0x{:010X}", hash));
+                            ui.label(label);
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        
+        self.show_session_labels = open;
+    }
+    
+    /// Report of structs grouped by field keys, with instance counts and sample paths
+    fn show_struct_layouts_window(&mut self, ctx: &egui::Context) {
+        if !self.show_struct_layouts {
+            return;
+        }
+        
+        // Rebuild only when the tree has changed since the last report
+        let revision = self.param_file.revision();
+        if self.struct_layouts.as_ref().map(|(r, _)| *r) != Some(revision) {
+            self.struct_layouts = Some((revision, self.param_file.struct_layouts(5)));
+        }
+        let Some((_, mut layouts)) = self.struct_layouts.take() else {
+            return;
+        };
+        
+        let (column, descending) = self.layout_sort;
+        layouts.sort_by(|a, b| {
+            let ordering = match column {
+                LayoutSort::Instances => a.count.cmp(&b.count),
+                LayoutSort::Fields => a.fields.len().cmp(&b.fields.len()),
+                LayoutSort::Savings => a.max_dedup_savings().cmp(&b.max_dedup_savings()),
+            };
+            if descending { ordering.reverse() } else { ordering }
+        });
+        
+        let mut open = true;
+        let mut sort_by = None;
+        let mut jump_to = None;
+        egui::Window::new("Struct Layouts")
+            .default_size([700.0, 450.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let struct_count: usize = layouts.iter().map(|l| l.count).sum();
+                let shared = layouts.iter().filter(|l| l.count > 1).count();
+                let savings: usize = layouts.iter().map(|l| l.max_dedup_savings()).sum();
+                ui.label(format!("{} structs, {} layouts ({} used more than once)", struct_count, layouts.len(), shared));
+                ui.label(format!("Reference table dedup can save up to {} bytes", savings))
+                    .on_hover_text("Duplicates only merge when their fields are in the same order with the same types");
+                ui.separator();
+                
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("struct_layouts_grid").striped(true).spacing([15.0, 4.0]).show(ui, |ui| {
+                        for (label, header_column) in [("Instances", LayoutSort::Instances), ("Fields", LayoutSort::Fields), ("Max Savings", LayoutSort::Savings)] {
+                            let arrow = match (column == header_column, descending) {
+                                (true, true) => " ⏷",
+                                (true, false) => " ⏶",
+                                _ => "",
+                            };
+                            if ui.button(format!("{}{}", label, arrow)).clicked() {
+                                sort_by = Some(header_column);
+                            }
+                        }
+                        ui.strong("Keys");
+                        ui.strong("Sample Paths");
+                        ui.end_row();
+                        
+                        for layout in &layouts {
+                            ui.label(layout.count.to_string());
+                            ui.label(layout.fields.len().to_string());
+                            ui.label(format!("{} B", layout.max_dedup_savings()));
+                            
+                            let keys: Vec<String> = layout.fields.iter()
+                                .map(|hash| self.param_file.hash_labels.hash_to_string(*hash))
+                                .collect();
+                            let keys = keys.join(", ");
+                            ui.label(truncate_chars(&keys, 60)).on_hover_text(keys);
+                            
+                            ui.horizontal(|ui| {
+                                for path in &layout.sample_paths {
+                                    if ui.small_button(path).clicked() {
+                                        jump_to = Some(path.clone());
+                                    }
+                                }
+                                if layout.count > layout.sample_paths.len() {
+                                    ui.label(format!("+{} more", layout.count - layout.sample_paths.len()));
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        
+        self.struct_layouts = Some((revision, layouts));
+        if let Some(new_column) = sort_by {
+            // Clicking the current column flips the direction; a new column starts descending
+            self.layout_sort = if new_column == column { (column, !descending) } else { (new_column, true) };
+        }
+        if let Some(path) = jump_to {
+            self.jump_to_path(&path);
+        }
+        self.show_struct_layouts = open;
+    }
+    
+    /// Add a path to the multi-selection, or remove it if it's already there
+    fn toggle_multi_selection(&mut self, path: &str) {
+        if let Some(position) = self.multi_selection.iter().position(|p| p == path) {
+            self.multi_selection.remove(position);
+        } else {
+            self.multi_selection.push(path.to_string());
+        }
+        self.status_message = format!("{} nodes in multi-selection (Ctrl+click to add or remove)", self.multi_selection.len());
+    }
+    
+    /// Add/subtract/multiply/set every numeric value in the multi-selection
+    fn show_apply_math_window(&mut self, ctx: &egui::Context) {
+        if !self.show_apply_math {
+            return;
+        }
+        
+        let numeric_count = self.multi_selection.iter()
+            .filter_map(|path| self.find_node_by_path(path))
+            .filter(|node| node.value.apply_math(MathOp::Add, 0.0).is_some())
+            .count();
+        let operand = self.math_operand.trim().parse::<f64>().ok().filter(|v| v.is_finite());
+        let mut open = true;
+        let mut apply = false;
+        let mut cancel = false;
+        
+        egui::Window::new("Apply Math to Selection")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} of {} selected nodes are numeric", numeric_count, self.multi_selection.len()));
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.math_op, MathOp::Add, "Add");
+                    ui.radio_value(&mut self.math_op, MathOp::Subtract, "Subtract");
+                    ui.radio_value(&mut self.math_op, MathOp::Multiply, "Multiply by");
+                    ui.radio_value(&mut self.math_op, MathOp::Set, "Set to");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Value:");
+                    ui.text_edit_singleline(&mut self.math_operand);
+                });
+                ui.label("Integers are rounded and clamped to their type's range. Multiply by 0.9 to reduce by 10%.");
+                if !self.math_operand.trim().is_empty() && operand.is_none() {
+                    ui.colored_label(egui::Color32::LIGHT_RED, "Enter a number");
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    apply = ui.add_enabled(operand.is_some() && numeric_count > 0, egui::Button::new("Apply")).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if let (true, Some(operand)) = (apply, operand) {
+            self.apply_math_to_selection(self.math_op, operand);
+        }
+        
+        self.show_apply_math = open && !apply && !cancel;
+    }
+    
+    /// Apply one operation to every numeric value in the multi-selection as a single undo step
+    fn apply_math_to_selection(&mut self, op: MathOp, operand: f64) {
+        let mut actions = Vec::new();
+        let mut skipped = 0;
+        let mut clamped_count = 0;
+        
+        for path in self.multi_selection.clone() {
+            let Some(old_value) = self.param_file.get_node_value(&path) else {
+                skipped += 1;
+                continue;
+            };
+            let Some((new_value, clamped)) = old_value.apply_math(op, operand) else {
+                skipped += 1;
+                continue;
+            };
+            if clamped {
+                clamped_count += 1;
+            }
+            if self.param_file.update_node_value(&path, new_value.clone()) {
+                actions.push(UndoAction::UpdateValue { path, old_value, new_value });
+            }
+        }
+        
+        let changed = actions.len();
+        if changed > 0 {
+            self.push_undo_action(UndoAction::Batch { actions });
+        }
+        
+        let mut message = format!("Updated {} values", changed);
+        if skipped > 0 {
+            message.push_str(&format!(" | {} non-numeric skipped", skipped));
+        }
+        if clamped_count > 0 {
+            message.push_str(&format!(" | {} clamped to their type's range", clamped_count));
+        }
+        self.status_message = message;
+    }
+    
+    /// Warning shown for as long as the open file contains unknown-type params
+    fn show_unknown_types_banner(&mut self, ui: &mut egui::Ui) {
+        if !self.param_file.had_unknown_types() || self.param_file.get_root().is_none() {
+            return;
+        }
+        
+        let count = self.param_file.unknown_types().len();
+        ui.horizontal(|ui| {
+            ui.colored_label(
+                egui::Color32::from_rgb(255, 140, 0),
+                format!("⚠ This file has {} params of unknown type. They are shown as placeholder UInts and saving will not preserve them.", count),
+            );
+            if ui.button("Show Details").clicked() {
+                self.show_unknown_types = true;
+            }
+        });
+        ui.separator();
+    }
+    
+    /// Every unknown type byte from the last open, with its offset in the file
+    fn show_unknown_types_window(&mut self, ctx: &egui::Context) {
+        if !self.show_unknown_types {
+            return;
+        }
+        
+        let mut open = true;
+        egui::Window::new("Unknown Param Types")
+            .default_size([400.0, 300.0])
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let unknown = self.param_file.unknown_types();
+                ui.label(format!("{} unknown-type params in {}:", unknown.len(), self.param_file.get_filename()));
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("unknown_types_grid").striped(true).show(ui, |ui| {
+                        ui.strong("Offset");
+                        ui.strong("Type Byte");
+                        ui.end_row();
+                        for (type_byte, offset) in unknown {
+                            ui.monospace(format!("0x{:08X}", offset));
+                            ui.monospace(type_byte.to_string());
+                            ui.end_row();
                         }
-                        new_fields.insert(*hash, field_value.clone());
-                        current_index += 1;
+                    });
+                });
+            });
+        
+        self.show_unknown_types = open;
+    }
+    
+    /// Confirm before replacing all labels with the canonical CSV
+    fn show_reset_labels_window(&mut self, ctx: &egui::Context) {
+        if !self.show_reset_labels_confirm {
+            return;
+        }
+        
+        let canonical = self.canonical_labels_path.clone().unwrap_or_default();
+        let session_count = self.param_file.hash_labels.session_additions().len();
+        let mut open = true;
+        let mut reset = false;
+        let mut review = false;
+        let mut cancel = false;
+        
+        egui::Window::new("Reset Labels to Canonical")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Replace all labels with {}?", canonical));
+                if let Some(path) = &self.param_labels_path {
+                    ui.label(format!("{} will be overwritten.", path));
+                }
+                ui.label(format!("{} labels added this session will be discarded.", session_count));
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    reset = ui.button("Reset").clicked();
+                    review = ui.add_enabled(session_count > 0, egui::Button::new("Review Session Labels")).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if reset {
+            self.reset_labels_to_canonical();
+        }
+        if review {
+            self.show_session_labels = true;
+        }
+        
+        self.show_reset_labels_confirm = open && !reset && !cancel;
+    }
+    
+    /// Confirm a rename onto a key another field in the same struct already uses
+    fn show_key_overwrite_window(&mut self, ctx: &egui::Context) {
+        let Some((path, new_name, new_hash)) = self.pending_key_overwrite.clone() else {
+            return;
+        };
+        let (Some(existing_index), Some(parent_path)) = (self.param_file.key_collision(&path, new_hash), self.get_parent_path(&path)) else {
+            // The tree changed underneath the prompt; nothing collides any more
+            self.pending_key_overwrite = None;
+            return;
+        };
+        
+        let existing_path = format!("{}[{}]", parent_path, existing_index);
+        let existing = self.find_node_by_path(&existing_path)
+            .map(|n| format!("{}: {}", n.get_type_name(), truncate_chars(&self.display_value(&existing_path, &n), 60)))
+            .unwrap_or_default();
+        let current_name = self.find_node_by_path(&path).map(|n| n.name).unwrap_or_default();
+        let mut open = true;
+        let mut overwrite = false;
+        let mut show_existing = false;
+        let mut cancel = false;
+        
+        egui::Window::new("Key Already Exists")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("This struct already has a field '{}' ({}).", new_name, existing));
+                ui.label(format!("Renaming '{}' to it will overwrite that field; its value is removed.", current_name));
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    overwrite = ui.button("Overwrite").clicked();
+                    show_existing = ui.button("Show Existing Field").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if overwrite {
+            self.overwrite_node_key_with_undo(&path, new_name, new_hash, existing_index);
+        } else if show_existing {
+            self.jump_to_path(&existing_path);
+        } else if cancel || !open {
+            self.status_message = "Rename cancelled".to_string();
+        }
+        
+        if overwrite || cancel || !open {
+            self.pending_key_overwrite = None;
+        }
+    }
+    
+    /// Ask for the key the wrapped node gets inside its new struct
+    fn show_wrap_in_struct_window(&mut self, ctx: &egui::Context) {
+        let Some((path, mut key)) = self.wrap_in_struct.clone() else {
+            return;
+        };
+        
+        let mut open = true;
+        let mut confirm = false;
+        let mut cancel = false;
+        egui::Window::new("Wrap in Struct")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("{} becomes the only field of a new struct in its place.", path));
+                ui.horizontal(|ui| {
+                    ui.label("Field key:");
+                    let response = ui.text_edit_singleline(&mut key);
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        confirm = true;
                     }
-                    
-                    // If target index is at the end
-                    if target_index >= s.fields.len() {
-                        new_fields.insert(node_to_restore.hash, node_to_restore.value);
+                });
+                ui.weak("A label, or a hash written as 0x...");
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    confirm |= ui.add_enabled(!key.trim().is_empty(), egui::Button::new("Wrap")).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        let key = key.trim().to_string();
+        if confirm && !key.is_empty() {
+            let hash = match key.strip_prefix("0x").map(|hex| u64::from_str_radix(hex, 16)) {
+                Some(Ok(hash)) => Some(hash),
+                Some(Err(_)) => None,
+                None => Some(self.param_file.hash_labels.add_label_and_save(&key, self.param_labels_path.as_deref())),
+            };
+            match hash {
+                Some(hash) => {
+                    self.wrap_node_with_undo(&path, Some(hash));
+                    self.wrap_in_struct = None;
+                }
+                None => self.status_message = format!("Invalid hash: {}", key),
+            }
+        } else if cancel || !open {
+            self.wrap_in_struct = None;
+        } else {
+            self.wrap_in_struct = Some((path, key));
+        }
+    }
+    
+    /// Resolve each note's label-path to a node path again if the tree, labels or notes changed
+    fn refresh_note_nodes(&mut self) {
+        let key = Some((self.param_file.revision(), self.param_file.label_revision(), self.notes.revision()));
+        if self.note_nodes_key == key {
+            return;
+        }
+        
+        self.note_nodes.clear();
+        for (label_path, _) in self.notes.iter() {
+            if let Some(PatchTarget::Existing(path)) = self.param_file.locate_label_path(label_path) {
+                self.note_nodes.insert(path, label_path.to_string());
+            }
+        }
+        self.note_nodes_key = key;
+    }
+    
+    /// Note on the node at `path`, as of the last `refresh_note_nodes`
+    fn note_at(&self, path: &str) -> Option<&str> {
+        self.notes.get(self.note_nodes.get(path)?)
+    }
+    
+    /// Replace the note on the node at `path`; blank text removes it
+    fn set_note(&mut self, path: &str, text: &str) {
+        self.refresh_note_nodes();
+        // The note may be stored under an older spelling of the label-path (0x... before a label was added)
+        let stored = self.note_nodes.get(path).cloned();
+        let Some(label_path) = stored.or_else(|| self.param_file.label_path_of(path)) else {
+            self.status_message = format!("Node not found: {}", path);
+            return;
+        };
+        
+        self.notes.set(&label_path, text);
+        self.save_notes();
+        self.status_message = if text.trim().is_empty() {
+            format!("Removed note from {}", path)
+        } else {
+            format!("Noted {}", path)
+        };
+    }
+    
+    fn show_note_editor_window(&mut self, ctx: &egui::Context) {
+        let Some((path, mut text)) = self.editing_note.clone() else {
+            return;
+        };
+        
+        let mut open = true;
+        let mut save = false;
+        let mut remove = false;
+        let mut cancel = false;
+        let had_note = self.note_at(&path).is_some();
+        egui::Window::new("Node Note")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let label_path = self.param_file.label_path_of(&path).unwrap_or_default();
+                ui.label(if label_path.is_empty() { "root".to_string() } else { label_path });
+                ui.add(egui::TextEdit::multiline(&mut text).hint_text("e.g. check this, TODO: verify value").desired_rows(3));
+                ui.weak("Kept with the session only, not in the param file");
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    save = ui.add_enabled(!text.trim().is_empty(), egui::Button::new("Save")).clicked();
+                    remove = ui.add_enabled(had_note, egui::Button::new("Remove")).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if save {
+            self.set_note(&path, &text);
+        } else if remove {
+            self.set_note(&path, "");
+        }
+        self.editing_note = if save || remove || cancel || !open { None } else { Some((path, text)) };
+    }
+    
+    /// Every note with its label-path; clicking one selects its node
+    fn show_notes_window(&mut self, ctx: &egui::Context) {
+        if !self.show_notes {
+            return;
+        }
+        self.refresh_note_nodes();
+        
+        // label-path -> node path for the notes that resolve in the open file
+        let resolved: HashMap<String, String> = self.note_nodes.iter()
+            .map(|(path, label_path)| (label_path.clone(), path.clone()))
+            .collect();
+        let notes: Vec<(String, String)> = self.notes.iter().map(|(label_path, text)| (label_path.to_string(), text.to_string())).collect();
+        
+        let mut open = true;
+        let mut jump = None;
+        let mut edit = None;
+        let mut remove = None;
+        egui::Window::new("Notes")
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                if notes.is_empty() {
+                    ui.weak("No notes yet - right-click a node and choose Add Note");
+                    return;
+                }
+                
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("notes_grid").num_columns(3).striped(true).spacing([10.0, 4.0]).show(ui, |ui| {
+                        for (label_path, text) in &notes {
+                            let shown = if label_path.is_empty() { "root" } else { label_path.as_str() };
+                            match resolved.get(label_path) {
+                                Some(path) => {
+                                    if ui.link(truncate_chars(shown, 40)).on_hover_text(shown).clicked() {
+                                        jump = Some(path.clone());
+                                    }
+                                }
+                                None => {
+                                    ui.weak(truncate_chars(shown, 40)).on_hover_text("Not found in the open file");
+                                }
+                            }
+                            ui.label(text);
+                            ui.horizontal(|ui| {
+                                if let Some(path) = resolved.get(label_path) {
+                                    if ui.small_button("✏").on_hover_text("Edit note").clicked() {
+                                        edit = Some((path.clone(), text.clone()));
+                                    }
+                                }
+                                if ui.small_button("🗑").on_hover_text("Remove note").clicked() {
+                                    remove = Some(label_path.clone());
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        
+        if let Some(path) = jump {
+            self.jump_to_path(&path);
+        }
+        if edit.is_some() {
+            self.editing_note = edit;
+        }
+        if let Some(label_path) = remove {
+            self.notes.set(&label_path, "");
+            self.save_notes();
+        }
+        self.show_notes = open;
+    }
+    
+    /// (index, hash) of the children of the container at `path` that can carry a label:
+    /// the field keys of a struct, or the hash40 items of a list
+    fn labelable_children(&self, path: &str) -> Vec<(usize, u64)> {
+        match self.find_node_by_path(path).map(|node| node.value) {
+            Some(ParamValue::Struct(s)) => s.fields.keys().copied().enumerate().collect(),
+            Some(ParamValue::List(l)) => l.values.iter().enumerate()
+                .filter_map(|(index, value)| match value {
+                    ParamValue::Hash(hash) => Some((index, *hash)),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+    
+    /// Numbered labels for the unlabeled children from `labelable_children`, each numbered by
+    /// its index at the digit width of already labeled `base_NN` siblings (at least two digits
+    /// and enough for the last index otherwise)
+    fn sequence_label_plan(&self, path: &str, base: &str) -> Vec<(usize, u64, String)> {
+        let labels = &self.param_file.hash_labels;
+        let children = self.labelable_children(path);
+        let child_count = self.find_node_by_path(path).map_or(0, |node| node.child_count());
+        
+        let prefix = format!("{}_", base);
+        let observed_width = children.iter()
+            .filter_map(|(_, hash)| labels.get_label(*hash)?.strip_prefix(&prefix).map(str::len))
+            .max();
+        let width = observed_width.unwrap_or_else(|| child_count.saturating_sub(1).to_string().len().max(2));
+        
+        let mut seen = HashSet::new();
+        children.into_iter()
+            .filter(|(_, hash)| !labels.has_exact_label(*hash) && seen.insert(*hash))
+            .map(|(index, hash)| (index, hash, format!("{}{:0width$}", prefix, index, width = width)))
+            .collect()
+    }
+    
+    /// Base name suggested for `sequence_label_plan`: the prefix of a labeled `name_NN` child, if any
+    fn sequence_label_base(&self, path: &str) -> String {
+        self.labelable_children(path).iter()
+            .filter_map(|(_, hash)| self.param_file.hash_labels.get_label(*hash))
+            .find_map(|label| {
+                let (base, number) = label.rsplit_once('_')?;
+                let numbered = !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+                numbered.then(|| base.to_string())
+            })
+            .unwrap_or_default()
+    }
+    
+    /// Preview and confirm numbered labels for a container's unlabeled children
+    fn show_sequence_labels_window(&mut self, ctx: &egui::Context) {
+        let Some((path, mut base)) = self.sequence_labels.clone() else {
+            return;
+        };
+        let plan = self.sequence_label_plan(&path, base.trim());
+        
+        let mut open = true;
+        let mut confirm = false;
+        let mut cancel = false;
+        egui::Window::new("Label Unlabeled Children")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Name the unlabeled children of {} base_NN by their index.", path));
+                ui.horizontal(|ui| {
+                    ui.label("Base name:");
+                    ui.text_edit_singleline(&mut base);
+                });
+                ui.weak("Only use this when the children really are a numbered sequence.");
+                ui.add_space(5.0);
+                
+                let matching = plan.iter().filter(|(_, hash, label)| self.param_file.hash_labels.string_to_hash40(label) == *hash).count();
+                egui::ScrollArea::vertical().max_height(250.0).show(ui, |ui| {
+                    egui::Grid::new("sequence_labels_preview").striped(true).show(ui, |ui| {
+                        for (index, hash, label) in &plan {
+                            ui.label(format!("[{}]", index));
+                            ui.monospace(format!("0x{:X}", hash));
+                            ui.monospace(label);
+                            if self.param_file.hash_labels.string_to_hash40(label) != *hash {
+                                ui.colored_label(egui::Color32::from_rgb(255, 140, 0), "⚠")
+                                    .on_hover_text("The label doesn't hash to this value; it will be stored as an override");
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+                ui.label(format!("{} labels, {} of them hash to their value", plan.len(), matching));
+                
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    confirm = ui.add_enabled(!plan.is_empty() && !base.trim().is_empty(), egui::Button::new("Add Labels")).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if confirm {
+            self.apply_sequence_labels(&plan);
+            self.sequence_labels = None;
+        } else if cancel || !open {
+            self.sequence_labels = None;
+        } else {
+            self.sequence_labels = Some((path, base));
+        }
+    }
+    
+    /// Empty a struct or list as one undo step that keeps the removed children for restoring
+    fn clear_children_with_undo(&mut self, path: &str) -> bool {
+        let (count, empty) = match self.param_file.get_node_value(path) {
+            Some(ParamValue::Struct(s)) => (s.fields.len(), ParamValue::Struct(ParamStruct { type_hash: s.type_hash, fields: indexmap::IndexMap::new() })),
+            Some(ParamValue::List(l)) => (l.values.len(), ParamValue::List(ParamList { values: Vec::new() })),
+            _ => return false,
+        };
+        if !self.update_node_value_with_undo(path, empty) {
+            self.status_message = format!("Failed to clear {}", path);
+            return false;
+        }
+        
+        // Nothing is left under the container: drop view state for its old children
+        let inside = |p: &String| p != path && is_same_or_descendant(p, path);
+        self.expanded_nodes.retain(|p| !inside(p));
+        self.multi_selection.retain(|p| !inside(p));
+        self.list_display_keys.retain(|p, _| !inside(p));
+        if self.selected_node.as_ref().is_some_and(inside) {
+            self.select_path(path);
+        }
+        if self.pinned_details.as_ref().is_some_and(inside) {
+            self.pinned_details = None;
+        }
+        self.build_tree_items();
+        self.status_message = format!("Removed {} children from {} (Ctrl+Z to restore)", count, path);
+        true
+    }
+    
+    /// Hash40 values with no exact label whose masked variant has one, as (path, stored hash,
+    /// labelled variant) in tree order
+    fn normalize_hashes_plan(&self) -> Vec<(String, u64, u64)> {
+        let hash_labels = &self.param_file.hash_labels;
+        let mut plan = Vec::new();
+        self.param_file.walk_leaves(|path, _, value| {
+            let ParamValue::Hash(hash) = *value else {
+                return;
+            };
+            if hash_labels.has_exact_label(hash) {
+                return;
+            }
+            if let Some((variant, _)) = hash_labels.masked_label(hash) {
+                plan.push((path.to_string(), hash, variant));
+            }
+        });
+        plan
+    }
+    
+    /// Find hash40 values to normalize and ask before rewriting them
+    fn open_normalize_hashes(&mut self) {
+        let plan = self.normalize_hashes_plan();
+        if plan.is_empty() {
+            self.status_message = "Nothing to normalize: every hash40 value has an exact label or none at all".to_string();
+        } else {
+            self.normalize_hashes = Some(plan);
+        }
+    }
+    
+    /// Rewrite the planned hash40 values as one undo step
+    fn apply_normalize_hashes(&mut self, plan: &[(String, u64, u64)]) {
+        let mut actions = Vec::new();
+        for (path, old_hash, new_hash) in plan {
+            // The tree may have changed while the window was open
+            if self.param_file.get_node_value(path) != Some(ParamValue::Hash(*old_hash)) {
+                continue;
+            }
+            let new_value = ParamValue::Hash(*new_hash);
+            if self.param_file.update_node_value(path, new_value.clone()) {
+                actions.push(UndoAction::UpdateValue { path: path.clone(), old_value: ParamValue::Hash(*old_hash), new_value });
+            }
+        }
+        
+        let changed = actions.len();
+        if changed > 0 {
+            self.push_undo_action(UndoAction::Batch { actions });
+        }
+        self.status_message = match plan.len() - changed {
+            0 => format!("Normalized {} hash40 values (Ctrl+Z to revert)", changed),
+            skipped => format!("Normalized {} hash40 values (Ctrl+Z to revert) | {} changed since the preview and were skipped", changed, skipped),
+        };
+    }
+    
+    /// List the hash40 values that normalizing would rewrite and confirm it
+    fn show_normalize_hashes_window(&mut self, ctx: &egui::Context) {
+        let Some(plan) = self.normalize_hashes.take() else {
+            return;
+        };
+        
+        let mut open = true;
+        let mut confirm = false;
+        let mut cancel = false;
+        egui::Window::new("Normalize Hash Values")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("{} hash40 values are only named through a masked variant of their hash.", plan.len()));
+                ui.label("Normalizing stores the variant that has the label instead. This changes the file's data.");
+                ui.add_space(5.0);
+                
+                egui::ScrollArea::vertical().max_height(250.0).show(ui, |ui| {
+                    egui::Grid::new("normalize_hashes_preview").striped(true).show(ui, |ui| {
+                        for (path, old_hash, new_hash) in &plan {
+                            ui.monospace(path);
+                            ui.monospace(format!("0x{:X} → 0x{:X}", old_hash, new_hash));
+                            ui.label(self.param_file.hash_labels.hash_to_string(*new_hash));
+                            ui.end_row();
+                        }
+                    });
+                });
+                
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    confirm = ui.button(format!("Rewrite {} Values", plan.len())).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if confirm {
+            self.apply_normalize_hashes(&plan);
+        } else if !cancel && open {
+            self.normalize_hashes = Some(plan);
+        }
+    }
+    
+    /// Clear the container at `path`, asking first when it has more than `CONFIRM_CLEAR_CHILDREN` children
+    fn request_clear_children(&mut self, path: &str, child_count: usize) {
+        if child_count > CONFIRM_CLEAR_CHILDREN {
+            self.clear_children = Some(path.to_string());
+        } else {
+            self.clear_children_with_undo(path);
+        }
+    }
+    
+    /// Confirm clearing a container with more than `CONFIRM_CLEAR_CHILDREN` children
+    fn show_clear_children_window(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.clear_children.clone() else {
+            return;
+        };
+        let Some(count) = self.find_node_by_path(&path).map(|node| node.child_count()) else {
+            self.clear_children = None;
+            return;
+        };
+        
+        let mut open = true;
+        let mut confirm = false;
+        let mut cancel = false;
+        egui::Window::new("Clear Children")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Remove all {} children of {}?", count, path));
+                ui.weak("Ctrl+Z brings them back.");
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    confirm = ui.button("Clear").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if confirm {
+            self.clear_children_with_undo(&path);
+        }
+        if confirm || cancel || !open {
+            self.clear_children = None;
+        }
+    }
+    
+    /// Ask how many copies of a list item to append and which numeric field, if any, counts up
+    fn show_duplicate_item_window(&mut self, ctx: &egui::Context) {
+        let Some((path, mut count_text, mut numbered_field)) = self.duplicate_item.clone() else {
+            return;
+        };
+        let Some(item) = self.find_node_by_path(&path).map(|n| n.to_owned_node()) else {
+            self.duplicate_item = None;
+            return;
+        };
+        let numeric_fields: Vec<(u64, String)> = match &item.value {
+            ParamValue::Struct(s) => s.fields.iter()
+                .filter(|(_, value)| value.as_f64().is_some())
+                .map(|(hash, _)| (*hash, self.param_file.hash_labels.hash_to_string(*hash)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let count = count_text.trim().parse::<usize>().ok().filter(|count| (1..=MAX_DUPLICATE_COUNT).contains(count));
+        
+        let mut open = true;
+        let mut confirm = false;
+        let mut cancel = false;
+        egui::Window::new("Duplicate List Item")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Append copies of {} to the end of its list.", path));
+                ui.horizontal(|ui| {
+                    ui.label("Copies:");
+                    let response = ui.add(egui::TextEdit::singleline(&mut count_text).desired_width(60.0));
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        confirm = true;
                     }
-                    
-                    s.fields = new_fields;
-                    return true;
+                });
+                if count.is_none() {
+                    ui.colored_label(egui::Color32::from_rgb(255, 140, 0), format!("Enter a number from 1 to {}", MAX_DUPLICATE_COUNT));
                 }
-                ParamValue::List(ref mut l) => {
-                    if target_index <= l.values.len() {
-                        l.values.insert(target_index, node_to_restore.value);
-                        return true;
-                    }
+                
+                if !numeric_fields.is_empty() {
+                    let selected = numbered_field
+                        .and_then(|hash| numeric_fields.iter().find(|(field, _)| *field == hash))
+                        .map_or("None", |(_, name)| name.as_str());
+                    ui.horizontal(|ui| {
+                        ui.label("Count up field:");
+                        egui::ComboBox::from_id_source("duplicate_numbered_field")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut numbered_field, None, "None");
+                                for (hash, name) in &numeric_fields {
+                                    ui.selectable_value(&mut numbered_field, Some(*hash), name);
+                                }
+                            });
+                    });
+                    ui.weak("Copy N gets the original value plus N in this field.");
                 }
-                _ => return false,
-            }
+                
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    confirm |= ui.add_enabled(count.is_some(), egui::Button::new("Duplicate")).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if let (true, Some(count)) = (confirm, count) {
+            self.duplicate_list_item(&path, count, numbered_field);
+            self.duplicate_item = None;
+        } else if cancel || !open {
+            self.duplicate_item = None;
+        } else {
+            self.duplicate_item = Some((path, count_text, numbered_field));
         }
+    }
+    
+    /// Append `count` copies of the list item at `path` to its list as one undo step. With
+    /// `numbered_field`, copy N has that numeric field of the original plus N.
+    fn duplicate_list_item(&mut self, path: &str, count: usize, numbered_field: Option<u64>) -> bool {
+        let (Some(list_path), Some(item)) = (self.get_parent_path(path), self.find_node_by_path(path).map(|n| n.to_owned_node())) else {
+            return false;
+        };
+        let Some(ParamValue::List(mut list)) = self.param_file.get_node_value(&list_path) else {
+            return false;
+        };
         
-        // Continue recursing
-        let current_index = indices[depth];
-        match value {
-            ParamValue::Struct(ref mut s) => {
-                if let Some((_, field_value)) = s.fields.get_index_mut(current_index) {
-                    return Self::restore_to_param_value(field_value, indices, node_to_restore, target_index, depth + 1);
-                }
-            }
-            ParamValue::List(ref mut l) => {
-                if current_index < l.values.len() {
-                    return Self::restore_to_param_value(&mut l.values[current_index], indices, node_to_restore, target_index, depth + 1);
+        for n in 1..=count {
+            let mut copy = item.value.clone();
+            if let (Some(hash), ParamValue::Struct(s)) = (numbered_field, &mut copy) {
+                if let Some(field) = s.fields.get_mut(&hash) {
+                    if let Some((numbered, _)) = field.apply_math(MathOp::Add, n as f64) {
+                        *field = numbered;
+                    }
                 }
             }
-            _ => {}
+            list.values.push(copy);
         }
         
-        false
+        // The whole list before and after is one value change, which undo and redo both handle
+        if count == 0 || !self.update_node_value_with_undo(&list_path, ParamValue::List(list)) {
+            self.status_message = format!("Failed to duplicate {}", path);
+            return false;
+        }
+        self.expanded_nodes.insert(list_path.clone());
+        self.build_tree_items();
+        self.status_message = format!("Appended {} copies of {} to {} (Ctrl+Z to undo)", count, path, list_path);
+        true
     }
-
-    /// Update a node's value with undo tracking
-    fn update_node_value_with_undo(&mut self, path: &str, new_value: ParamValue) -> bool {
-        // Get the old value for undo
-        if let Some(old_value) = self.param_file.get_node_value(path) {
-            if self.param_file.update_node_value(path, new_value.clone()) {
-                // Record undo action
-                self.push_undo_action(UndoAction::UpdateValue {
-                    path: path.to_string(),
-                    old_value,
-                    new_value,
-                });
-                return true;
+    
+    /// Add the planned labels as one undo step, saving the labels CSV once
+    fn apply_sequence_labels(&mut self, plan: &[(usize, u64, String)]) {
+        let mut actions = Vec::new();
+        for (_, hash, label) in plan {
+            let old = self.param_file.hash_labels.label_state(*hash);
+            self.param_file.hash_labels.add_label_for_hash(*hash, label);
+            let new = self.param_file.hash_labels.label_state(*hash);
+            actions.push(UndoAction::Label { hash: *hash, old, new });
+        }
+        self.param_file.refresh_names_only();
+        self.push_undo_action(UndoAction::Batch { actions });
+        self.build_tree_items();
+        
+        self.status_message = format!("Added {} sequence labels", plan.len());
+        if let Some(path) = &self.param_labels_path {
+            if let Err(e) = self.param_file.hash_labels.save_to_csv(path) {
+                self.status_message.push_str(&format!(" but failed to save labels: {}", e));
             }
         }
-        false
     }
     
-    /// Update a node's key with undo tracking
-    fn update_node_key_with_undo(&mut self, path: &str, new_name: String, new_hash: u64) -> bool {
-        // Get the old key for undo
-        if let Some(node) = self.find_node_by_path(path) {
-            let old_name = node.name.clone();
-            let old_hash = node.hash;
-            
-            if self.param_file.update_node_key(path, new_name.clone(), new_hash) {
-                // Record undo action
-                self.push_undo_action(UndoAction::UpdateKey {
-                    path: path.to_string(),
-                    old_name,
-                    old_hash,
-                    new_name,
-                    new_hash,
-                });
-                return true;
+    /// Replace the node at `path` with a new container holding it as the only child: a struct
+    /// keyed by `struct_key`, or a list when that's None. The node's own slot (its key, or its
+    /// list index) is unchanged. One undo step.
+    fn wrap_node_with_undo(&mut self, path: &str, struct_key: Option<u64>) -> bool {
+        let Some(value) = self.param_file.get_node_value(path) else {
+            return false;
+        };
+        let (wrapped, kind) = match struct_key {
+            Some(hash) => (ParamValue::Struct(ParamStruct { type_hash: 0, fields: [(hash, value)].into_iter().collect() }), "struct"),
+            None if path == "root" => {
+                self.status_message = "The root must stay a struct".to_string();
+                return false;
             }
+            None => (ParamValue::List(ParamList { values: vec![value] }), "list"),
+        };
+        
+        if !self.update_node_value_with_undo(path, wrapped) {
+            self.status_message = format!("Failed to wrap {}", path);
+            return false;
         }
-        false
+        
+        // Paths under the node gained a level; drop their stale expansion state
+        self.expanded_nodes.retain(|expanded| expanded == path || !is_same_or_descendant(expanded, path));
+        self.multi_selection.retain(|selected| !is_same_or_descendant(selected, path));
+        self.expanded_nodes.insert(path.to_string());
+        self.select_path(&format!("{}[0]", path));
+        self.status_message = format!("Wrapped {} in a new {}", path, kind);
+        true
     }
     
-    /// Add a node with undo tracking
-    fn add_node_with_undo(&mut self, target_path: &str, node_to_add: ParamNode) -> bool {
-        let target_indices = match self.param_file.parse_node_path(target_path) {
-            Some(indices) => indices,
-            None => return false,
+    /// Rename a key onto a sibling's key, removing the sibling first. Both steps undo together.
+    fn overwrite_node_key_with_undo(&mut self, path: &str, new_name: String, new_hash: u64, existing_index: usize) -> bool {
+        let (Some(parent_path), Some(own_index)) = (self.get_parent_path(path), self.get_node_index_in_parent(path)) else {
+            return false;
+        };
+        let Some(old_hash) = self.find_node_by_path(path).map(|n| n.hash) else {
+            return false;
         };
+        let old_name = self.param_file.hash_labels.hash_to_string(old_hash);
         
-        // Get the current size to calculate the new index
-        let new_index = if let Some(target_node) = self.find_node_by_path(target_path) {
-            match &target_node.value {
-                ParamValue::Struct(s) => s.fields.len(),
-                ParamValue::List(l) => l.values.len(),
-                _ => return false,
-            }
-        } else {
+        // delete_node records its own undo step; take it back to batch it with the rename
+        if !self.delete_node(&format!("{}[{}]", parent_path, existing_index)) {
+            self.status_message = "Failed to remove the existing field".to_string();
+            return false;
+        }
+        let Some(delete_action) = self.undo_stack.pop() else {
             return false;
         };
         
-        // Add to the underlying data structure
-        if let Some(root) = &mut self.param_file.root {
-            if Self::add_to_param_value(&mut root.value, &target_indices, node_to_add.clone(), 0) {
-                // Calculate the path where the node was added
-                let added_path = format!("{}[{}]", target_path, new_index);
-                
-                // Record undo action
-                self.push_undo_action(UndoAction::AddNode {
-                    path: added_path,
-                });
-                
-                // Rebuild the display tree to show the new node
-                self.param_file.rebuild_tree_with_labels();
-                return true;
-            }
+        // Removing an earlier sibling shifts the renamed field up by one
+        let renamed_index = if existing_index < own_index { own_index - 1 } else { own_index };
+        let renamed_path = format!("{}[{}]", parent_path, renamed_index);
+        
+        if !self.param_file.update_node_key(&renamed_path, new_name.clone(), new_hash) {
+            self.push_undo_action(delete_action);
+            self.status_message = "Failed to update key".to_string();
+            return false;
         }
         
-        false
+        self.push_undo_action(UndoAction::Batch {
+            actions: vec![
+                delete_action,
+                UndoAction::UpdateKey {
+                    path: renamed_path.clone(),
+                    old_name,
+                    old_hash,
+                    new_name: new_name.clone(),
+                    new_hash,
+                },
+            ],
+        });
+        
+        self.note_recent_edit(&renamed_path);
+        self.jump_to_path(&renamed_path);
+        self.status_message = format!("Key renamed to '{}', overwriting the existing field (Ctrl+Z to undo)", new_name);
+        true
     }
-
-    /// Generate a sequential name for a new node to avoid duplicates
-    fn generate_sequential_name(&self, parent_path: &str, _base_name: &str) -> String {
-        // Get the parent node to check existing children
-        if let Some(parent_node) = self.find_node_by_path(parent_path) {
-            // Find the highest numeric name among all children
-            let mut max_number = 0;
-            
-            for child in &parent_node.children {
-                // Try to parse the child name as a number
-                if let Ok(number) = child.name.parse::<u32>() {
-                    max_number = max_number.max(number);
-                }
-                
-                // Also try to extract numbers from names like "[18]" 
-                let trimmed = child.name.trim_start_matches('[').trim_end_matches(']');
-                if let Ok(number) = trimmed.parse::<u32>() {
-                    max_number = max_number.max(number);
-                }
-            }
-            
-            // Return the next sequential number with brackets
-            format!("[{}]", max_number + 1)
+    
+    /// Put an unpasted cut node back where it was cut from
+    fn restore_cut_node(&mut self) {
+        let (Some((parent_path, index)), Some(node)) = (self.cut_origin.clone(), self.clipboard_data.clone()) else {
+            return;
+        };
+        
+        if self.restore_node_at_index(&parent_path, node, index) {
+            let restored_path = format!("{}[{}]", parent_path, index);
+            self.clipboard = None;
+            self.clipboard_data = None;
+            self.cut_origin = None;
+            self.cut_mode = false;
+            self.build_tree_items();
+            self.status_message = format!("Restored cut node to {}", restored_path);
         } else {
-            // Fallback if we can't find the parent
-            "[1]".to_string()
+            self.status_message = format!("Could not restore cut node - {} no longer exists", parent_path);
         }
     }
     
-    /// Ensure a name is unique by adding _copy suffix if needed
-    fn ensure_unique_name(&self, node_path: &str, desired_name: &str) -> String {
-        // Get the parent path to check for siblings
-        if let Some(parent_path) = self.get_parent_path(node_path) {
-            if let Some(parent_node) = self.find_node_by_path(&parent_path) {
-                // Check if the desired name conflicts with any sibling (excluding self)
-                let current_node_index = self.get_node_index_in_parent(node_path);
+    /// Status bar entry with the opened file's full path, shortened from the front; click it to
+    /// open the containing folder. A tree that wasn't opened from disk shows as untitled.
+    fn show_opened_path(&mut self, ui: &mut egui::Ui) {
+        if self.param_file.get_root().is_none() {
+            return;
+        }
+        
+        let Some((path, _)) = &self.opened_file else {
+            ui.label("File: untitled").on_hover_text("Not opened from or saved to a file yet");
+            return;
+        };
+        let full = path.display().to_string();
+        let dir = path.parent().map(Path::to_path_buf);
+        let response = ui.add(
+            egui::Label::new(format!("File: {}", truncate_chars_start(&full, MAX_STATUS_PATH_CHARS)))
+                .sense(egui::Sense::click())
+        );
+        if response.on_hover_text(format!("{}\nClick to open the containing folder", full)).clicked() {
+            self.open_folder("containing", dir);
+        }
+    }
+    
+    /// Open `dir` in the file manager, reporting a missing folder or launch failure in the status bar
+    fn open_folder(&mut self, what: &str, dir: Option<PathBuf>) {
+        // A bare file name (e.g. a labels path relative to the working directory) has an empty parent
+        let dir = dir.map(|dir| if dir.as_os_str().is_empty() { PathBuf::from(".") } else { dir });
+        self.status_message = match dir {
+            Some(dir) if dir.is_dir() => match open_in_file_manager(&dir) {
+                Ok(()) => format!("Opened {} folder: {}", what, dir.display()),
+                Err(e) => format!("Couldn't open {} folder {}: {}", what, dir.display(), e),
+            },
+            Some(dir) => format!("The {} folder doesn't exist: {}", what, dir.display()),
+            None => format!("No {} folder to open", what),
+        };
+    }
+    
+    fn get_preferences_path() -> std::path::PathBuf {
+        let mut prefs_path = Self::get_config_path();
+        prefs_path.set_file_name("prc_editor_prefs.txt");
+        prefs_path
+    }
+    
+    fn get_notes_path() -> std::path::PathBuf {
+        let mut notes_path = Self::get_config_path();
+        notes_path.set_file_name(NOTES_FILE_NAME);
+        notes_path
+    }
+    
+    /// Notes are kept under the full path the file was opened from
+    fn notes_file_key(&self) -> Option<String> {
+        self.opened_file.as_ref().map(|(path, _)| path.display().to_string())
+    }
+    
+    /// Start the opened file's notes: the ones saved for it when keeping notes, else none
+    fn load_notes(&mut self) {
+        self.notes = NodeNotes::new();
+        self.editing_note = None;
+        let Some(file) = self.notes_file_key().filter(|_| self.persist_notes) else {
+            return;
+        };
+        let Ok(content) = std::fs::read_to_string(Self::get_notes_path()) else {
+            return; // No notes saved yet
+        };
+        match NodeNotes::load_for_file(&content, &file) {
+            Ok(notes) => self.notes = notes,
+            Err(e) => self.status_message = format!("Error reading notes from {}: {}", NOTES_FILE_NAME, e),
+        }
+    }
+    
+    /// Write the opened file's notes when keeping notes, leaving other files' notes in place
+    fn save_notes(&mut self) {
+        let Some(file) = self.notes_file_key().filter(|_| self.persist_notes) else {
+            return;
+        };
+        let path = Self::get_notes_path();
+        let existing = std::fs::read_to_string(&path).unwrap_or_default();
+        let result = self.notes.replace_in_csv(&existing, &file)
+            .and_then(|content| Ok(std::fs::write(&path, content)?));
+        if let Err(e) = result {
+            self.status_message = format!("Error saving notes to {}: {}", path.display(), e);
+        }
+    }
+    
+    /// Every persisted setting in one place. Changes apply and are saved immediately.
+    fn show_preferences_window(&mut self, ctx: &egui::Context) {
+        if !self.show_preferences {
+            return;
+        }
+        
+        let mut open = true;
+        let mut changed = false;
+        egui::Window::new("Preferences")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading("Tree");
+                changed |= ui.checkbox(&mut self.show_tree_values, "Show values in tree").changed();
+                changed |= ui.checkbox(&mut self.fold_single_child, "Fold single-child chains").changed();
+                changed |= ui.checkbox(&mut self.auto_expand_on_select, "Auto-expand on select").changed();
+                changed |= ui.checkbox(&mut self.highlight_unlabeled, "Highlight unlabeled hashes").changed();
+                changed |= ui.checkbox(&mut self.highlight_editing, "Highlight node being edited").changed();
+                changed |= ui.checkbox(&mut self.group_struct_fields, "Group struct fields into sections")
+                    .on_hover_text("By a leading [Category] in the field's description, else by the label before the first _")
+                    .changed();
+                changed |= ui.checkbox(&mut self.case_sensitive_search, "Case-sensitive search").changed();
                 
-                let name_conflicts = parent_node.children.iter().enumerate().any(|(i, child)| {
-                    // Don't compare with self
-                    if let Some(current_index) = current_node_index {
-                        if i == current_index {
-                            return false;
-                        }
+                ui.separator();
+                ui.heading("Values");
+                ui.horizontal(|ui| {
+                    ui.label("Integers:");
+                    changed |= ui.radio_value(&mut self.int_display, IntDisplay::Native, "As Stored").changed();
+                    changed |= ui.radio_value(&mut self.int_display, IntDisplay::FlippedSign, "Opposite Signedness").changed();
+                    changed |= ui.radio_value(&mut self.int_display, IntDisplay::Hex, "Hex").changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Floats:");
+                    let mut exact = self.float_digits.is_none();
+                    if ui.checkbox(&mut exact, "Exact").changed() {
+                        self.float_digits = if exact { None } else { Some(6) };
+                        changed = true;
+                    }
+                    if let Some(digits) = &mut self.float_digits {
+                        changed |= ui.add(egui::DragValue::new(digits).range(1..=9).suffix(" significant digits")).changed();
                     }
-                    child.name == desired_name
                 });
+                changed |= ui.checkbox(&mut self.flag_special_floats, "Flag NaN/Inf floats").changed();
                 
-                if name_conflicts {
-                    // Add _# suffix starting from _2
-                    let mut copy_counter = 2;
-                    loop {
-                        let copy_name = format!("{}_{}", desired_name, copy_counter);
-                        
-                        let copy_exists = parent_node.children.iter().enumerate().any(|(i, child)| {
-                            // Don't compare with self
-                            if let Some(current_index) = current_node_index {
-                                if i == current_index {
-                                    return false;
-                                }
-                            }
-                            child.name == copy_name
-                        });
-                        
-                        if !copy_exists {
-                            return copy_name;
-                        }
-                        
-                        copy_counter += 1;
-                        if copy_counter > 100 {
-                            break; // Prevent infinite loop
-                        }
+                ui.separator();
+                ui.heading("Saving");
+                changed |= ui.checkbox(&mut self.verify_after_save, "Verify after save").changed();
+                changed |= ui.checkbox(&mut self.write_xml_sidecar, "Write XML sidecar").changed();
+                if ui.checkbox(&mut self.param_file.keep_trailing_bytes, "Keep trailing padding")
+                    .on_hover_text("Write back alignment padding or other bytes that followed the param data in the opened file")
+                    .changed()
+                {
+                    // The status bar's round-trip verdict depends on it
+                    self.save_ordering_cache = None;
+                    changed = true;
+                }
+                if ui.checkbox(&mut self.persist_notes, "Keep notes between sessions")
+                    .on_hover_text(format!("Save node notes per file in {} next to the preferences", NOTES_FILE_NAME))
+                    .changed()
+                {
+                    self.save_notes();
+                    changed = true;
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Undo history:");
+                    if ui.add(egui::DragValue::new(&mut self.undo_limit).range(1..=MAX_UNDO_LIMIT).suffix(" steps")).changed() {
+                        self.trim_undo_stack();
+                        changed = true;
+                    }
+                });
+                
+                ui.separator();
+                ui.heading("Labels");
+                ui.horizontal(|ui| {
+                    ui.label("CSV sort order:");
+                    let sort_order = &mut self.param_file.hash_labels.csv_sort_order;
+                    changed |= ui.radio_value(sort_order, LabelSortOrder::Hash, "Hash").changed();
+                    changed |= ui.radio_value(sort_order, LabelSortOrder::Label, "Label").changed();
+                    changed |= ui.radio_value(sort_order, LabelSortOrder::Insertion, "Insertion").changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Canonical labels:");
+                    match &self.canonical_labels_path {
+                        Some(path) => ui.monospace(path),
+                        None => ui.weak("(not set)"),
+                    };
+                });
+                
+                ui.separator();
+                ui.heading("Advanced");
+                changed |= ui.checkbox(&mut self.raw_byte_editing, "Raw byte editing")
+                    .on_hover_text("Show a leaf's type byte and payload in the details panel and allow editing them directly")
+                    .changed();
+                
+                ui.separator();
+                changed |= ui.checkbox(&mut self.compact_mode, "Compact mode").on_hover_text("Hide the shortcuts overlay and status bar extras").changed();
+                changed |= ui.checkbox(&mut self.compact_density, "Compact density").on_hover_text("Fit more rows on screen in the tree and editor grids").changed();
+                ui.horizontal(|ui| {
+                    let conflicts = self.shortcuts.conflict_count();
+                    if ui.button("Keyboard Shortcuts...").on_hover_text("View and change key bindings").clicked() {
+                        self.show_shortcuts = true;
                     }
-                }
-            }
-        }
+                    if conflicts > 0 {
+                        ui.colored_label(egui::Color32::from_rgb(255, 140, 0), format!("⚠ {} conflicting", conflicts));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.weak(format!("Saved to {}", Self::get_preferences_path().display()));
+                    if ui.small_button("Open Folder").clicked() {
+                        self.open_folder("config", Self::get_config_path().parent().map(Path::to_path_buf));
+                    }
+                });
+            });
         
-        // No conflict or couldn't check, return original name
-        desired_name.to_string()
+        if changed {
+            self.save_preferences();
+        }
+        self.show_preferences = open;
     }
     
-    /// Generate a name for pasting, preserving original names when possible
-    fn generate_paste_name(&self, parent_path: &str, original_name: &str) -> String {
-        // If the original name is not a number or bracketed number, try to preserve it
-        let is_numeric = original_name.parse::<u32>().is_ok();
-        let is_bracketed_numeric = {
-            let trimmed = original_name.trim_start_matches('[').trim_end_matches(']');
-            original_name.starts_with('[') && original_name.ends_with(']') && trimmed.parse::<u32>().is_ok()
+    /// Load `key=value` preferences saved next to the executable
+    fn load_preferences(&mut self) {
+        let Ok(content) = std::fs::read_to_string(Self::get_preferences_path()) else {
+            return; // Preferences file doesn't exist yet
         };
         
-        if !is_numeric && !is_bracketed_numeric {
-            // Check if this name already exists in the parent
-            if let Some(parent_node) = self.find_node_by_path(parent_path) {
-                let name_exists = parent_node.children.iter().any(|child| child.name == original_name);
-                if !name_exists {
-                    // Original name doesn't exist, we can use it as-is
-                    return original_name.to_string();
+        for line in content.lines() {
+            // Unknown keys (e.g. from other versions) are ignored
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            let enabled = value == "true";
+            match key.trim() {
+                "compact_mode" => self.compact_mode = enabled,
+                "compact_density" => self.compact_density = enabled,
+                "group_struct_fields" => self.group_struct_fields = enabled,
+                "show_tree_values" => self.show_tree_values = enabled,
+                "fold_single_child" => self.fold_single_child = enabled,
+                "flag_special_floats" => self.flag_special_floats = enabled,
+                "verify_after_save" => self.verify_after_save = enabled,
+                "keep_trailing_bytes" => self.param_file.keep_trailing_bytes = enabled,
+                "persist_notes" => self.persist_notes = enabled,
+                "write_xml_sidecar" => self.write_xml_sidecar = enabled,
+                "case_sensitive_search" => self.case_sensitive_search = enabled,
+                "float_digits" => self.float_digits = value.parse().ok().filter(|digits| *digits > 0),
+                "auto_expand_on_select" => self.auto_expand_on_select = enabled,
+                "highlight_unlabeled" => self.highlight_unlabeled = enabled,
+                "highlight_editing" => self.highlight_editing = enabled,
+                "raw_byte_editing" => self.raw_byte_editing = enabled,
+                "undo_limit" => {
+                    if let Ok(limit) = value.parse::<usize>() {
+                        self.undo_limit = limit.clamp(1, MAX_UNDO_LIMIT);
+                    }
                 }
-                
-                // If it exists, try adding _# suffix for text names starting from _2
-                let mut copy_counter = 2;
-                loop {
-                    let copy_name = format!("{}_{}", original_name, copy_counter);
-                    
-                    let copy_exists = parent_node.children.iter().any(|child| child.name == copy_name);
-                    if !copy_exists {
-                        return copy_name;
+                "int_display" => {
+                    self.int_display = match value {
+                        "flipped" => IntDisplay::FlippedSign,
+                        "hex" => IntDisplay::Hex,
+                        _ => IntDisplay::Native,
                     }
-                    
-                    copy_counter += 1;
-                    if copy_counter > 100 {
-                        break; // Prevent infinite loop
+                }
+                "label_sort" => {
+                    self.param_file.hash_labels.csv_sort_order = match value {
+                        "label" => LabelSortOrder::Label,
+                        "insertion" => LabelSortOrder::Insertion,
+                        _ => LabelSortOrder::Hash,
                     }
                 }
-            }
-        }
-        
-        // For numeric names or when name conflicts exist, generate sequential name
-        self.generate_sequential_name(parent_path, original_name)
-    }
-    
-    /// Find the Smash Ultimate Blender plugin directory
-    fn find_blender_addon_directory() -> Option<PathBuf> {
-        // Get the user's AppData/Roaming directory
-        if let Some(mut appdata_dir) = dirs::config_dir() {
-            // On Windows, this gives us AppData/Roaming
-            // On other platforms, we'll try to find Blender config
-            
-            #[cfg(target_os = "windows")]
-            {
-                appdata_dir.push("Blender Foundation");
-                appdata_dir.push("Blender");
-            }
-            
-            #[cfg(not(target_os = "windows"))]
-            {
-                // On Linux/Mac, Blender config is usually in ~/.config/blender/
-                appdata_dir.push("blender");
-            }
-            
-            // Try to find any Blender version directory
-            if let Ok(entries) = std::fs::read_dir(&appdata_dir) {
-                let mut blender_versions: Vec<_> = entries
-                    .filter_map(|entry| entry.ok())
-                    .filter(|entry| entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false))
-                    .filter_map(|entry| {
-                        let name = entry.file_name().to_string_lossy().to_string();
-                        // Look for version patterns like "4.2", "3.6", etc.
-                        if name.chars().next().map(|c| c.is_ascii_digit()).unwrap_or(false) {
-                            Some((name, entry.path()))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                
-                // Sort by version (newest first)
-                blender_versions.sort_by(|a, b| b.0.cmp(&a.0));
-                
-                // Try each version directory
-                for (_, version_path) in blender_versions {
-                    let mut addon_path = version_path;
-                    addon_path.push("scripts");
-                    addon_path.push("addons");
-                    addon_path.push("smash_ultimate_blender");
-                    addon_path.push("dependencies");
-                    addon_path.push("pyprc");
-                    
-                    if addon_path.exists() {
-                        return Some(addon_path);
+                "canonical_labels" if !value.is_empty() => self.canonical_labels_path = Some(value.to_string()),
+                key => {
+                    if let Some(id) = key.strip_prefix("shortcut.") {
+                        self.shortcuts.load_preference(id, value);
                     }
                 }
             }
         }
-        
-        None
     }
     
-    /// Get the path to the configuration file
-    fn get_config_path() -> std::path::PathBuf {
-        // Store config in the same directory as the executable
-        let mut config_path = std::env::current_exe()
-            .unwrap_or_else(|_| std::path::PathBuf::from("."));
-        config_path.pop(); // Remove the executable name
-        config_path.push("prc_editor_config.txt");
-        config_path
+    /// Save preferences as `key=value` lines
+    fn save_preferences(&self) {
+        let mut content = format!(
+            "compact_mode={}\nshow_tree_values={}\nfold_single_child={}\nflag_special_floats={}\nverify_after_save={}\nwrite_xml_sidecar={}\ncase_sensitive_search={}\nauto_expand_on_select={}\nhighlight_unlabeled={}\nundo_limit={}\nraw_byte_editing={}\nhighlight_editing={}\ncompact_density={}\ngroup_struct_fields={}\n",
+            self.compact_mode, self.show_tree_values, self.fold_single_child, self.flag_special_floats, self.verify_after_save, self.write_xml_sidecar, self.case_sensitive_search,
+            self.auto_expand_on_select, self.highlight_unlabeled, self.undo_limit, self.raw_byte_editing, self.highlight_editing, self.compact_density, self.group_struct_fields
+        );
+        content.push_str(&format!("int_display={}\n", match self.int_display {
+            IntDisplay::Native => "native",
+            IntDisplay::FlippedSign => "flipped",
+            IntDisplay::Hex => "hex",
+        }));
+        content.push_str(&format!("keep_trailing_bytes={}\n", self.param_file.keep_trailing_bytes));
+        content.push_str(&format!("persist_notes={}\n", self.persist_notes));
+        content.push_str(&format!("label_sort={}\n", match self.param_file.hash_labels.csv_sort_order {
+            LabelSortOrder::Hash => "hash",
+            LabelSortOrder::Label => "label",
+            LabelSortOrder::Insertion => "insertion",
+        }));
+        // 0 means exact
+        content.push_str(&format!("float_digits={}\n", self.float_digits.unwrap_or(0)));
+        if let Some(path) = &self.canonical_labels_path {
+            content.push_str(&format!("canonical_labels={}\n", path));
+        }
+        content.push_str(&self.shortcuts.to_preferences());
+        let _ = std::fs::write(Self::get_preferences_path(), content);
     }
     
-    /// Save the ParamLabels.csv path to a config file
-    fn save_labels_path(&self, path: &str) {
-        let config_path = Self::get_config_path();
-        let _ = std::fs::write(&config_path, path);
+    /// Label Editor "Add new label": hash the typed label and add it (undoable)
+    fn add_label_from_editor(&mut self) {
+        let label = self.new_label_input.clone();
+        let hash = self.param_file.hash_labels.string_to_hash40(&label);
+        self.status_message = match self.change_label_with_undo(hash, Some(&label)) {
+            Ok(()) => {
+                let path_display = self.param_labels_path.as_deref().unwrap_or("ParamLabels.csv");
+                format!("Added label '{}' with hash 0x{:X} and saved to {}", label, hash, path_display)
+            }
+            Err(e) => format!("Added label '{}' with hash 0x{:X} but failed to save: {}", label, hash, e),
+        };
+        self.new_label_input.clear();
     }
     
-    /// Load the saved ParamLabels.csv path from the config file
-    fn load_saved_labels_path(&self) -> Option<String> {
-        let config_path = Self::get_config_path();
-        match std::fs::read_to_string(&config_path) {
-            Ok(content) => {
-                let path = content.trim().to_string();
-                if !path.is_empty() {
-                    Some(path)
-                } else {
-                    None
-                }
-            }
-            Err(_) => None, // Config file doesn't exist yet
+    /// Labels matching the editor's filter, limited to mismatched ones when that's ticked.
+    /// Takes the fields it reads so the result can be held while the rest of self changes.
+    fn filtered_editor_labels<'a>(hash_labels: &'a HashLabels, filter: &str, case_sensitive: bool, mismatches_only: bool) -> Vec<(u64, &'a String)> {
+        let mut labels = hash_labels.get_labels_filtered(filter, case_sensitive);
+        if mismatches_only && hash_labels.mismatched_count() > 0 {
+            labels.retain(|(hash, _)| hash_labels.is_mismatched(*hash));
         }
+        labels
     }
     
     fn show_label_editor_window(&mut self, ctx: &egui::Context) {
@@ -2070,22 +7770,12 @@ impl PrcEditorApp {
                     ui.label("Add new label:");
                     if ui.text_edit_singleline(&mut self.new_label_input).lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
                         if !self.new_label_input.is_empty() {
-                            let hash = self.param_file.hash_labels.add_label_and_save(&self.new_label_input, self.param_labels_path.as_deref());
-                            let path_display = self.param_labels_path.as_deref().unwrap_or("ParamLabels.csv");
-                            self.status_message = format!("Added label '{}' with hash 0x{:X} and saved to {}", self.new_label_input, hash, path_display);
-                            self.new_label_input.clear();
-                            // Rebuild tree to show updated labels
-                            self.param_file.rebuild_tree_with_labels();
+                            self.add_label_from_editor();
                         }
                     }
                     if ui.button("Generate Hash").clicked() {
                         if !self.new_label_input.is_empty() {
-                            let hash = self.param_file.hash_labels.add_label_and_save(&self.new_label_input, self.param_labels_path.as_deref());
-                            let path_display = self.param_labels_path.as_deref().unwrap_or("ParamLabels.csv");
-                            self.status_message = format!("Added label '{}' with hash 0x{:X} and saved to {}", self.new_label_input, hash, path_display);
-                            self.new_label_input.clear();
-                            // Rebuild tree to show updated labels
-                            self.param_file.rebuild_tree_with_labels();
+                            self.add_label_from_editor();
                         }
                     }
                 });
@@ -2103,12 +7793,11 @@ impl PrcEditorApp {
                             let hash_str = self.new_hash_input.trim_start_matches("0x");
                             if let Ok(hash) = u64::from_str_radix(hash_str, 16) {
                                 // Add the label for this specific hash and save
-                                match self.param_file.hash_labels.add_label_for_hash_and_save(hash, &self.new_label_input, self.param_labels_path.as_deref()) {
+                                let label = self.new_label_input.clone();
+                                match self.change_label_with_undo(hash, Some(&label)) {
                                     Ok(()) => {
                                         let path_display = self.param_labels_path.as_deref().unwrap_or("ParamLabels.csv");
                                         self.status_message = format!("Added label '{}' for hash 0x{:X} and saved to {}", self.new_label_input, hash, path_display);
-                                        // Rebuild tree to show updated labels
-                                        self.param_file.rebuild_tree_with_labels();
                                     }
                                     Err(e) => {
                                         self.status_message = format!("Added label but failed to save: {}", e);
@@ -2135,13 +7824,18 @@ impl PrcEditorApp {
                         self.label_editor_filter.clear();
                         self.label_page = 1; // Reset to first page
                     }
+                    self.show_case_sensitive_toggle(ui);
                     
                     ui.separator();
                     
                     // Pagination controls
-                    let filtered_labels = self.param_file.hash_labels.get_labels_filtered(&self.label_editor_filter);
+                    let filtered_labels = Self::filtered_editor_labels(
+                        &self.param_file.hash_labels, &self.label_editor_filter, self.case_sensitive_search, self.label_mismatches_only,
+                    );
                     let total_labels = filtered_labels.len();
                     let total_pages = (total_labels + self.labels_per_page - 1) / self.labels_per_page;
+                    // Stay on a real page when the filter or a bulk delete shrinks the list
+                    self.label_page = self.label_page.clamp(1, total_pages.max(1));
                     
                     ui.label(format!("Page {} of {} ({} labels)", self.label_page, total_pages.max(1), total_labels));
                     
@@ -2167,9 +7861,40 @@ impl PrcEditorApp {
                         });
                 });
                 
+                // Bulk selection controls
+                ui.horizontal(|ui| {
+                    // Drop ticks for labels removed some other way (undo, reload, single delete)
+                    let labels = self.param_file.hash_labels.get_all_labels();
+                    self.selected_label_hashes.retain(|hash| labels.contains_key(hash));
+                    
+                    let selected_count = self.selected_label_hashes.len();
+                    ui.label(format!("{} selected", selected_count));
+                    if ui.add_enabled(selected_count > 0, egui::Button::new("Delete Selected...")).clicked() {
+                        self.show_bulk_label_delete_confirm = true;
+                    }
+                    if ui.add_enabled(selected_count > 0, egui::Button::new("Clear Selection")).clicked() {
+                        self.selected_label_hashes.clear();
+                        self.label_select_anchor = None;
+                    }
+                    ui.label("(Shift+click a checkbox to select a range)");
+                    
+                    let mismatched = self.param_file.hash_labels.mismatched_count();
+                    if mismatched > 0 {
+                        ui.separator();
+                        ui.colored_label(egui::Color32::from_rgb(255, 140, 0), format!("⚠ {} labels don't hash to their key", mismatched))
+                            .on_hover_text("The label text hashes to a different value than the hash it's listed under. Usually a typo, but some names are deliberate overrides.");
+                        if ui.checkbox(&mut self.label_mismatches_only, "Show only these").changed() {
+                            self.label_page = 1;
+                        }
+                    }
+                });
+                
                 ui.separator();
                 
                 // Labels list
+                let mut find_hash = None;
+                let mut delete_hash = None;
+                let mut toggled_row = None;
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     egui::Grid::new("labels_grid")
                         .num_columns(3)
@@ -2183,7 +7908,9 @@ impl PrcEditorApp {
                             ui.end_row();
                             
                             // Show actual loaded labels with pagination
-                            let mut filtered_labels = self.param_file.hash_labels.get_labels_filtered(&self.label_editor_filter);
+                            let mut filtered_labels = Self::filtered_editor_labels(
+                                &self.param_file.hash_labels, &self.label_editor_filter, self.case_sensitive_search, self.label_mismatches_only,
+                            );
                             filtered_labels.sort_by(|a, b| a.1.cmp(b.1)); // Sort by label name
                             
                             // Calculate pagination
@@ -2191,8 +7918,21 @@ impl PrcEditorApp {
                             let _end_index = (start_index + self.labels_per_page).min(filtered_labels.len());
                             
                             // Show only current page
-                            for (hash, label) in filtered_labels.iter().skip(start_index).take(self.labels_per_page) {
-                                ui.monospace(format!("0x{:X}", hash));
+                            let page: Vec<(u64, &String)> = filtered_labels.iter().skip(start_index).take(self.labels_per_page).copied().collect();
+                            for (row, (hash, label)) in page.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    let mut checked = self.selected_label_hashes.contains(hash);
+                                    if ui.checkbox(&mut checked, "").clicked() {
+                                        let shift = ui.input(|i| i.modifiers.shift);
+                                        toggled_row = Some((page.iter().map(|(h, _)| *h).collect::<Vec<_>>(), row, checked, shift));
+                                    }
+                                    ui.monospace(format!("0x{:X}", hash));
+                                    if self.param_file.hash_labels.is_mismatched(*hash) {
+                                        let own_hash = self.param_file.hash_labels.string_to_hash40(label);
+                                        ui.colored_label(egui::Color32::from_rgb(255, 140, 0), "⚠")
+                                            .on_hover_text(format!("'{}' hashes to 0x{:X}, not 0x{:X}", label, own_hash, hash));
+                                    }
+                                });
                                 
                                 // Editable label
                                 let mut edit_label = (*label).clone();
@@ -2203,392 +7943,487 @@ impl PrcEditorApp {
                                 
                                 // Actions
                                 ui.horizontal(|ui| {
+                                    if ui.small_button("Find in file").clicked() {
+                                        find_hash = Some(*hash);
+                                    }
                                     if ui.small_button("Copy").clicked() {
                                         ui.output_mut(|o| o.copied_text = label.to_string());
                                         self.status_message = format!("Copied: {}", label);
                                     }
                                     if ui.small_button("Delete").clicked() {
-                                        self.status_message = format!("Delete label: {}", label);
+                                        delete_hash = Some(*hash);
                                     }
                                 });
                                 
                                 ui.end_row();
-                            }
-                        });
-                });
-                
-                ui.separator();
-                
-                ui.horizontal(|ui| {
-                    if ui.button("Close").clicked() {
-                        self.show_label_editor = false;
-                    }
-                });
-            });
-            
-        // Handle window close button (X)
-        if !open {
-            self.show_label_editor = false;
-        }
-    }
-
-    fn handle_keyboard_shortcuts(&mut self, ctx: &egui::Context) {
-        // Try to handle clipboard operations using egui's events
-        ctx.input_mut(|i| {
-            // Check for copy/paste events that egui might have processed
-            if !i.events.is_empty() {
-                for event in &i.events {
-                    match event {
-                        egui::Event::Copy => {
-                            if let Some(selected_path) = &self.selected_node {
-                                self.clipboard = Some(selected_path.clone());
-                                self.clipboard_data = self.find_node_by_path(selected_path).cloned();
-                                self.cut_mode = false;
-                                let has_data = self.clipboard_data.is_some();
-                                self.status_message = format!("Copied node via event: {} (data: {})", selected_path, has_data);
-                            } else {
-                                self.status_message = "Copy event: No node selected".to_string();
-                            }
-                            return;
-                        }
-                        egui::Event::Paste(_text) => {
-                            // Handle paste using our internal clipboard
-                            if let (Some(clipboard_data), Some(selected_path)) = (self.clipboard_data.clone(), self.selected_node.clone()) {
-                                if self.paste_node_into(&selected_path, clipboard_data) {
-                                    let action = if self.cut_mode { "Moved" } else { "Pasted" };
-                                    self.status_message = format!("{} node into {} via paste event", action, selected_path);
-                                    
-                                    // For cut operations, clear the clipboard since it's now moved
-                                    if self.cut_mode {
-                                        self.clipboard = None;
-                                        self.clipboard_data = None;
-                                        self.cut_mode = false;
-                                    }
-                                    
-                                    // Rebuild tree items to show changes
-                                    self.build_tree_items();
-                                } else {
-                                    self.status_message = format!("Failed to paste into {} via paste event", selected_path);
-                                }
-                            } else {
-                                self.status_message = "Paste event: Nothing to paste".to_string();
-                            }
-                            return;
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            
-
-            
-            // Try alternative shortcut detection using egui's shortcut system
-            if !self.editing_value.is_some() {
-                // Try using egui's shortcut detection
-                if i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::CTRL, egui::Key::V)) ||
-                   i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::SHIFT, egui::Key::V)) {
-                    // Handle paste logic here
-                    if let (Some(clipboard_data), Some(selected_path)) = (self.clipboard_data.clone(), self.selected_node.clone()) {
-                        if self.paste_node_into(&selected_path, clipboard_data.clone()) {
-                            let action = if self.cut_mode { "Moved" } else { "Pasted" };
-                            let paste_type = match (&clipboard_data.value, self.find_node_by_path(&selected_path).map(|n| &n.value)) {
-                                (ParamValue::Struct(_), Some(ParamValue::Struct(_))) => "fields",
-                                (ParamValue::List(_), Some(ParamValue::List(_))) => "items",
-                                _ => "node"
-                            };
-                            self.status_message = format!("{} {} into {} with Ctrl+V (shortcut)", action, paste_type, selected_path);
-                            
-                            if self.cut_mode {
-                                self.clipboard = None;
-                                self.clipboard_data = None;
-                                self.cut_mode = false;
-                            }
-                            self.build_tree_items();
-                        } else {
-                            self.status_message = format!("Failed to paste into {} with Ctrl+V (shortcut)", selected_path);
-                        }
-                    } else {
-                        self.status_message = "Ctrl+V (shortcut): Nothing to paste".to_string();
-                    }
-                }
-                
-                if i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::SHIFT, egui::Key::Insert)) ||
-                   i.consume_shortcut(&egui::KeyboardShortcut::new(egui::Modifiers::ALT, egui::Key::Insert)) {
-                    // Handle paste logic here
-                    if let (Some(clipboard_data), Some(selected_path)) = (self.clipboard_data.clone(), self.selected_node.clone()) {
-                        if self.paste_node_into(&selected_path, clipboard_data.clone()) {
-                            let action = if self.cut_mode { "Moved" } else { "Pasted" };
-                            let paste_type = match (&clipboard_data.value, self.find_node_by_path(&selected_path).map(|n| &n.value)) {
-                                (ParamValue::Struct(_), Some(ParamValue::Struct(_))) => "fields",
-                                (ParamValue::List(_), Some(ParamValue::List(_))) => "items",
-                                _ => "node"
-                            };
-                            self.status_message = format!("{} {} into {} with Shift+Insert (shortcut)", action, paste_type, selected_path);
-                            
-                            if self.cut_mode {
-                                self.clipboard = None;
-                                self.clipboard_data = None;
-                                self.cut_mode = false;
-                            }
-                            self.build_tree_items();
-                        } else {
-                            self.status_message = format!("Failed to paste into {} with Shift+Insert (shortcut)", selected_path);
-                        }
-                    } else {
-                        self.status_message = "Shift+Insert (shortcut): Nothing to paste".to_string();
-                    }
-                }
-            }
-            
-            // Only handle shortcuts if no text editing is active
-            if !self.editing_value.is_some() {
-                let ctrl = i.modifiers.ctrl;
-                
-                // Arrow key navigation
-                if i.key_pressed(egui::Key::ArrowUp) {
-                    self.navigate_up();
-                }
-                if i.key_pressed(egui::Key::ArrowDown) {
-                    self.navigate_down();
-                }
-                if i.key_pressed(egui::Key::ArrowLeft) {
-                    self.navigate_left();
-                }
-                if i.key_pressed(egui::Key::ArrowRight) {
-                    self.navigate_right();
-                }
-                
-                // ENTER - Open data grid for selected node (expand/collapse)
-                if i.key_pressed(egui::Key::Enter) {
-                    if let Some(selected_path) = &self.selected_node {
-                        if self.expanded_nodes.contains(selected_path) {
-                            self.expanded_nodes.remove(selected_path);
-                            self.status_message = "Collapsed node".to_string();
-                        } else {
-                            self.expanded_nodes.insert(selected_path.clone());
-                            self.status_message = "Expanded node".to_string();
-                        }
-                    }
-                }
-                
-                // DEL - Delete the node
-                if i.key_pressed(egui::Key::Delete) {
-                    if let Some(selected_path) = self.selected_node.clone() {
-                        if self.delete_node(&selected_path) {
-                            self.status_message = format!("Deleted node: {}", selected_path);
-                            // Clear selection since the node no longer exists
-                            self.selected_node = None;
-                            self.selected_index = None;
-                            // Rebuild tree items
-                            self.build_tree_items();
-                        } else {
-                            self.status_message = format!("Failed to delete node: {}", selected_path);
-                        }
-                    }
-                }
-                
-                // CTRL + C - Copy the node (try multiple approaches)
-                if (ctrl && i.key_pressed(egui::Key::C)) || 
-                   (ctrl && i.modifiers.shift && i.key_pressed(egui::Key::C)) ||
-                   (ctrl && i.key_pressed(egui::Key::Insert)) {
-                    if let Some(selected_path) = &self.selected_node {
-                        self.clipboard = Some(selected_path.clone());
-                        let node_data = self.find_node_by_path(selected_path).cloned();
-                        
-
-                        
-                        self.clipboard_data = node_data;
-                        self.cut_mode = false;
-                        let shortcut = if i.key_pressed(egui::Key::Insert) { "Ctrl+Insert" } 
-                                      else if i.modifiers.shift { "Ctrl+Shift+C" } 
-                                      else { "Ctrl+C" };
-                        self.status_message = format!("Copied node with {}: {}", shortcut, selected_path);
-                    } else {
-                        self.status_message = "No node selected to copy".to_string();
-                    }
-                }
-                
-                // CTRL + X - Cut the node
-                if ctrl && i.key_pressed(egui::Key::X) {
-                    if let Some(selected_path) = self.selected_node.clone() {
-                        // First copy the node data
-                        if let Some(node_data) = self.find_node_by_path(&selected_path).cloned() {
-                        self.clipboard = Some(selected_path.clone());
-                            self.clipboard_data = Some(node_data);
-                            self.cut_mode = true;
-                            
-                            // Then delete the node from its current location
-                            if self.delete_node(&selected_path) {
-                                self.status_message = format!("Cut node: {}", selected_path);
-                                // Clear selection since the node no longer exists
-                                self.selected_node = None;
-                                self.selected_index = None;
-                                // Rebuild tree items
-                                self.build_tree_items();
-                            } else {
-                                self.status_message = format!("Failed to cut node: {}", selected_path);
-                                // Clear clipboard if cut failed
-                                self.clipboard = None;
-                                self.clipboard_data = None;
-                                self.cut_mode = false;
-                            }
-                        } else {
-                            self.status_message = format!("Could not find node to cut: {}", selected_path);
-                        }
-                    }
-                }
-                
-                // Note: Ctrl+V paste is handled by the egui shortcut system above
-                // Only handle the alternative paste shortcuts here that egui might not catch
-                if (i.modifiers.shift && i.key_pressed(egui::Key::V) && !ctrl) ||  // Shift+V (when Ctrl+V is detected as Shift+V)
-                   (i.modifiers.alt && i.key_pressed(egui::Key::Insert)) {  // Alt+Insert (when Shift+Insert is detected as Alt+Insert)
-                    let shortcut = if i.modifiers.alt && i.key_pressed(egui::Key::Insert) { "Shift+Insert (detected as Alt+Insert)" }
-                                  else { "Ctrl+V (detected as Shift+V)" };
-                    
-                    if let (Some(clipboard_data), Some(selected_path)) = (self.clipboard_data.clone(), self.selected_node.clone()) {
-                        if self.paste_node_into(&selected_path, clipboard_data.clone()) {
-                            let action = if self.cut_mode { "Moved" } else { "Pasted" };
-                            let paste_type = match self.find_node_by_path(&selected_path).map(|n| &n.value) {
-                                Some(ParamValue::Struct(_)) => "node into struct",
-                                Some(ParamValue::List(_)) => "node into list",
-                                _ => "node"
-                            };
-                            self.status_message = format!("{} {} {} with {}", action, paste_type, selected_path, shortcut);
-                            
-                            // For cut operations, clear the clipboard since it's now moved
-                            if self.cut_mode {
-                                self.clipboard = None;
-                                self.clipboard_data = None;
-                                self.cut_mode = false;
-                            }
-                            
-                            // Rebuild tree items to show changes
-                            self.build_tree_items();
-                        } else {
-                            self.status_message = format!("Failed to paste into {} with {}", selected_path, shortcut);
-                        }
-                    } else {
-                        let has_clipboard = self.clipboard.is_some();
-                        let has_data = self.clipboard_data.is_some();
-                        let has_selection = self.selected_node.is_some();
-                        self.status_message = format!("{}: Nothing to paste (clipboard: {}, data: {}, selection: {})", 
-                            shortcut, has_clipboard, has_data, has_selection);
-                    }
-                }
-                
-                // CTRL + P - Paste the copied node into the parent
-                if ctrl && i.key_pressed(egui::Key::P) {
-                    if let (Some(clipboard_data), Some(selected_path)) = (self.clipboard_data.clone(), self.selected_node.clone()) {
-                        if let Some(parent_path) = self.get_parent_path(&selected_path) {
-                            // Generate a new name for the pasted node, preserving original names when possible
-                            let mut new_clipboard_data = clipboard_data.clone();
-                            let original_name = &clipboard_data.name;
-                            let generated_name = self.generate_paste_name(&parent_path, original_name);
-                            new_clipboard_data.name = generated_name.clone();
-                            new_clipboard_data.hash = self.param_file.hash_labels.add_label_and_save(&new_clipboard_data.name, self.param_labels_path.as_deref());
-                            
-
-                            
-                            if self.paste_node_into(&parent_path, new_clipboard_data) {
-                                let action = if self.cut_mode { "Moved" } else { "Pasted" };
-                                self.status_message = format!("{} node into parent of {}", action, selected_path);
-                                
-                                // For cut operations, clear the clipboard since it's now moved
-                                if self.cut_mode {
-                                    self.clipboard = None;
-                                    self.clipboard_data = None;
-                                    self.cut_mode = false;
-                                }
-                                
-                                // Rebuild tree items to show changes
-                                self.build_tree_items();
-                            } else {
-                                self.status_message = format!("Failed to paste into parent of {}", selected_path);
-                            }
-                        } else {
-                            self.status_message = "Root node has no parent".to_string();
-                        }
-                    } else {
-                        self.status_message = "Nothing to paste into parent".to_string();
-                    }
+                            }
+                        });
+                });
+                
+                if let Some((page_hashes, row, checked, shift)) = toggled_row {
+                    self.toggle_label_selection(&page_hashes, row, checked, shift);
+                }
+                if let Some(hash) = find_hash {
+                    self.locate_hash_in_tree(hash);
+                }
+                if let Some(hash) = delete_hash {
+                    let label = self.param_file.hash_labels.hash_to_string(hash);
+                    self.status_message = match self.change_label_with_undo(hash, None) {
+                        Ok(()) => format!("Deleted label '{}' (Ctrl+Z to undo)", label),
+                        Err(e) => format!("Deleted label '{}' but failed to save: {}", label, e),
+                    };
                 }
                 
-                // CTRL + D - Duplicate param on the same level
-                if ctrl && i.key_pressed(egui::Key::D) {
-                    if let Some(selected_path) = self.selected_node.clone() {
-                        if let Some(node_to_duplicate) = self.find_node_by_path(&selected_path).cloned() {
-                            if let Some(parent_path) = self.get_parent_path(&selected_path) {
-                                // Generate a new name for the duplicated node
-                                let mut new_node = node_to_duplicate.clone();
-                                new_node.name = self.generate_sequential_name(&parent_path, &node_to_duplicate.name);
-                                new_node.hash = self.param_file.hash_labels.add_label_and_save(&new_node.name, self.param_labels_path.as_deref());
-                                
-                                if self.paste_node_into(&parent_path, new_node) {
-                                    self.status_message = format!("Duplicated node: {}", selected_path);
-                                    // Rebuild tree items to show changes
-                                    self.build_tree_items();
-                                } else {
-                                    self.status_message = format!("Failed to duplicate node: {}", selected_path);
+                // Other uses of the hash from the last "Find in file"
+                let mut jump_to = None;
+                if let Some((hash, paths)) = &self.hash_locations {
+                    if paths.len() > 1 {
+                        ui.separator();
+                        ui.label(format!("0x{:X} is used at {} places:", hash, paths.len()));
+                        egui::ScrollArea::vertical().id_source("hash_locations").max_height(100.0).show(ui, |ui| {
+                            for path in paths {
+                                let is_selected = self.selected_node.as_ref() == Some(path);
+                                if ui.selectable_label(is_selected, path).clicked() {
+                                    jump_to = Some(path.clone());
                                 }
-                            } else {
-                                self.status_message = "Cannot duplicate root node".to_string();
                             }
-                        } else {
-                            self.status_message = format!("Could not find node to duplicate: {}", selected_path);
-                        }
+                        });
                     }
                 }
-                
-                // CTRL + S - Save file
-                if ctrl && i.key_pressed(egui::Key::S) {
-                    if self.param_file.get_root().is_some() {
-                        self.save_file_dialog();
-                    } else {
-                        self.status_message = "No file to save".to_string();
-                    }
+                if let Some(path) = jump_to {
+                    self.jump_to_path(&path);
                 }
                 
-                // CTRL + Z - Undo
-                if ctrl && i.key_pressed(egui::Key::Z) {
-                    if self.undo() {
-                        // Undo was successful, status message is set by undo()
-                    } else {
-                        self.status_message = "Nothing to undo".to_string();
-                    }
-                }
+                ui.separator();
                 
-                // CTRL + Y - Redo
-                if ctrl && i.key_pressed(egui::Key::Y) {
-                    if self.redo() {
-                        // Redo was successful, status message is set by redo()
-                    } else {
-                        self.status_message = "Nothing to redo".to_string();
+                ui.horizontal(|ui| {
+                    if ui.button("Close").clicked() {
+                        self.show_label_editor = false;
                     }
+                });
+            });
+            
+        // Handle window close button (X)
+        if !open {
+            self.show_label_editor = false;
+        }
+    }
+
+    /// Tick or untick a Label Editor row. With shift held, every row on the page between
+    /// the previous click and this one gets the same state.
+    fn toggle_label_selection(&mut self, page_hashes: &[u64], row: usize, checked: bool, shift: bool) {
+        let anchor_row = self.label_select_anchor
+            .filter(|_| shift)
+            .and_then(|anchor| page_hashes.iter().position(|h| *h == anchor));
+        let range = match anchor_row {
+            Some(anchor_row) => anchor_row.min(row)..=anchor_row.max(row),
+            None => row..=row,
+        };
+        
+        for hash in &page_hashes[range] {
+            if checked {
+                self.selected_label_hashes.insert(*hash);
+            } else {
+                self.selected_label_hashes.remove(hash);
+            }
+        }
+        self.label_select_anchor = Some(page_hashes[row]);
+    }
+    
+    /// Confirm before deleting every ticked label in the Label Editor
+    fn show_bulk_label_delete_window(&mut self, ctx: &egui::Context) {
+        if !self.show_bulk_label_delete_confirm {
+            return;
+        }
+        
+        let count = self.selected_label_hashes.len();
+        let mut open = true;
+        let mut delete = false;
+        let mut cancel = false;
+        
+        egui::Window::new("Delete Labels")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("Delete {} selected labels?", count));
+                if let Some(path) = &self.param_labels_path {
+                    ui.label(format!("{} will be saved afterwards. Ctrl+Z restores them.", path));
                 }
-                
-                // F2 - Rename selected node
-                if i.key_pressed(egui::Key::F2) {
-                    if let Some(selected_path) = &self.selected_node {
-                        if let Some(node) = self.find_node_by_path(selected_path) {
-                            let name_edit_path = format!("{}_name", selected_path);
-                            self.editing_value = Some((name_edit_path, node.name.clone()));
-                            self.status_message = "Press Enter to confirm rename, Escape to cancel".to_string();
-                        }
-                    }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    delete = ui.add_enabled(count > 0, egui::Button::new("Delete")).clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        
+        if delete {
+            let mut hashes: Vec<u64> = self.selected_label_hashes.drain().collect();
+            hashes.sort_unstable();
+            self.label_select_anchor = None;
+            self.status_message = match self.remove_labels_with_undo(&hashes) {
+                Ok(removed) => format!("Deleted {} labels (Ctrl+Z to undo)", removed),
+                Err(e) => format!("Deleted labels but failed to save: {}", e),
+            };
+        }
+        
+        self.show_bulk_label_delete_confirm = open && !delete && !cancel;
+    }
+    
+    /// Remove several labels as a single undo step, saving the CSV once; returns how many were removed
+    fn remove_labels_with_undo(&mut self, hashes: &[u64]) -> anyhow::Result<usize> {
+        let mut actions = Vec::new();
+        for &hash in hashes {
+            let old = self.param_file.hash_labels.label_state(hash);
+            if old.label.is_some() {
+                self.param_file.hash_labels.remove_label(hash);
+                let new = self.param_file.hash_labels.label_state(hash);
+                actions.push(UndoAction::Label { hash, old, new });
+            }
+        }
+        
+        let removed = actions.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+        self.param_file.refresh_names_only();
+        self.push_undo_action(UndoAction::Batch { actions });
+        
+        if let Some(path) = &self.param_labels_path {
+            self.param_file.hash_labels.save_to_csv(path)?;
+        }
+        Ok(removed)
+    }
+
+    /// Run the commands whose shortcuts were pressed this frame, as bound in `self.shortcuts`
+    fn handle_keyboard_shortcuts(&mut self, ctx: &egui::Context) {
+        // The shortcuts window is waiting for a chord to bind
+        if self.capturing_shortcut.is_some() {
+            self.capture_shortcut(ctx);
+            return;
+        }
+        
+        let text_has_focus = ctx.wants_keyboard_input();
+        let commands = ctx.input(|i| self.shortcuts.commands_for(&shortcuts::pressed_chords(i)));
+        for command in commands {
+            // Checked per command: F2 starts editing, which holds back the rest of the frame's keys
+            let editing = self.editing_value.is_some();
+            let allowed = match command_info(command).scope {
+                CommandScope::Always => true,
+                CommandScope::NotEditing => !editing,
+                CommandScope::NoTextFocus => !editing && !text_has_focus,
+            };
+            if allowed {
+                self.run_command(command);
+            }
+        }
+    }
+
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::NavigateUp => self.navigate_up(),
+            Command::NavigateDown => self.navigate_down(),
+            Command::NavigateLeft => self.navigate_left(),
+            Command::NavigateRight => self.navigate_right(),
+            Command::Back => self.navigate_history(true),
+            Command::Forward => self.navigate_history(false),
+            Command::CollapseSubtree => {
+                if let Some(selected_path) = self.selected_node.clone() {
+                    self.collapse_all_under(&selected_path);
                 }
-                
-                // F1 key removed - shortcuts are now always visible
             }
+            Command::ExpandSubtree => {
+                if let Some(selected_path) = self.selected_node.clone() {
+                    self.expand_all_under(&selected_path);
+                }
+            }
+            Command::ToggleExpand => self.toggle_selected_expanded(),
+            // Focuses the search box, even from another text field
+            Command::Search => self.focus_search = true,
+            Command::NextMatch => self.step_search_match(true),
+            Command::PreviousMatch => self.step_search_match(false),
+            Command::NextUnlabeled => self.select_next_unlabeled(),
+            Command::Rename => self.rename_selected(),
+            Command::AddSibling => self.add_sibling_field(),
+            Command::Delete => self.delete_selected(),
+            Command::Duplicate => self.duplicate_selected(),
+            Command::Undo => {
+                // On success the status message is set by undo()
+                if !self.undo_and_log() {
+                    self.status_message = "Nothing to undo".to_string();
+                }
+            }
+            Command::Redo => {
+                if !self.redo_and_log() {
+                    self.status_message = "Nothing to redo".to_string();
+                }
+            }
+            Command::UndoAll => self.undo_all(),
+            Command::RedoAll => self.redo_all(),
+            Command::Copy => self.copy_selected(),
+            Command::Cut => self.cut_selected(),
+            Command::Paste => self.paste_into_selected(),
+            Command::PasteValues => {
+                if let Some(selected_path) = self.selected_node.clone() {
+                    self.paste_values_into(&selected_path);
+                }
+            }
+            Command::PasteToParent => self.paste_into_parent_of_selected(),
+            Command::CopyValue => {
+                if let Some(selected_path) = self.selected_node.clone() {
+                    self.copy_leaf_value(&selected_path);
+                }
+            }
+            Command::PasteValue => {
+                if let Some(selected_path) = self.selected_node.clone() {
+                    self.paste_leaf_value(&selected_path);
+                }
+            }
+            Command::Save => {
+                if self.param_file.get_root().is_some() {
+                    self.save_file_dialog();
+                } else {
+                    self.status_message = "No file to save".to_string();
+                }
+            }
+            Command::LabelEditor => self.show_label_editor = !self.show_label_editor,
+            Command::ShortcutsHelp => self.show_shortcuts = !self.show_shortcuts,
+        }
+    }
+
+    /// Bind the next chord pressed to the command being rebound in the shortcuts window.
+    /// Escape on its own cancels.
+    fn capture_shortcut(&mut self, ctx: &egui::Context) {
+        let Some((command, add)) = self.capturing_shortcut else {
+            return;
+        };
+        let pressed = ctx.input_mut(|i| {
+            let pressed = shortcuts::pressed_chords(i).into_iter().next();
+            if pressed.is_some() {
+                // Keep the chord from also pressing a focused button or closing the window
+                i.events.clear();
+            }
+            pressed
         });
+        let Some(pressed) = pressed else {
+            return;
+        };
+        
+        self.capturing_shortcut = None;
+        let action = command_info(command).action;
+        if pressed.logical_key == egui::Key::Escape && pressed.modifiers.is_none() {
+            self.status_message = format!("Shortcut for '{}' unchanged", action);
+            return;
+        }
+        
+        let binding = shortcuts::binding_for(pressed);
+        if add {
+            self.shortcuts.add_binding(command, binding);
+        } else {
+            self.shortcuts.set_bindings(command, vec![binding]);
+        }
+        self.save_preferences();
+        
+        let conflicts: Vec<&str> = self.shortcuts.conflicts_with(command).into_iter()
+            .map(|other| command_info(other).action)
+            .collect();
+        self.status_message = if conflicts.is_empty() {
+            format!("Bound {} to '{}'", format_shortcut(&binding), action)
+        } else {
+            format!("Bound {} to '{}' - also bound to: {}", format_shortcut(&binding), action, conflicts.join(", "))
+        };
+    }
+
+    fn toggle_selected_expanded(&mut self) {
+        if let Some(selected_path) = &self.selected_node {
+            if self.expanded_nodes.contains(selected_path) {
+                self.expanded_nodes.remove(selected_path);
+                self.status_message = "Collapsed node".to_string();
+            } else {
+                self.expanded_nodes.insert(selected_path.clone());
+                self.status_message = "Expanded node".to_string();
+            }
+        }
+    }
+
+    fn rename_selected(&mut self) {
+        if let Some(selected_path) = &self.selected_node {
+            if let Some(node) = self.find_node_by_path(selected_path) {
+                let name_edit_path = format!("{}_name", selected_path);
+                self.editing_value = Some((name_edit_path, node.name.clone()));
+                self.status_message = "Press Enter to confirm rename, Escape to cancel".to_string();
+            }
+        }
+    }
+
+    fn delete_selected(&mut self) {
+        if let Some(selected_path) = self.selected_node.clone() {
+            if self.delete_node(&selected_path) {
+                self.status_message = format!("Deleted node: {}", selected_path);
+                // Clear selection since the node no longer exists
+                self.selected_node = None;
+                self.selected_index = None;
+                self.build_tree_items();
+            } else {
+                self.status_message = format!("Failed to delete node: {}", selected_path);
+            }
+        }
     }
 
+    fn copy_selected(&mut self) {
+        if !self.confirm_discard_cut() {
+            // Warning window asks what to do with the pending cut
+        } else if let Some(selected_path) = self.selected_node.clone() {
+            self.clipboard_data = self.find_node_by_path(&selected_path).map(|n| n.to_owned_node());
+            self.clipboard = Some(selected_path.clone());
+            self.cut_mode = false;
+            self.status_message = format!("Copied node: {}", selected_path);
+        } else {
+            self.status_message = "No node selected to copy".to_string();
+        }
+    }
+
+    /// Take the selected node out of the tree into the clipboard. It goes back where it was
+    /// if it's never pasted.
+    fn cut_selected(&mut self) {
+        if !self.confirm_discard_cut() {
+            // Warning window asks what to do with the pending cut
+            return;
+        }
+        let Some(selected_path) = self.selected_node.clone() else {
+            return;
+        };
+        let Some(node_data) = self.find_node_by_path(&selected_path).map(|n| n.to_owned_node()) else {
+            self.status_message = format!("Could not find node to cut: {}", selected_path);
+            return;
+        };
+        
+        self.clipboard = Some(selected_path.clone());
+        self.clipboard_data = Some(node_data);
+        self.cut_mode = true;
+        
+        // Remember where the node came from so it can be restored if never pasted
+        let origin = self.get_parent_path(&selected_path)
+            .zip(self.get_node_index_in_parent(&selected_path));
+        
+        if self.delete_node(&selected_path) {
+            self.cut_origin = origin;
+            self.status_message = format!("Cut node: {}", selected_path);
+            // Clear selection since the node no longer exists
+            self.selected_node = None;
+            self.selected_index = None;
+            self.build_tree_items();
+        } else {
+            self.status_message = format!("Failed to cut node: {}", selected_path);
+            self.clipboard = None;
+            self.clipboard_data = None;
+            self.cut_origin = None;
+            self.cut_mode = false;
+        }
+    }
+
+    /// Paste the clipboard node into the selected node: a field of a struct, an item of a
+    /// list, or the new value of a leaf
+    fn paste_into_selected(&mut self) {
+        let (Some(clipboard_data), Some(selected_path)) = (self.clipboard_data.clone(), self.selected_node.clone()) else {
+            self.status_message = "Nothing to paste".to_string();
+            return;
+        };
+        if !self.check_paste_target(&selected_path) {
+            return; // Status message explains the rejection
+        }
+        
+        let paste_type = match (&clipboard_data.value, self.find_node_by_path(&selected_path).map(|n| n.value)) {
+            (ParamValue::Struct(_), Some(ParamValue::Struct(_))) => "fields",
+            (ParamValue::List(_), Some(ParamValue::List(_))) => "items",
+            _ => "node"
+        };
+        if self.paste_node_into(&selected_path, clipboard_data) {
+            let action = if self.cut_mode { "Moved" } else { "Pasted" };
+            self.status_message = format!("{} {} into {}", action, paste_type, selected_path);
+            
+            // A cut node is moved now, so it can't be pasted again
+            if self.cut_mode {
+                self.clipboard = None;
+                self.clipboard_data = None;
+                self.cut_origin = None;
+                self.cut_mode = false;
+            }
+            self.build_tree_items();
+        } else {
+            self.status_message = format!("Failed to paste into {}", selected_path);
+        }
+    }
+
+    fn paste_into_parent_of_selected(&mut self) {
+        let (Some(clipboard_data), Some(selected_path)) = (self.clipboard_data.clone(), self.selected_node.clone()) else {
+            self.status_message = "Nothing to paste into parent".to_string();
+            return;
+        };
+        let Some(parent_path) = self.get_parent_path(&selected_path) else {
+            self.status_message = "Root node has no parent".to_string();
+            return;
+        };
+        if !self.check_paste_target(&parent_path) {
+            return; // Status message explains the rejection
+        }
+        
+        // Generate a new name for the pasted node, preserving original names when possible
+        let mut new_clipboard_data = clipboard_data.clone();
+        new_clipboard_data.name = self.generate_paste_name(&parent_path, &clipboard_data.name);
+        new_clipboard_data.hash = self.param_file.hash_labels.add_label_and_save(&new_clipboard_data.name, self.param_labels_path.as_deref());
+        
+        if self.paste_node_into(&parent_path, new_clipboard_data) {
+            let action = if self.cut_mode { "Moved" } else { "Pasted" };
+            self.status_message = format!("{} node into parent of {}", action, selected_path);
+            
+            // A cut node is moved now, so it can't be pasted again
+            if self.cut_mode {
+                self.clipboard = None;
+                self.clipboard_data = None;
+                self.cut_origin = None;
+                self.cut_mode = false;
+            }
+            self.build_tree_items();
+        } else {
+            self.status_message = format!("Failed to paste into parent of {}", selected_path);
+        }
+    }
 
+    /// Copy the selected node next to itself under a numbered name
+    fn duplicate_selected(&mut self) {
+        let Some(selected_path) = self.selected_node.clone() else {
+            return;
+        };
+        let Some(node_to_duplicate) = self.find_node_by_path(&selected_path).map(|n| n.to_owned_node()) else {
+            self.status_message = format!("Could not find node to duplicate: {}", selected_path);
+            return;
+        };
+        let Some(parent_path) = self.get_parent_path(&selected_path) else {
+            self.status_message = "Cannot duplicate root node".to_string();
+            return;
+        };
+        
+        let mut new_node = node_to_duplicate.clone();
+        new_node.name = self.generate_sequential_name(&parent_path, &node_to_duplicate.name);
+        new_node.hash = self.param_file.hash_labels.add_label_and_save(&new_node.name, self.param_labels_path.as_deref());
+        
+        if self.paste_node_into(&parent_path, new_node) {
+            self.status_message = format!("Duplicated node: {}", selected_path);
+            self.build_tree_items();
+        } else {
+            self.status_message = format!("Failed to duplicate node: {}", selected_path);
+        }
+    }
 }
 
 impl eframe::App for PrcEditorApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Handle keyboard shortcuts
         self.handle_keyboard_shortcuts(ctx);
+        self.poll_round_trip_check(ctx);
+        self.poll_labels_save_failure();
         
         // Status bar at bottom using bottom panel - create this FIRST so main content knows about it
         egui::TopBottomPanel::bottom("status_panel")
@@ -2607,9 +8442,11 @@ impl eframe::App for PrcEditorApp {
                             if ui.button("Paste").clicked() {
                                 if let Some(selected_path) = self.selected_node.clone() {
                                     if let Some(clipboard_data) = self.clipboard_data.clone() {
-                                        if self.paste_node_into(&selected_path, clipboard_data.clone()) {
+                                        if !self.check_paste_target(&selected_path) {
+                                            // Status message explains the rejection
+                                        } else if self.paste_node_into(&selected_path, clipboard_data.clone()) {
                                             let action = if self.cut_mode { "Moved" } else { "Pasted" };
-                                            let paste_type = match self.find_node_by_path(&selected_path).map(|n| &n.value) {
+                                            let paste_type = match self.find_node_by_path(&selected_path).map(|n| n.value) {
                                                 Some(ParamValue::Struct(_)) => "node into struct",
                                                 Some(ParamValue::List(_)) => "node into list",
                                                 _ => "node"
@@ -2619,6 +8456,7 @@ impl eframe::App for PrcEditorApp {
                                             if self.cut_mode {
                                                 self.clipboard = None;
                                                 self.clipboard_data = None;
+                                                self.cut_origin = None;
                                                 self.cut_mode = false;
                                             }
                                             self.build_tree_items();
@@ -2633,14 +8471,42 @@ impl eframe::App for PrcEditorApp {
                         }
                         
                         // Show clipboard status
-                        if let Some(clipboard_path) = &self.clipboard {
+                        if let Some(clipboard_path) = self.clipboard.as_ref().filter(|_| !self.compact_mode) {
                             let mode = if self.cut_mode { "Cut" } else { "Copy" };
                             let has_data = self.clipboard_data.is_some();
                             ui.label(&format!("Clipboard: {} {} (data: {})", mode, clipboard_path, has_data));
                         }
                         
+                        // Show unlabeled hash count while highlighting
+                        if self.highlight_unlabeled {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 140, 0),
+                                format!("{} unlabeled hashes", self.unlabeled_paths.len())
+                            );
+                        }
+                        
+                        self.show_round_trip_badge(ui);
+                        
+                        // Which ordering the next save will use and whether it reproduces the opened file
+                        if let Some(ordering) = self.save_ordering() {
+                            let text = format!("Save: {}", Self::save_ordering_text(ordering));
+                            let label = if ordering.byte_identical {
+                                ui.label(text)
+                            } else {
+                                ui.colored_label(egui::Color32::from_rgb(255, 140, 0), text)
+                            };
+                            label.on_hover_text(
+                                "Saving rebuilds the hash table in paracobNET's traversal order and writes struct fields sorted by hash.\n\
+                                 Byte-identical: the output matches the opened file exactly.\n\
+                                 Original hash order kept: same hash table, but other bytes differ (edited values or layout).\n\
+                                 Hash table reordered: hashes were added, removed or now appear in a different order."
+                            );
+                        }
+                        
                         // Show undo/redo stack info
-                        ui.label(&format!("Undo: {} | Redo: {}", self.undo_stack.len(), self.redo_stack.len()));
+                        if !self.compact_mode {
+                            ui.label(format!("Undo: {}/{} | Redo: {}", self.undo_stack.len(), self.undo_limit, self.redo_stack.len()));
+                        }
                         
                         // Show labels count and file path
                         if let Some(path) = &self.param_labels_path {
@@ -2652,6 +8518,8 @@ impl eframe::App for PrcEditorApp {
                         } else {
                             ui.label(&format!("Labels: {} (no file)", self.param_file.hash_labels.len()));
                         }
+                        
+                        self.show_opened_path(ui);
                     });
                 });
             });
@@ -2663,11 +8531,342 @@ impl eframe::App for PrcEditorApp {
             
             ui.separator();
             
+            self.show_unknown_types_banner(ui);
+            
             // Main content area
             self.show_main_content(ui);
         });
         
         // Show label editor window if open
         self.show_label_editor_window(ctx);
+        self.show_shortcuts_window(ctx);
+        self.show_cut_warning_window(ctx);
+        self.show_session_labels_window(ctx);
+        self.show_reset_labels_window(ctx);
+        self.show_key_overwrite_window(ctx);
+        self.show_bulk_label_delete_window(ctx);
+        self.show_struct_layouts_window(ctx);
+        self.show_apply_math_window(ctx);
+        self.show_unknown_types_window(ctx);
+        self.show_patch_conflicts_window(ctx);
+        self.show_preferences_window(ctx);
+        self.show_wrap_in_struct_window(ctx);
+        self.show_note_editor_window(ctx);
+        self.show_notes_window(ctx);
+        self.show_sequence_labels_window(ctx);
+        self.show_duplicate_item_window(ctx);
+        self.show_clear_children_window(ctx);
+        self.show_normalize_hashes_window(ctx);
+        self.show_external_change_window(ctx);
+        self.show_unwritable_labels_window(ctx);
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Editor holding a struct whose only field is the list [0, 1, 2]
+    fn app_with_list() -> PrcEditorApp {
+        let mut app = PrcEditorApp::with_defaults();
+        let list = ParamValue::List(ParamList { values: vec![ParamValue::I32(0), ParamValue::I32(1), ParamValue::I32(2)] });
+        let root = ParamValue::Struct(ParamStruct { type_hash: 0, fields: [(0x05_0000_0005, list)].into_iter().collect() });
+        app.param_file.root = Some(ParamNode::new(String::new(), 0, root));
+        app.build_tree_items();
+        app
+    }
+
+    fn list_order(app: &PrcEditorApp) -> Vec<i32> {
+        let Some(ParamValue::List(list)) = app.param_file.get_node_value("root[0]") else {
+            panic!("root[0] is the list");
+        };
+        list.values.iter().map(|value| match value {
+            ParamValue::I32(n) => *n,
+            other => panic!("unexpected item {:?}", other),
+        }).collect()
+    }
+
+    fn cut(app: &mut PrcEditorApp, path: &str) {
+        app.select_path(path);
+        app.cut_selected();
+        assert!(app.cut_mode, "{}", app.status_message);
+    }
+
+    #[test]
+    fn truncate_chars_keeps_char_boundaries() {
+        assert_eq!(truncate_chars("short", 22), "short");
+        assert_eq!(truncate_chars("exactly_twelve", 14), "exactly_twelve");
+        // Byte 10 falls inside "é"; slicing by bytes would panic here
+        assert_eq!(truncate_chars("ééééééééééé", 8), "ééééé...");
+        assert_eq!(truncate_chars("ファイター名のパラメータ", 7), "ファイタ...");
+        assert_eq!(truncate_chars("🦊🦊🦊🦊", 3), "...");
+    }
+
+    #[test]
+    fn cut_item_pastes_before_its_old_next_sibling() {
+        let mut app = app_with_list();
+        cut(&mut app, "root[0][1]");
+        assert_eq!(list_order(&app), vec![0, 2]);
+
+        // Item 2 has moved up to index 1
+        assert!(app.paste_beside("root[0][1]", DropPlacement::Before));
+        assert_eq!(list_order(&app), vec![0, 1, 2]);
+        assert_eq!(app.selected_node.as_deref(), Some("root[0][1]"));
+        assert!(app.clipboard_data.is_none() && !app.cut_mode);
+    }
+
+    #[test]
+    fn cut_item_pastes_at_the_end() {
+        let mut app = app_with_list();
+        cut(&mut app, "root[0][1]");
+        assert!(app.paste_beside("root[0][1]", DropPlacement::After));
+        assert_eq!(list_order(&app), vec![0, 2, 1]);
+        assert_eq!(app.selected_node.as_deref(), Some("root[0][2]"));
+    }
+
+    #[test]
+    fn deleting_nodes_leaves_a_valid_selection() {
+        // Deleting the selected node moves the selection to its parent and drops its edit
+        let mut app = app_with_list();
+        app.select_path("root[0][2]");
+        app.editing_value = Some(("root[0][2]".to_string(), "2".to_string()));
+        assert!(app.delete_node("root[0][2]"));
+        app.build_tree_items();
+        assert_eq!(app.selected_node.as_deref(), Some("root[0]"));
+        assert!(app.editing_value.is_none());
+        assert!(app.path_is_valid("root[0][1]") && !app.path_is_valid("root[0][2]"));
+
+        // Deleting an earlier sibling keeps the same node selected at its new path
+        let mut app = app_with_list();
+        app.select_path("root[0][2]");
+        assert!(app.delete_node("root[0][0]"));
+        app.build_tree_items();
+        assert_eq!(app.selected_node.as_deref(), Some("root[0][1]"));
+        assert_eq!(app.param_file.get_node_value("root[0][1]"), Some(ParamValue::I32(2)));
+
+        // The Delete command clears the selection, then the tree selects its first row
+        let mut app = app_with_list();
+        app.select_path("root[0][1]");
+        app.delete_selected();
+        assert_eq!(list_order(&app), vec![0, 2]);
+        let selected = app.selected_node.clone().unwrap();
+        assert!(app.path_is_valid(&selected));
+        assert_eq!(app.selected_index.map(|i| &app.tree_items[i]), Some(&selected));
+    }
+
+    #[test]
+    fn hash_edited_to_a_new_value_saves() {
+        let mut app = PrcEditorApp::with_defaults();
+        let root = ParamValue::Struct(ParamStruct { type_hash: 0, fields: [(0x05_0000_0005, ParamValue::Hash(0x01_0000_0001))].into_iter().collect() });
+        app.param_file.root = Some(ParamNode::new(String::new(), 0, root));
+        let reopen = |app: &PrcEditorApp| {
+            let mut file = ParamFile::new();
+            file.open(&app.param_file.to_bytes().unwrap(), "test.prc").unwrap();
+            file.get_node_value("root[0]")
+        };
+
+        // A hash no file or label has used before
+        assert_eq!(app.apply_hash_edit("root[0]", "0xABCDEF1234"), "Hash40 value set to 0xABCDEF1234");
+        assert_eq!(reopen(&app), Some(ParamValue::Hash(0xAB_CDEF_1234)));
+
+        // A new label is hashed and registered
+        assert!(app.apply_hash_edit("root[0]", "brand_new_label").contains("brand_new_label"));
+        let hash = app.param_file.hash_labels.string_to_hash40("brand_new_label");
+        assert!(app.param_file.hash_labels.has_exact_label(hash));
+        assert_eq!(reopen(&app), Some(ParamValue::Hash(hash)));
+
+        assert_eq!(app.apply_hash_edit("root[0]", "0xZZ"), "Invalid hash40 value: 0xZZ");
+        assert_eq!(reopen(&app), Some(ParamValue::Hash(hash)));
+    }
+
+    #[test]
+    fn paste_target_inside_source_is_detected() {
+        // A copied node can't go into itself or anything under it
+        assert!(is_same_or_descendant("root[1]", "root[1]"));
+        assert!(is_same_or_descendant("root[1][0]", "root[1]"));
+        assert!(is_same_or_descendant("root[1][0][3]", "root[1]"));
+        // Siblings that only share a prefix of the index are fine
+        assert!(!is_same_or_descendant("root[10]", "root[1]"));
+        assert!(!is_same_or_descendant("root[0][1]", "root[1]"));
+        assert!(!is_same_or_descendant("root", "root[1]"));
+
+        // Cutting A (root[1]) takes it out of the tree: its former child location root[1][0]
+        // now belongs to what was root[2], so it's no longer inside A
+        assert_eq!(path_after_removal("root[2][0]", "root", 1), "root[1][0]");
+        assert_eq!(path_after_removal("root[0][4]", "root", 1), "root[0][4]");
+        assert_eq!(path_after_removal("root[12]", "root[1]", 0), "root[12]");
+    }
+
+    #[test]
+    fn param_extensions_are_kept_when_saving() {
+        for extension in PARAM_EXTENSIONS {
+            let filename = format!("stage/normal/param/data.{}", extension.to_uppercase());
+            assert_eq!(param_extension(&filename), extension);
+            assert_eq!(default_save_name(&filename, "modified"), format!("data_modified.{}", extension));
+        }
+        // Anything else saves as .prc
+        assert_eq!(param_extension("fighter_param.bin"), "prc");
+        assert_eq!(param_extension("fighter_param"), "prc");
+        assert_eq!(default_save_name("", "selection"), "params_selection.prc");
+    }
+
+    /// Value change of list item `index` in app_with_list from `old` to `new`
+    fn item_change(index: usize, old: i32, new: i32) -> UndoAction {
+        UndoAction::UpdateValue {
+            path: format!("root[0][{}]", index),
+            old_value: ParamValue::I32(old),
+            new_value: ParamValue::I32(new),
+        }
+    }
+
+    #[test]
+    fn batch_undo_and_redo_are_all_or_nothing() {
+        let mut app = app_with_list();
+        for (index, new) in [(0, 10), (2, 12)] {
+            assert!(app.param_file.update_node_value(&format!("root[0][{}]", index), ParamValue::I32(new)));
+        }
+        // The middle change points past the end of the list, so it can't be undone
+        let batch = UndoAction::Batch { actions: vec![item_change(0, 0, 10), item_change(7, 7, 17), item_change(2, 2, 12)] };
+        app.undo_stack.push(batch.clone());
+
+        assert!(!app.undo());
+        assert_eq!(list_order(&app), vec![10, 1, 12]);
+        assert_eq!(app.undo_stack.len(), 1);
+        assert!(app.redo_stack.is_empty());
+
+        let mut app = app_with_list();
+        app.redo_stack.push(batch);
+        assert!(!app.redo());
+        assert_eq!(list_order(&app), vec![0, 1, 2]);
+        assert!(app.undo_stack.is_empty());
+        assert_eq!(app.redo_stack.len(), 1);
+    }
+
+    #[test]
+    fn failed_undo_stays_on_the_stack() {
+        let mut app = app_with_list();
+        app.undo_stack.push(item_change(7, 7, 17));
+        assert!(!app.undo());
+        assert_eq!(app.undo_stack.len(), 1);
+    }
+
+    #[test]
+    fn deletes_redo_after_undo() {
+        let mut app = app_with_list();
+        assert!(app.delete_node("root[0][1]"));
+        assert!(app.undo());
+        assert_eq!(list_order(&app), vec![0, 1, 2]);
+        assert!(app.redo());
+        assert_eq!(list_order(&app), vec![0, 2]);
+        assert!(app.undo());
+        assert_eq!(list_order(&app), vec![0, 1, 2]);
+        assert_eq!(app.undo_stack.len(), 0);
+        assert_eq!(app.redo_stack.len(), 1);
+    }
+
+    #[test]
+    fn label_undo_and_redo_keep_its_place() {
+        let mut app = PrcEditorApp::with_defaults();
+        app.param_file.hash_labels.load_from_csv("0x1,one\n0x2,two\n0x3,three\n").unwrap();
+        let order = |app: &PrcEditorApp| app.param_file.hash_labels.get_all_labels().values().cloned().collect::<Vec<_>>();
+        let session = |app: &PrcEditorApp| app.param_file.hash_labels.session_additions().keys().copied().collect::<Vec<_>>();
+
+        app.change_label_with_undo(0x3, Some("drei")).unwrap();
+        app.change_label_with_undo(0x2, Some("zwei")).unwrap();
+        assert_eq!(order(&app), ["one", "zwei", "drei"]);
+        assert_eq!(session(&app), [0x3, 0x2]);
+
+        assert!(app.undo());
+        assert_eq!(order(&app), ["one", "two", "drei"]);
+        assert_eq!(session(&app), [0x3]);
+        assert!(app.undo());
+        assert!(app.redo() && app.redo());
+        assert_eq!(order(&app), ["one", "zwei", "drei"]);
+        assert_eq!(session(&app), [0x3, 0x2]);
+
+        // A removed label comes back where it was
+        app.change_label_with_undo(0x1, None).unwrap();
+        assert_eq!(order(&app), ["zwei", "drei"]);
+        assert!(app.undo());
+        assert_eq!(order(&app), ["one", "zwei", "drei"]);
+        assert_eq!(session(&app), [0x3, 0x2]);
+    }
+
+    #[test]
+    fn opening_a_file_starts_a_fresh_history() {
+        let dir = std::env::temp_dir().join(format!("prc-editor-open-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (good, bad) = (dir.join("other.prc"), dir.join("broken.prc"));
+        std::fs::write(&good, app_with_list().param_file.to_bytes().unwrap()).unwrap();
+        std::fs::write(&bad, b"paracobn\x08\0\0\0").unwrap();
+
+        let mut app = app_with_list();
+        assert!(app.param_file.update_node_value("root[0][0]", ParamValue::I32(5)));
+        app.undo_stack.push(item_change(0, 0, 5));
+        cut(&mut app, "root[0][2]");
+
+        // A file that fails to open leaves everything as it was
+        app.open_path(&bad);
+        assert_eq!(list_order(&app), vec![5, 1]);
+        assert_eq!(app.undo_stack.len(), 2);
+        assert!(app.cut_origin.is_some());
+
+        app.open_path(&good);
+        assert_eq!(list_order(&app), vec![0, 1, 2]);
+        assert!(app.undo_stack.is_empty() && app.redo_stack.is_empty());
+        assert!(app.cut_origin.is_none() && !app.cut_mode);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn undo_all_stops_at_an_action_that_fails() {
+        let mut app = app_with_list();
+        for (index, new) in [(0, 10), (2, 12)] {
+            assert!(app.param_file.update_node_value(&format!("root[0][{}]", index), ParamValue::I32(new)));
+        }
+        // The middle action points past the end of the list
+        app.undo_stack = vec![item_change(0, 0, 10), item_change(7, 7, 17), item_change(2, 2, 12)];
+
+        app.undo_all();
+        assert!(app.status_message.starts_with("Undid 1 of 3 changes"), "{}", app.status_message);
+        assert_eq!(list_order(&app), vec![10, 1, 2]);
+        assert_eq!(app.undo_stack.len(), 2);
+        assert_eq!(app.redo_stack.len(), 1);
+
+        app.redo_stack = vec![item_change(2, 2, 12), item_change(7, 7, 17), item_change(0, 10, 20)];
+        app.redo_all();
+        assert!(app.status_message.starts_with("Redid 1 of 3 changes"), "{}", app.status_message);
+        assert_eq!(list_order(&app), vec![20, 1, 2]);
+        assert_eq!(app.redo_stack.len(), 2);
+    }
+
+    #[test]
+    fn duplicated_items_undo_and_redo() {
+        let mut app = app_with_list();
+        assert!(app.duplicate_list_item("root[0][1]", 2, None));
+        assert_eq!(list_order(&app), vec![0, 1, 2, 1, 1]);
+        assert!(app.undo());
+        assert_eq!(list_order(&app), vec![0, 1, 2]);
+        assert!(app.redo());
+        assert_eq!(list_order(&app), vec![0, 1, 2, 1, 1]);
+        assert_eq!(app.undo_stack.len(), 1);
+    }
+
+    #[test]
+    fn clearing_children_drops_their_view_state() {
+        let mut app = app_with_list();
+        app.expanded_nodes.extend(["root".to_string(), "root[0]".to_string(), "root[0][1]".to_string()]);
+        app.multi_selection = vec!["root[0][1]".to_string(), "root[0][2]".to_string()];
+        app.list_display_keys.insert("root[0][1]".to_string(), 0x1);
+        app.list_display_keys.insert("root[0]".to_string(), 0x1);
+        app.select_path("root[0][2]");
+
+        app.request_clear_children("root[0]", 3);
+        assert!(list_order(&app).is_empty());
+        assert!(app.expanded_nodes.contains("root[0]") && !app.expanded_nodes.contains("root[0][1]"));
+        assert!(app.multi_selection.is_empty());
+        assert_eq!(app.list_display_keys.keys().collect::<Vec<_>>(), ["root[0]"]);
+        assert_eq!(app.selected_node.as_deref(), Some("root[0]"));
+        assert!(app.undo());
+        assert_eq!(list_order(&app), vec![0, 1, 2]);
+    }
+}